@@ -1,14 +1,79 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use std::path::PathBuf;
 
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ErrorFormat {
+    /// Errors with source snippets
+    Human,
+    /// One JSON object per error
+    Json,
+}
+
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 pub struct CliArguments {
-    /// Proto files directory path
-    pub src: PathBuf,
+    /// Proto files, glob patterns or directories to compile.
+    /// Directories are compiled entirely
+    #[arg(value_name = "SRC")]
+    pub src: Vec<PathBuf>,
 
     /// Output directory into which the files
-    /// will be generated
+    /// will be generated, may be omitted with --descriptor-set-out
     #[arg(short, long, value_name = "DIR_PATH")]
-    pub out: PathBuf,
+    pub out: Option<PathBuf>,
+
+    /// Config file, by default protos-ts.toml is searched
+    /// in the current directory and its parents
+    #[arg(short, long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Remove the whole output directory before generation.
+    /// By default only files generated by the previous run are removed
+    #[arg(long)]
+    pub clean: bool,
+
+    /// Do not write anything, fail if the output directory
+    /// is not up to date and print the difference
+    #[arg(long, conflicts_with = "clean")]
+    pub check: bool,
+
+    /// Keep running and recompile whenever
+    /// one of the compiled files changes
+    #[arg(long, conflicts_with = "check")]
+    pub watch: bool,
+
+    /// Serialized FileDescriptorSet to compile instead of .proto files,
+    /// SRC are then names of the files in the set to generate, all by default
+    #[arg(long, value_name = "FILE", conflicts_with = "watch")]
+    pub descriptor_set: Option<PathBuf>,
+
+    /// Also write the resolved schema as a FileDescriptorSet,
+    /// in the JSON mapping if the file name ends with .json
+    #[arg(long, value_name = "FILE")]
+    pub descriptor_set_out: Option<PathBuf>,
+
+    /// Directory in which imports are searched, may be repeated.
+    /// Files found only in these directories are not generated
+    #[arg(short = 'I', long = "proto-path", value_name = "DIR_PATH")]
+    pub proto_path: Vec<PathBuf>,
+
+    /// Generate code for imported files as well,
+    /// --include-imports=false overrides the config file
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true"
+    )]
+    pub include_imports: Option<bool>,
+
+    /// Maximum number of reported errors, 20 by default,
+    /// 0 reports all of them
+    #[arg(long, value_name = "COUNT")]
+    pub max_errors: Option<usize>,
+
+    /// Format of the reported errors
+    #[arg(long, value_enum, default_value_t = ErrorFormat::Human)]
+    pub error_format: ErrorFormat,
 }
@@ -0,0 +1,3 @@
+fn main() {
+    protos_ts::run_protoc_plugin()
+}
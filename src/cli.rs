@@ -0,0 +1,204 @@
+use clap::Parser;
+use prost::Message;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::process;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+use crate::args::{CliArguments, ErrorFormat};
+use crate::config::{DEFAULT_MAX_ERRORS, Options, resolve_options};
+use crate::proto::compiler::ts::ast::Folder;
+use crate::proto::compiler::ts::check_folder::check_folder;
+use crate::proto::compiler::ts::commit_folder::commit_folder;
+use crate::proto::compiler::ts::scope_to_folder::root_scope_to_folder;
+use crate::proto::descriptor::read_descriptor_set;
+use crate::proto::descriptor_set::{descriptor_set_to_json, root_scope_to_descriptor_set};
+use crate::proto::diagnostic::{DiagnosticRenderer, render_json};
+use crate::proto::error::ProtoError;
+use crate::proto::file_cache::ProtoFileCache;
+use crate::proto::file_system::OsFileSystem;
+use crate::proto::folder::read_proto_inputs;
+use crate::proto::package::read_root_scope;
+use crate::proto::proto_scope::root_scope::RootScope;
+use crate::watch::watch;
+
+/// Runs the command line interface with the arguments of the process and exits
+pub(crate) fn run() -> ! {
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer())
+        .with(tracing_subscriber::filter::EnvFilter::from_default_env())
+        .init();
+
+    let args = CliArguments::parse();
+    let cwd = std::env::current_dir().unwrap();
+    let (max_errors, error_format) = (
+        args.max_errors.unwrap_or(DEFAULT_MAX_ERRORS),
+        args.error_format,
+    );
+
+    let options = match resolve_options(args, &cwd) {
+        Err(e) => {
+            report_errors(e, max_errors, error_format);
+            process::exit(2);
+        }
+        Ok(r) => r,
+    };
+    tracing::trace!(src = ?options.src, out = ?options.out, proto_path = ?options.proto_path, "resolved args");
+
+    let mut cache = ProtoFileCache::new();
+    if options.watch {
+        watch(
+            &options.src,
+            &options.proto_path,
+            &cwd,
+            &mut cache,
+            |cache| {
+                compile(&options, &cwd, cache);
+            },
+        );
+    }
+    process::exit(compile(&options, &cwd, &mut cache));
+}
+
+/// Runs the whole pipeline and returns the exit code
+fn compile(options: &Options, cwd: &Path, cache: &mut ProtoFileCache) -> i32 {
+    let Options {
+        src,
+        descriptor_set,
+        out,
+        descriptor_set_out,
+        clean: clean_out,
+        check,
+        proto_path,
+        include_imports,
+        max_errors,
+        error_format,
+        generation,
+        ..
+    } = options;
+    let (max_errors, error_format) = (*max_errors, *error_format);
+
+    let root_scope = match descriptor_set {
+        Some(descriptor_set) => match std::fs::read(descriptor_set) {
+            Err(e) => {
+                report_errors(ProtoError::CannotOpenFile(e), max_errors, error_format);
+                return 2;
+            }
+            Ok(content) => read_descriptor_set(&content, src, *include_imports),
+        },
+        None => {
+            let proto_folders = match read_proto_inputs(src, proto_path, cwd) {
+                Err(e) => {
+                    report_errors(ProtoError::IOError(e), max_errors, error_format);
+                    return 2;
+                }
+                Ok(r) => r,
+            };
+            let root_scope = read_root_scope(
+                &OsFileSystem,
+                &proto_folders,
+                proto_path,
+                *include_imports,
+                cache,
+            );
+            cache.finish_compilation();
+            root_scope
+        }
+    };
+    let root_scope = match root_scope {
+        Err(e) => {
+            report_errors(e, max_errors, error_format);
+            return 3;
+        }
+        Ok(r) => r,
+    };
+
+    if let (Some(path), false) = (descriptor_set_out, *check) {
+        if let Err(e) = write_descriptor_set(&root_scope, path) {
+            report_errors(e, max_errors, error_format);
+            return 4;
+        }
+        println!("{}: descriptor set written", path.display());
+    }
+    let Some(out) = out else {
+        return 0;
+    };
+
+    let root_file_name: String = out.file_name().map(|s| s.to_string_lossy()).unwrap().into();
+
+    let folder: Folder = match root_scope_to_folder(&root_scope, root_file_name, generation) {
+        Err(e) => {
+            report_errors(e, max_errors, error_format);
+            return 4;
+        }
+        Ok(r) => r,
+    };
+
+    if *check {
+        return match check_folder(&folder, out) {
+            Ok(diffs) if diffs.is_empty() => {
+                println!("{}: up to date", out.display());
+                0
+            }
+            Ok(diffs) => {
+                for diff in &diffs {
+                    print!("{}", diff);
+                }
+                eprintln!("{}: {} files are out of date", out.display(), diffs.len());
+                1
+            }
+            Err(e) => {
+                report_errors(e, max_errors, error_format);
+                4
+            }
+        };
+    }
+
+    match commit_folder(&folder, out, *clean_out) {
+        Ok(summary) => {
+            println!("{}: {}", out.display(), summary);
+            0
+        }
+        Err(e) => {
+            report_errors(e, max_errors, error_format);
+            4
+        }
+    }
+}
+
+/// Writes the binary descriptor set, or its JSON mapping if the file has a `.json` extension
+fn write_descriptor_set(root_scope: &RootScope, path: &Path) -> Result<(), ProtoError> {
+    let descriptor_set = root_scope_to_descriptor_set(root_scope);
+    let content = match path.extension() {
+        Some(extension) if extension == "json" => {
+            descriptor_set_to_json(&descriptor_set).into_bytes()
+        }
+        _ => descriptor_set.encode_to_vec(),
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(ProtoError::IOError)?;
+    }
+    std::fs::write(path, content).map_err(ProtoError::IOError)
+}
+
+fn report_errors(error: ProtoError, max_errors: usize, error_format: ErrorFormat) {
+    let errors = error.into_errors();
+    let reported = match max_errors {
+        0 => errors.len(),
+        max => max.min(errors.len()),
+    };
+    if error_format == ErrorFormat::Json {
+        for e in &errors[..reported] {
+            eprintln!("{}", render_json(e));
+        }
+        return;
+    }
+    let mut renderer = DiagnosticRenderer::new(std::io::stderr().is_terminal());
+    for e in &errors[..reported] {
+        eprintln!("{}", renderer.render(e));
+    }
+    if reported < errors.len() {
+        eprintln!("... and {} more errors", errors.len() - reported);
+    }
+}
@@ -0,0 +1,290 @@
+use std::{
+    collections::BTreeMap,
+    ops::Range,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use path_clean::clean;
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::{
+    args::{CliArguments, ErrorFormat},
+    proto::{
+        compiler::ts::codegen_options::{
+            CodegenOptions, GenerationOptions, Int64Mode, PackageOptions,
+        },
+        error::ProtoError,
+        span::Span,
+    },
+};
+
+pub(crate) const CONFIG_FILE_NAME: &str = "protos-ts.toml";
+
+pub(crate) const DEFAULT_MAX_ERRORS: usize = 20;
+
+/// Options of the compilation, command line arguments take precedence over the config file
+pub(crate) struct Options {
+    pub src: Vec<PathBuf>,
+    /// `src` are names of files in the descriptor set if it is set
+    pub descriptor_set: Option<PathBuf>,
+    /// TypeScript is not generated without `out`
+    pub out: Option<PathBuf>,
+    pub descriptor_set_out: Option<PathBuf>,
+    pub clean: bool,
+    pub check: bool,
+    pub watch: bool,
+    pub proto_path: Vec<PathBuf>,
+    pub include_imports: bool,
+    pub max_errors: usize,
+    pub error_format: ErrorFormat,
+    pub generation: GenerationOptions,
+}
+
+/// Contents of `protos-ts.toml`, paths are relative to the directory of the file
+#[derive(Debug, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    src: Vec<PathBuf>,
+    descriptor_set: Option<PathBuf>,
+    out: Option<PathBuf>,
+    descriptor_set_out: Option<PathBuf>,
+    proto_path: Vec<PathBuf>,
+    include_imports: bool,
+    max_errors: Option<usize>,
+    codegen: CodegenConfig,
+    packages: BTreeMap<Spanned<String>, PackageConfig>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct CodegenConfig {
+    services: Option<bool>,
+    int64: Option<Int64Mode>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct PackageConfig {
+    generate: Option<bool>,
+    services: Option<bool>,
+    int64: Option<Int64Mode>,
+}
+
+/// Merges the arguments with the config file passed with `--config`
+/// or found in the current directory or one of its parents
+pub(crate) fn resolve_options(args: CliArguments, cwd: &Path) -> Result<Options, ProtoError> {
+    let config_path = match &args.config {
+        Some(path) => Some(clean(cwd.join(path))),
+        None => find_config(cwd),
+    };
+    let config = match &config_path {
+        Some(path) => read_config(path, cwd)?,
+        None => ConfigFile::default(),
+    };
+    let config_dir = config_path.as_deref().and_then(Path::parent).unwrap_or(cwd);
+    let resolve = |base: &Path, paths: &[PathBuf]| -> Vec<PathBuf> {
+        paths.iter().map(|path| clean(base.join(path))).collect()
+    };
+
+    let descriptor_set = match (&args.descriptor_set, &config.descriptor_set) {
+        (Some(path), _) => Some(clean(cwd.join(path))),
+        (None, Some(path)) => Some(clean(config_dir.join(path))),
+        (None, None) => None,
+    };
+    // Names of files in the descriptor set are not paths on the disk
+    let src = match (args.src.is_empty(), &descriptor_set) {
+        (false, None) => resolve(cwd, &args.src),
+        (true, None) => resolve(config_dir, &config.src),
+        (false, Some(_)) => args.src.clone(),
+        (true, Some(_)) => config.src.clone(),
+    };
+    if descriptor_set.is_some() && args.watch {
+        return Err(ProtoError::new(
+            "--watch cannot be used with a descriptor set",
+        ));
+    }
+    if src.is_empty() && descriptor_set.is_none() {
+        return Err(ProtoError::new(
+            "No proto files to compile, pass them as arguments or set `src` in protos-ts.toml",
+        ));
+    }
+    let descriptor_set_out = match (&args.descriptor_set_out, &config.descriptor_set_out) {
+        (Some(path), _) => Some(clean(cwd.join(path))),
+        (None, Some(path)) => Some(clean(config_dir.join(path))),
+        (None, None) => None,
+    };
+    let out = match (&args.out, &config.out) {
+        (Some(out), _) => Some(clean(cwd.join(out))),
+        (None, Some(out)) => Some(clean(config_dir.join(out))),
+        (None, None) if descriptor_set_out.is_some() => None,
+        (None, None) => {
+            return Err(ProtoError::new(
+                "No output directory, pass it with --out or set `out` in protos-ts.toml",
+            ));
+        }
+    };
+    let proto_path = match args.proto_path.is_empty() {
+        false => resolve(cwd, &args.proto_path),
+        true => resolve(config_dir, &config.proto_path),
+    };
+
+    let default_codegen = CodegenOptions::default();
+    let generation = GenerationOptions {
+        codegen: CodegenOptions {
+            services: config.codegen.services.unwrap_or(default_codegen.services),
+            int64: config.codegen.int64.unwrap_or(default_codegen.int64),
+            ..default_codegen
+        },
+        packages: config
+            .packages
+            .into_iter()
+            .map(|(package, options)| {
+                (
+                    package.into_inner(),
+                    PackageOptions {
+                        generate: options.generate,
+                        services: options.services,
+                        int64: options.int64,
+                    },
+                )
+            })
+            .collect(),
+    };
+
+    Ok(Options {
+        src,
+        descriptor_set,
+        out,
+        descriptor_set_out,
+        clean: args.clean,
+        check: args.check,
+        watch: args.watch,
+        proto_path,
+        include_imports: args.include_imports.unwrap_or(config.include_imports),
+        max_errors: args
+            .max_errors
+            .or(config.max_errors)
+            .unwrap_or(DEFAULT_MAX_ERRORS),
+        error_format: args.error_format,
+        generation,
+    })
+}
+
+fn find_config(cwd: &Path) -> Option<PathBuf> {
+    cwd.ancestors()
+        .map(|dir| dir.join(CONFIG_FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+fn read_config(path: &Path, cwd: &Path) -> Result<ConfigFile, ProtoError> {
+    let content = std::fs::read_to_string(path).map_err(ProtoError::CannotOpenFile)?;
+    // Errors point to the file relative to the current directory, as proto errors do
+    let file_path: Rc<Path> = Rc::from(path.strip_prefix(cwd).unwrap_or(path));
+    let config: ConfigFile = toml::from_str(&content).map_err(|err| match err.span() {
+        Some(range) => ProtoError::SyntaxError {
+            span: span_of(&file_path, &content, range),
+            message: err.message().to_string(),
+        },
+        None => ProtoError::Default(format!(
+            "{}: {}",
+            file_path.to_string_lossy(),
+            err.message()
+        )),
+    })?;
+
+    let mut errors = Vec::new();
+    for package in config.packages.keys() {
+        let is_valid = package.get_ref().split('.').all(|part| {
+            part.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+        if !is_valid {
+            errors.push(ProtoError::SemanticError {
+                span: span_of(&file_path, &content, package.span()),
+                message: format!("Invalid package name \"{}\"", package.get_ref()),
+                notes: Vec::new(),
+            });
+        }
+    }
+    ProtoError::from_errors(errors)?;
+    Ok(config)
+}
+
+/// Converts the byte range of the content into 1-based lines and columns, end is inclusive
+fn span_of(file_path: &Rc<Path>, content: &str, range: Range<usize>) -> Span {
+    let position = |offset: usize| {
+        let before = &content[..offset.min(content.len())];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        (line, before[line_start..].chars().count() + 1)
+    };
+    let (start_line, start_column) = position(range.start);
+    let (end_line, end_column) = position(range.end.max(range.start + 1) - 1);
+    Span {
+        file_path: Rc::clone(file_path),
+        start_line,
+        start_column,
+        end_line,
+        end_column,
+    }
+}
+
+#[cfg(test)]
+mod test_config {
+    use std::path::Path;
+
+    use clap::Parser;
+
+    use super::{read_config, resolve_options};
+    use crate::{args::CliArguments, test_utils::TempDir};
+
+    #[test]
+    fn reports_located_errors() {
+        let temp_dir = TempDir::new("config_reports_located_errors");
+        let dir = temp_dir.path();
+        let path = dir.join("protos-ts.toml");
+
+        std::fs::write(
+            &path,
+            "src = [\"proto\"]\nout = \"gen\"\n\n[packages.\"google.api\"]\ngenerate = false\n",
+        )
+        .unwrap();
+        let config = read_config(&path, &dir).unwrap();
+        assert_eq!(config.src, vec![Path::new("proto")]);
+        assert_eq!(config.packages.len(), 1);
+
+        std::fs::write(&path, "src = [\"proto\"]\noutput = \"gen\"\n").unwrap();
+        let err = read_config(&path, &dir).unwrap_err();
+        let span = err.span().unwrap();
+        assert_eq!((span.start_line, span.start_column), (2, 1));
+
+        std::fs::write(&path, "[packages.\"google..api\"]\n").unwrap();
+        let err = read_config(&path, &dir).unwrap_err();
+        assert_eq!(err.message(), "Invalid package name \"google..api\"");
+        assert_eq!(err.span().unwrap().start_line, 1);
+    }
+
+    #[test]
+    fn prefers_explicit_arguments() {
+        let temp_dir = TempDir::new("config_prefers_explicit_arguments");
+        let dir = temp_dir.path();
+        let path = dir.join("protos-ts.toml");
+        std::fs::write(
+            &path,
+            "src = [\"proto\"]\nout = \"gen\"\ninclude_imports = true\n",
+        )
+        .unwrap();
+        let include_imports = |flags: &[&str]| {
+            let config = path.to_string_lossy();
+            let args = ["protos-ts", "--config", &config];
+            let args = CliArguments::parse_from(args.iter().chain(flags));
+            let options = resolve_options(args, dir).unwrap();
+            options.include_imports
+        };
+        assert!(include_imports(&[]));
+        assert!(include_imports(&["--include-imports"]));
+        assert!(!include_imports(&["--include-imports=false"]));
+    }
+}
@@ -0,0 +1,140 @@
+//! Compiler of `.proto` schemas into TypeScript modules.
+//!
+//! Schemas are parsed into [`schema::ProtoFile`], files and their imports are resolved
+//! into a [`RootScope`], which is compiled into TypeScript files kept in memory.
+//! Files are read through [`FileSystem`], so schemas do not have to be on the disk.
+//!
+//! ```
+//! use std::path::{Path, PathBuf};
+//! use protos_ts::{GenerationOptions, MemoryFileSystem, build_root_scope, compile};
+//!
+//! let mut files = MemoryFileSystem::new();
+//! files.insert(
+//!     "api/user.proto",
+//!     "syntax = \"proto3\";\npackage api;\nmessage User { string name = 1; }\n",
+//! );
+//! let root = build_root_scope(&files, &[PathBuf::from("api/user.proto")], &[], false)?;
+//! let output = compile(&root, &GenerationOptions::default())?;
+//! assert!(output.contains_key(Path::new("api/user/User/types.ts")));
+//! # Ok::<(), protos_ts::ProtoError>(())
+//! ```
+
+mod args;
+mod cli;
+mod config;
+mod plugin;
+mod proto;
+#[cfg(test)]
+mod test_utils;
+mod watch;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use proto::{
+    compiler::ts::{commit_folder::render_folder, scope_to_folder::root_scope_to_folder},
+    descriptor::read_descriptor_set,
+    descriptor_set::{descriptor_set_to_json, root_scope_to_descriptor_set},
+    file_cache::ProtoFileCache,
+    folder::group_files,
+    id_generator::IdGenerator,
+    package::{parse_proto_file, read_root_scope},
+};
+
+pub use proto::{
+    compiler::ts::codegen_options::{CodegenOptions, GenerationOptions, Int64Mode, PackageOptions},
+    error::{Note, ProtoError},
+    file_system::{FileSystem, MemoryFileSystem, OsFileSystem},
+    proto_scope::root_scope::RootScope,
+    span::Span,
+};
+
+/// Declarations of a parsed `.proto` file
+pub mod schema {
+    pub use crate::proto::{
+        options::{Constant, OptionName, OptionNamePart, ProtoOption},
+        package::{
+            Declaration, EnumDeclaration, EnumEntry, ExtendDeclaration, ExtensionsDeclaration,
+            FieldDeclaration, FieldRange, FieldTypeReference, ImportPath, MessageDeclaration,
+            MessageDeclarationEntry, OneOfDeclaration, ProtoFile, ProtoVersion,
+            ReservedDeclaration, RpcDeclaration, ServiceDeclaration,
+        },
+    };
+}
+
+/// Parses the content of a `.proto` file, `path` is used in the reported errors
+pub fn parse_str(path: &Path, content: &str) -> Result<schema::ProtoFile, ProtoError> {
+    parse_proto_file(&mut IdGenerator::new(), path, content)
+}
+
+/// Reads and parses a `.proto` file from the disk
+pub fn parse_file(path: &Path) -> Result<schema::ProtoFile, ProtoError> {
+    let content = std::fs::read_to_string(path).map_err(ProtoError::CannotOpenFile)?;
+    parse_str(path, &content)
+}
+
+/// Loads the files and the files they import, and resolves all type references.
+/// Imports are searched relative to the include path containing the importing file,
+/// or relative to the root of the file system, and then in all `include_paths`.
+/// Code is generated for imported files only if `include_imports` is set
+pub fn build_root_scope(
+    file_system: &dyn FileSystem,
+    files: &[PathBuf],
+    include_paths: &[PathBuf],
+    include_imports: bool,
+) -> Result<RootScope, ProtoError> {
+    let mut folders = Vec::new();
+    group_files(&mut folders, files.to_vec(), include_paths, Path::new(""));
+    read_root_scope(
+        file_system,
+        &folders,
+        include_paths,
+        include_imports,
+        &mut ProtoFileCache::new(),
+    )
+}
+
+/// Resolves files of a serialized `google.protobuf.FileDescriptorSet`, as written by
+/// `protoc --descriptor_set_out` or `buf build -o`, without parsing `.proto` files.
+/// `files` are names of the files in the set to generate, all files are generated if it is empty
+pub fn build_root_scope_from_descriptor_set(
+    descriptor_set: &[u8],
+    files: &[PathBuf],
+    include_imports: bool,
+) -> Result<RootScope, ProtoError> {
+    read_descriptor_set(descriptor_set, files, include_imports)
+}
+
+/// Serializes the schema into a binary `google.protobuf.FileDescriptorSet`
+/// of all its files, with the files they import before them
+pub fn encode_descriptor_set(root: &RootScope) -> Vec<u8> {
+    prost::Message::encode_to_vec(&root_scope_to_descriptor_set(root))
+}
+
+/// Renders the descriptor set of the schema in the proto3 JSON mapping
+pub fn descriptor_set_json(root: &RootScope) -> String {
+    descriptor_set_to_json(&root_scope_to_descriptor_set(root))
+}
+
+/// Compiles the schema into TypeScript files by their paths relative to the output directory
+pub fn compile(
+    root: &RootScope,
+    options: &GenerationOptions,
+) -> Result<BTreeMap<PathBuf, String>, ProtoError> {
+    let folder = root_scope_to_folder(root, String::new(), options)?;
+    Ok(render_folder(&folder)?.into_iter().collect())
+}
+
+/// Runs the command line interface with the arguments of the process and exits
+pub fn run_cli() -> ! {
+    cli::run()
+}
+
+/// Runs as a protoc plugin, reading the request from stdin and writing the response to stdout
+pub fn run_protoc_plugin() -> ! {
+    plugin::run()
+}
@@ -0,0 +1,219 @@
+use std::io::{Read, Write};
+use std::process;
+
+use prost::Message;
+use prost_types::compiler::{
+    CodeGeneratorRequest, CodeGeneratorResponse,
+    code_generator_response::{Feature, File},
+};
+
+use crate::proto::compiler::ts::codegen_options::{GenerationOptions, Int64Mode};
+use crate::proto::compiler::ts::commit_folder::render_folder;
+use crate::proto::compiler::ts::scope_to_folder::root_scope_to_folder;
+use crate::proto::descriptor::{REQUEST_FILES, read_descriptor_root_scope, restore_field_options};
+use crate::proto::error::ProtoError;
+
+/// Runs as a protoc plugin: reads `CodeGeneratorRequest` from stdin,
+/// writes `CodeGeneratorResponse` to stdout and exits
+pub(crate) fn run() -> ! {
+    let mut input = Vec::new();
+    if let Err(e) = std::io::stdin().read_to_end(&mut input) {
+        eprintln!("Cannot read CodeGeneratorRequest: {}", e);
+        process::exit(1);
+    }
+    let mut request = match CodeGeneratorRequest::decode(input.as_slice()) {
+        Err(e) => {
+            eprintln!("Cannot decode CodeGeneratorRequest: {}", e);
+            process::exit(1);
+        }
+        Ok(r) => r,
+    };
+    restore_field_options(&mut request.proto_file, &input, REQUEST_FILES);
+    let response = generate(&request).encode_to_vec();
+    if let Err(e) = std::io::stdout().write_all(&response) {
+        eprintln!("Cannot write CodeGeneratorResponse: {}", e);
+        process::exit(1);
+    }
+    process::exit(0);
+}
+
+/// Compiles the files protoc asks for, errors are reported in the response
+/// so that protoc shows them
+pub(crate) fn generate(request: &CodeGeneratorRequest) -> CodeGeneratorResponse {
+    let mut response = CodeGeneratorResponse {
+        supported_features: Some(Feature::Proto3Optional as u64),
+        ..Default::default()
+    };
+    match generate_files(request) {
+        Ok(files) => response.file = files,
+        Err(e) => {
+            let errors = e
+                .into_errors()
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>();
+            response.error = Some(errors.join("\n"));
+        }
+    }
+    response
+}
+
+fn generate_files(request: &CodeGeneratorRequest) -> Result<Vec<File>, ProtoError> {
+    let options = parse_parameter(request.parameter())?;
+    let root = read_descriptor_root_scope(&request.proto_file, |file| {
+        options.include_imports || request.file_to_generate.iter().any(|f| f == file.name())
+    })?;
+    let folder = root_scope_to_folder(&root, String::new(), &options.generation)?;
+    let files = render_folder(&folder)?
+        .into_iter()
+        .map(|(path, content)| File {
+            // Names of generated files always use `/`
+            name: Some(
+                path.components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/"),
+            ),
+            content: Some(content),
+            ..Default::default()
+        })
+        .collect();
+    Ok(files)
+}
+
+#[derive(Debug, Default)]
+struct PluginOptions {
+    generation: GenerationOptions,
+    /// Generate code for imported files too, not only for files given to protoc
+    include_imports: bool,
+}
+
+/// Parses comma separated `--ts_opt` values: `include_imports`, `services`, `generate`
+/// and `int64`. Options other than `include_imports` prefixed with a package name are set
+/// for the package and its subpackages, e.g. `google.api.generate=false`.
+/// Value `true` of boolean options can be omitted
+fn parse_parameter(parameter: &str) -> Result<PluginOptions, ProtoError> {
+    let mut res = PluginOptions::default();
+    for entry in parameter
+        .split(',')
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+    {
+        let (key, value) = entry.split_once('=').unwrap_or((entry, "true"));
+        match key.rsplit_once('.') {
+            None if key == "include_imports" => res.include_imports = parse_bool(key, value)?,
+            None if key == "services" => res.generation.codegen.services = parse_bool(key, value)?,
+            None if key == "generate" => res.generation.codegen.generate = parse_bool(key, value)?,
+            None if key == "int64" => res.generation.codegen.int64 = parse_int64(key, value)?,
+            Some((package, "services")) => {
+                let package_options = res.generation.packages.entry(package.to_string());
+                package_options.or_default().services = Some(parse_bool(key, value)?);
+            }
+            Some((package, "generate")) => {
+                let package_options = res.generation.packages.entry(package.to_string());
+                package_options.or_default().generate = Some(parse_bool(key, value)?);
+            }
+            Some((package, "int64")) => {
+                let package_options = res.generation.packages.entry(package.to_string());
+                package_options.or_default().int64 = Some(parse_int64(key, value)?);
+            }
+            _ => {
+                return Err(ProtoError::Default(format!("Unknown option: {}", key)));
+            }
+        }
+    }
+    Ok(res)
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, ProtoError> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(ProtoError::Default(format!(
+            "Value of option {} must be true or false, got {}",
+            key, value
+        ))),
+    }
+}
+
+fn parse_int64(key: &str, value: &str) -> Result<Int64Mode, ProtoError> {
+    Int64Mode::from_name(value).ok_or_else(|| {
+        ProtoError::Default(format!(
+            "Value of option {} must be long, bigint, string or number, got {}",
+            key, value
+        ))
+    })
+}
+
+#[cfg(test)]
+mod test_plugin {
+    use prost_types::{
+        DescriptorProto, FieldDescriptorProto, FileDescriptorProto, MethodDescriptorProto,
+        ServiceDescriptorProto, compiler::CodeGeneratorRequest, field_descriptor_proto::Type,
+    };
+
+    use super::{generate, parse_parameter};
+
+    fn request(parameter: &str) -> CodeGeneratorRequest {
+        let string_field = FieldDescriptorProto {
+            name: Some("name".into()),
+            number: Some(1),
+            r#type: Some(Type::String as i32),
+            ..Default::default()
+        };
+        let file = FileDescriptorProto {
+            name: Some("app/user.proto".into()),
+            package: Some("app".into()),
+            syntax: Some("proto3".into()),
+            message_type: vec![DescriptorProto {
+                name: Some("User".into()),
+                field: vec![string_field],
+                ..Default::default()
+            }],
+            service: vec![ServiceDescriptorProto {
+                name: Some("Users".into()),
+                method: vec![MethodDescriptorProto {
+                    name: Some("GetUser".into()),
+                    input_type: Some(".app.User".into()),
+                    output_type: Some(".app.User".into()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        CodeGeneratorRequest {
+            file_to_generate: vec!["app/user.proto".into()],
+            parameter: Some(parameter.into()),
+            proto_file: vec![file],
+            ..Default::default()
+        }
+    }
+
+    fn generated_names(parameter: &str) -> Vec<String> {
+        let response = generate(&request(parameter));
+        assert_eq!(response.error, None);
+        response.file.into_iter().filter_map(|f| f.name).collect()
+    }
+
+    #[test]
+    fn generates_files_of_request() {
+        let names = generated_names("");
+        assert!(names.contains(&"app/user/User/types.ts".to_string()));
+        assert!(names.contains(&"app/user/Users/client.ts".to_string()));
+        let names = generated_names("app.services=false");
+        assert!(names.contains(&"app/user/User/types.ts".to_string()));
+        assert!(!names.contains(&"app/user/Users/client.ts".to_string()));
+    }
+
+    #[test]
+    fn rejects_invalid_parameter() {
+        assert!(parse_parameter("include_imports,services=false").is_ok());
+        assert!(parse_parameter("services=no").is_err());
+        assert!(parse_parameter("int64=bigint,google.protobuf.int64=long").is_ok());
+        assert!(parse_parameter("int64").is_err());
+        assert!(parse_parameter("app.unknown=true").is_err());
+        let response = generate(&request("unknown"));
+        assert_eq!(response.error.as_deref(), Some("Unknown option: unknown"));
+    }
+}
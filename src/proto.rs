@@ -2,8 +2,15 @@ pub(super) mod folder;
 pub(super) mod package;
 pub(super) mod error;
 pub(super) mod compiler;
+pub(super) mod descriptor;
+pub(super) mod descriptor_set;
+pub(super) mod diagnostic;
+pub(super) mod file_cache;
+pub(super) mod file_system;
 mod syntax;
 mod lexems;
-mod id_generator;
-mod proto_scope;
+pub(super) mod id_generator;
+pub(super) mod proto_scope;
+pub(super) mod options;
 mod protopath;
+pub(super) mod span;
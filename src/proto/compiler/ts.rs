@@ -1,4 +1,7 @@
 pub(crate) mod ast;
+pub(crate) mod check_folder;
+mod client_compiler;
+pub(crate) mod codegen_options;
 pub(crate) mod commit_folder;
 mod constants;
 mod decode_compiler;
@@ -12,14 +15,20 @@ mod encode_message_expr;
 mod ensure_import;
 mod enum_compiler;
 mod file_name_to_folder_name;
+mod from_json_compiler;
 mod file_to_folder;
+mod handler_compiler;
 mod get_relative_import;
 mod has_property;
+mod int64_mode;
 mod is_reserved;
 mod is_safe_id;
 mod message_name_to_encode_type_name;
+pub(crate) mod rpc_name_to_method_name;
 pub(crate) mod scope_to_folder;
 mod render_file;
+mod service_imports;
 mod to_js_string;
+mod to_json_compiler;
 mod ts_path;
 mod types_compiler;
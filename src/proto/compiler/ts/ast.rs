@@ -143,6 +143,20 @@ impl ImportDeclaration {
 #[derive(Debug)]
 pub(crate) enum Modifier {
     Export,
+    Private,
+    Readonly,
+    Async,
+}
+
+impl From<&Modifier> for &str {
+    fn from(modifier: &Modifier) -> Self {
+        match modifier {
+            Modifier::Export => "export",
+            Modifier::Private => "private",
+            Modifier::Readonly => "readonly",
+            Modifier::Async => "async",
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -235,6 +249,7 @@ impl From<Vec<Type>> for UnionType {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum Type {
     Number,
+    BigInt,
     Null,
     Never,
     Boolean,
@@ -243,6 +258,11 @@ pub(crate) enum Type {
     ArrayType(Box<Type>),
     Record(Box<Type>, Box<Type>),
     TypeReference(Vec<Rc<Identifier>>),
+    /// Generic type with arguments: `Promise<T>`
+    Generic(Rc<Identifier>, Vec<Type>),
+    /// Function type: `(a: A, b: B) => R`
+    Function(Vec<(Rc<Identifier>, Type)>, Box<Type>),
+    Void,
     Any,
 }
 
@@ -271,12 +291,16 @@ impl Type {
             Type::ArrayType(_) => true,
             Type::UnionType(_) => true,
             Type::Number => false,
+            Type::BigInt => false,
             Type::Never => false,
             Type::Null => false,
             Type::Boolean => false,
             Type::String => false,
             Type::TypeReference(_) => false,
             Type::Record(_, _) => false,
+            Type::Generic(_, _) => false,
+            Type::Function(_, _) => true,
+            Type::Void => false,
             Type::Any => false,
         }
     }
@@ -285,6 +309,10 @@ impl Type {
         return Type::TypeReference(vec![id]);
     }
 
+    pub fn generic(name: &str, arguments: Vec<Type>) -> Self {
+        Type::Generic(Rc::new(name.into()), arguments)
+    }
+
     pub fn or(&self, another: &Self) -> Self {
         let mut res = UnionType::new();
         res.push(self.clone());
@@ -344,9 +372,27 @@ impl PropertySignature {
     }
 }
 
+#[derive(Debug)]
+pub(crate) struct MethodSignature {
+    pub name: Identifier,
+    pub parameters: Vec<Parameter>,
+    pub return_type: Type,
+}
+
+impl MethodSignature {
+    pub fn new(name: &str, parameters: Vec<Parameter>, return_type: Type) -> Self {
+        Self {
+            name: name.into(),
+            parameters,
+            return_type,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) enum InterfaceMember {
     PropertySignature(PropertySignature),
+    MethodSignature(MethodSignature),
 }
 
 impl From<PropertySignature> for InterfaceMember {
@@ -355,6 +401,12 @@ impl From<PropertySignature> for InterfaceMember {
     }
 }
 
+impl From<MethodSignature> for InterfaceMember {
+    fn from(method_signature: MethodSignature) -> Self {
+        Self::MethodSignature(method_signature)
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct InterfaceDeclaration {
     pub modifiers: Vec<Modifier>,
@@ -381,6 +433,7 @@ pub(crate) struct Parameter {
     pub name: Rc<Identifier>,
     pub parameter_type: Rc<Type>,
     pub optional: bool,
+    pub initializer: Option<Rc<Expression>>,
 }
 
 impl Parameter {
@@ -390,6 +443,7 @@ impl Parameter {
             name: id,
             parameter_type: Rc::new(_type),
             optional: false,
+            initializer: None,
         }
     }
     pub fn new_optional(name: &str, _type: Type) -> Self {
@@ -398,11 +452,19 @@ impl Parameter {
             ..Self::new(name, _type)
         }
     }
+    pub fn new_with_default(name: &str, _type: Type, initializer: Expression) -> Self {
+        Self {
+            initializer: Some(Rc::new(initializer)),
+            ..Self::new(name, _type)
+        }
+    }
 }
 
 #[derive(Debug)]
 pub(crate) struct FunctionDeclaration {
     pub modifiers: Vec<Modifier>,
+    /// Generator functions are rendered as `function*`
+    pub generator: bool,
     pub name: Identifier,
     pub parameters: Vec<Parameter>,
     pub return_type: Type,
@@ -413,6 +475,7 @@ impl FunctionDeclaration {
     pub fn new(name: &str) -> Self {
         Self {
             modifiers: Vec::new(),
+            generator: false,
             name: name.into(),
             parameters: Vec::new(),
             return_type: Type::Never,
@@ -424,6 +487,11 @@ impl FunctionDeclaration {
         res.modifiers.push(Modifier::Export);
         res
     }
+    pub fn new_async(name: &str) -> Self {
+        let mut res = FunctionDeclaration::new(name);
+        res.modifiers.push(Modifier::Async);
+        res
+    }
     pub fn add_param(&mut self, param: Parameter) {
         self.parameters.push(param);
     }
@@ -438,16 +506,137 @@ impl StatementList for FunctionDeclaration {
     }
 }
 
+#[derive(Debug)]
+pub(crate) struct PropertyDeclaration {
+    pub modifiers: Vec<Modifier>,
+    pub name: Identifier,
+    pub property_type: Type,
+}
+
+#[derive(Debug)]
+pub(crate) struct ConstructorDeclaration {
+    pub parameters: Vec<Parameter>,
+    pub body: Block,
+}
+
+impl ConstructorDeclaration {
+    pub fn new() -> Self {
+        Self {
+            parameters: Vec::new(),
+            body: Block::new(),
+        }
+    }
+    pub fn add_param(&mut self, param: Parameter) {
+        self.parameters.push(param);
+    }
+}
+
+impl StatementList for ConstructorDeclaration {
+    fn push_statement(&mut self, statement: Statement) {
+        self.body.statements.push(statement.into());
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct MethodDeclaration {
+    pub modifiers: Vec<Modifier>,
+    /// Generator methods are rendered with `*` before the name
+    pub generator: bool,
+    pub name: Identifier,
+    pub parameters: Vec<Parameter>,
+    pub return_type: Type,
+    pub body: Block,
+}
+
+impl MethodDeclaration {
+    pub fn new(name: &str) -> Self {
+        Self {
+            modifiers: Vec::new(),
+            generator: false,
+            name: name.into(),
+            parameters: Vec::new(),
+            return_type: Type::Void,
+            body: Block::new(),
+        }
+    }
+    pub fn new_async(name: &str) -> Self {
+        let mut res = MethodDeclaration::new(name);
+        res.modifiers.push(Modifier::Async);
+        res
+    }
+    pub fn add_param(&mut self, param: Parameter) {
+        self.parameters.push(param);
+    }
+    pub fn returns(&mut self, return_type: Type) {
+        self.return_type = return_type;
+    }
+}
+
+impl StatementList for MethodDeclaration {
+    fn push_statement(&mut self, statement: Statement) {
+        self.body.statements.push(statement.into());
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum ClassMember {
+    Property(PropertyDeclaration),
+    Constructor(ConstructorDeclaration),
+    Method(MethodDeclaration),
+}
+
+impl From<PropertyDeclaration> for ClassMember {
+    fn from(property: PropertyDeclaration) -> Self {
+        Self::Property(property)
+    }
+}
+impl From<ConstructorDeclaration> for ClassMember {
+    fn from(constructor: ConstructorDeclaration) -> Self {
+        Self::Constructor(constructor)
+    }
+}
+impl From<MethodDeclaration> for ClassMember {
+    fn from(method: MethodDeclaration) -> Self {
+        Self::Method(method)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct ClassDeclaration {
+    pub modifiers: Vec<Modifier>,
+    pub name: Identifier,
+    pub members: Vec<ClassMember>,
+}
+
+impl ClassDeclaration {
+    pub fn new_exported(name: &str) -> Self {
+        Self {
+            modifiers: vec![Modifier::Export],
+            name: name.into(),
+            members: Vec::new(),
+        }
+    }
+    pub fn add_member(&mut self, member: ClassMember) {
+        self.members.push(member);
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum BinaryOperator {
     LogicalOr,
     LogicalAnd,
     BinaryAnd,
+    BinaryOr,
     WeakNotEqual,
     LessThan,
     InstanceOf,
     StrictEqual,
+    StrictNotEqual,
     Plus,
+    Minus,
+    Multiply,
+    Divide,
+    Remainder,
     UnsignedRightShift,
     Assign,
 }
@@ -472,9 +661,15 @@ impl From<&BinaryOperator> for &str {
             BinaryOperator::LessThan => "<",
             BinaryOperator::InstanceOf => "instanceof",
             BinaryOperator::Plus => "+",
+            BinaryOperator::Minus => "-",
             BinaryOperator::StrictEqual => "===",
+            BinaryOperator::StrictNotEqual => "!==",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Remainder => "%",
             BinaryOperator::UnsignedRightShift => ">>>",
             BinaryOperator::BinaryAnd => "&",
+            BinaryOperator::BinaryOr => "|",
             BinaryOperator::Assign => "=",
         }
     }
@@ -545,6 +740,9 @@ impl PropertyAccessExpression {
             Expression::PrefixUnaryExpression(_) => true,
             Expression::ConditionalExpression(_) => true,
             Expression::Typeof(_) => true,
+            Expression::Await(_) => true,
+            Expression::Yield(_) => true,
+            Expression::RegularExpressionLiteral(_) => false,
         }
     }
 }
@@ -568,7 +766,6 @@ impl NewExpression {
             arguments: Vec::new(),
         }
     }
-    #[allow(dead_code)]
     pub fn add_argument(&mut self, argument: Rc<Expression>) -> &mut Self {
         self.arguments.push(argument);
         self
@@ -585,6 +782,7 @@ pub(crate) struct ElementAccessExpression {
 pub(crate) enum UnaryOperator {
     Increment,
     Not,
+    Minus,
 }
 
 impl From<&UnaryOperator> for &str {
@@ -592,6 +790,7 @@ impl From<&UnaryOperator> for &str {
         match unary_operator {
             UnaryOperator::Increment => "++",
             UnaryOperator::Not => "!",
+            UnaryOperator::Minus => "-",
         }
     }
 }
@@ -652,6 +851,10 @@ pub(crate) enum Expression {
     PrefixUnaryExpression(PrefixUnaryExpression),
     ConditionalExpression(ConditionalExpression),
     Typeof(Rc<Expression>),
+    Await(Rc<Expression>),
+    Yield(Option<Rc<Expression>>),
+    /// Rendered as is, e.g. `/^\d+$/`
+    RegularExpressionLiteral(Rc<str>),
 }
 
 impl Expression {
@@ -696,9 +899,20 @@ impl Expression {
         })
     }
 
+    pub fn negate(self) -> Expression {
+        Expression::PrefixUnaryExpression(PrefixUnaryExpression {
+            operator: UnaryOperator::Minus,
+            operand: self.into(),
+        })
+    }
+
     pub fn into_parentheses(self) -> Expression {
         Expression::ParenthesizedExpression(self.into())
     }
+
+    pub fn into_await(self) -> Expression {
+        Expression::Await(self.into())
+    }
 }
 
 impl From<ConditionalExpression> for Expression {
@@ -1029,6 +1243,7 @@ impl StatementList for ForStatement {
             Statement::EnumDeclaration(_) => unreachable!(),
             Statement::InterfaceDeclaration(_) => unreachable!(),
             Statement::FunctionDeclaration(_) => unreachable!(),
+            Statement::ClassDeclaration(_) => unreachable!(),
             stmt => {
                 let mut block = Block::new();
                 block.push_statement(stmt);
@@ -1039,6 +1254,38 @@ impl StatementList for ForStatement {
     }
 }
 
+/// `for (const name of expression)` or `for await (const name of expression)`
+#[derive(Debug)]
+pub(crate) struct ForOfStatement {
+    pub is_await: bool,
+    pub name: Rc<Identifier>,
+    pub expression: Rc<Expression>,
+    pub statement: Box<Block>,
+}
+
+impl ForOfStatement {
+    pub fn new(name: Rc<Identifier>, expression: Rc<Expression>) -> Self {
+        Self {
+            is_await: false,
+            name,
+            expression,
+            statement: Box::new(Block::new()),
+        }
+    }
+    pub fn for_await(name: Rc<Identifier>, expression: Rc<Expression>) -> Self {
+        Self {
+            is_await: true,
+            ..Self::new(name, expression)
+        }
+    }
+}
+
+impl StatementList for ForOfStatement {
+    fn push_statement(&mut self, stmt: Statement) {
+        self.statement.push_statement(stmt);
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct WhileStatement {
     pub condition: Rc<Expression>,
@@ -1120,15 +1367,18 @@ pub(crate) enum Statement {
     EnumDeclaration(Box<EnumDeclaration>),
     InterfaceDeclaration(Box<InterfaceDeclaration>),
     FunctionDeclaration(Box<FunctionDeclaration>),
+    ClassDeclaration(Box<ClassDeclaration>),
     ReturnStatement(Option<Expression>),
     VariableStatement(Rc<VariableDeclarationList>),
     IfStatement(IfStatement),
     Block(Block),
     Expression(Rc<Expression>),
     For(Rc<ForStatement>),
+    ForOf(Rc<ForOfStatement>),
     While(Rc<WhileStatement>),
     Break,
     Switch(Box<SwitchStatement>),
+    Throw(Expression),
 }
 
 impl Default for Statement {
@@ -1143,6 +1393,12 @@ impl From<SwitchStatement> for Statement {
     }
 }
 
+impl From<ForOfStatement> for Statement {
+    fn from(for_of: ForOfStatement) -> Self {
+        Self::ForOf(Rc::new(for_of))
+    }
+}
+
 impl From<WhileStatement> for Statement {
     fn from(wh: WhileStatement) -> Self {
         Self::While(Rc::new(wh))
@@ -1211,6 +1467,11 @@ impl From<FunctionDeclaration> for Statement {
         Statement::FunctionDeclaration(Box::new(interface_declaration))
     }
 }
+impl From<ClassDeclaration> for Statement {
+    fn from(class_declaration: ClassDeclaration) -> Self {
+        Statement::ClassDeclaration(Box::new(class_declaration))
+    }
+}
 
 #[derive(Debug)]
 pub(crate) struct File {
@@ -0,0 +1,259 @@
+use std::{
+    collections::HashSet,
+    fmt::Write,
+    io,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    super::super::error::ProtoError,
+    commit_folder::{read_manifest, render_folder},
+};
+
+/// Number of unchanged lines shown around each change
+const CONTEXT_LINES: usize = 3;
+
+/// Above this number of compared line pairs changed parts are shown as replaced entirely
+const MAX_DIFF_CELLS: usize = 16_000_000;
+
+/// Compares the folder with the files in `destination` without writing anything.
+/// Returns unified diffs of the files that differ, including the files that
+/// would be removed, empty if the destination is up to date
+pub(crate) fn check_folder(
+    folder: &super::ast::Folder,
+    destination: &Path,
+) -> Result<Vec<String>, ProtoError> {
+    let files = render_folder(folder)?;
+    let previous_files = read_manifest(destination).map_err(ProtoError::IOError)?;
+    let mut diffs = Vec::new();
+
+    for (file_path, content) in &files {
+        let current = read_existing(&destination.join(file_path)).map_err(ProtoError::IOError)?;
+        let diff = match current {
+            Some(current) if &current == content => continue,
+            Some(current) => unified_diff(file_path, Some(&current), Some(content)),
+            None => unified_diff(file_path, None, Some(content)),
+        };
+        diffs.push(diff);
+    }
+
+    let generated: HashSet<&PathBuf> = files.iter().map(|(path, _)| path).collect();
+    for stale_file in previous_files.iter().filter(|f| !generated.contains(f)) {
+        if let Some(current) =
+            read_existing(&destination.join(stale_file)).map_err(ProtoError::IOError)?
+        {
+            diffs.push(unified_diff(stale_file, Some(&current), None));
+        }
+    }
+    Ok(diffs)
+}
+
+fn read_existing(path: &Path) -> io::Result<Option<String>> {
+    match std::fs::read(path) {
+        Ok(content) => Ok(Some(String::from_utf8_lossy(&content).into_owned())),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Unified diff of the file, `None` content means that the file does not exist
+fn unified_diff(path: &Path, current: Option<&str>, expected: Option<&str>) -> String {
+    let path = path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    let mut res = String::new();
+    match current {
+        Some(_) => writeln!(res, "--- a/{}", path).unwrap(),
+        None => writeln!(res, "--- /dev/null").unwrap(),
+    }
+    match expected {
+        Some(_) => writeln!(res, "+++ b/{}", path).unwrap(),
+        None => writeln!(res, "+++ /dev/null").unwrap(),
+    }
+
+    let current_lines: Vec<&str> = current.map(|c| c.lines().collect()).unwrap_or_default();
+    let expected_lines: Vec<&str> = expected.map(|c| c.lines().collect()).unwrap_or_default();
+    let lines = diff_lines(&current_lines, &expected_lines);
+    for hunk in hunks(&lines) {
+        write_hunk(
+            &mut res,
+            &lines[hunk.start..hunk.end],
+            hunk.current_line,
+            hunk.expected_line,
+        );
+    }
+    res
+}
+
+/// Line diff based on the longest common subsequence of the lines
+/// between the common prefix and suffix
+fn diff_lines<'a>(current: &[&'a str], expected: &[&'a str]) -> Vec<DiffLine<'a>> {
+    let prefix = current
+        .iter()
+        .zip(expected)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = current[prefix..]
+        .iter()
+        .rev()
+        .zip(expected[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let current_middle = &current[prefix..current.len() - suffix];
+    let expected_middle = &expected[prefix..expected.len() - suffix];
+
+    let mut res = current[..prefix]
+        .iter()
+        .map(|l| DiffLine::Same(l))
+        .collect::<Vec<_>>();
+    if current_middle.len() * expected_middle.len() > MAX_DIFF_CELLS {
+        res.extend(current_middle.iter().map(|l| DiffLine::Removed(l)));
+        res.extend(expected_middle.iter().map(|l| DiffLine::Added(l)));
+    } else {
+        res.extend(lcs_diff(current_middle, expected_middle));
+    }
+    res.extend(
+        current[current.len() - suffix..]
+            .iter()
+            .map(|l| DiffLine::Same(l)),
+    );
+    res
+}
+
+fn lcs_diff<'a>(current: &[&'a str], expected: &[&'a str]) -> Vec<DiffLine<'a>> {
+    let width = expected.len() + 1;
+    // lengths[i * width + j] is the length of the common subsequence of current[i..] and expected[j..]
+    let mut lengths = vec![0u32; (current.len() + 1) * width];
+    for i in (0..current.len()).rev() {
+        for j in (0..expected.len()).rev() {
+            lengths[i * width + j] = if current[i] == expected[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut res = Vec::with_capacity(current.len() + expected.len());
+    let (mut i, mut j) = (0, 0);
+    while i < current.len() && j < expected.len() {
+        if current[i] == expected[j] {
+            res.push(DiffLine::Same(current[i]));
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            res.push(DiffLine::Removed(current[i]));
+            i += 1;
+        } else {
+            res.push(DiffLine::Added(expected[j]));
+            j += 1;
+        }
+    }
+    res.extend(current[i..].iter().map(|l| DiffLine::Removed(l)));
+    res.extend(expected[j..].iter().map(|l| DiffLine::Added(l)));
+    res
+}
+
+/// Range of the diff lines shown together
+struct Hunk {
+    start: usize,
+    end: usize,
+    /// 1-based line of the current file at the start of the hunk
+    current_line: usize,
+    /// 1-based line of the expected file at the start of the hunk
+    expected_line: usize,
+}
+
+fn hunks(lines: &[DiffLine]) -> Vec<Hunk> {
+    let mut res: Vec<Hunk> = Vec::new();
+    let (mut current_line, mut expected_line) = (1, 1);
+    for (i, line) in lines.iter().enumerate() {
+        if !matches!(line, DiffLine::Same(_)) {
+            let start = i.saturating_sub(CONTEXT_LINES);
+            let end = (i + 1 + CONTEXT_LINES).min(lines.len());
+            match res.last_mut() {
+                Some(hunk) if hunk.end >= start => hunk.end = end,
+                _ => res.push(Hunk {
+                    start,
+                    end,
+                    current_line: current_line - (i - start),
+                    expected_line: expected_line - (i - start),
+                }),
+            }
+        }
+        match line {
+            DiffLine::Same(_) => {
+                current_line += 1;
+                expected_line += 1;
+            }
+            DiffLine::Removed(_) => current_line += 1,
+            DiffLine::Added(_) => expected_line += 1,
+        }
+    }
+    res
+}
+
+fn write_hunk(res: &mut String, lines: &[DiffLine], current_start: usize, expected_start: usize) {
+    let current_count = lines
+        .iter()
+        .filter(|l| !matches!(l, DiffLine::Added(_)))
+        .count();
+    let expected_count = lines
+        .iter()
+        .filter(|l| !matches!(l, DiffLine::Removed(_)))
+        .count();
+    // Empty side of the hunk is addressed by the line preceding it
+    let range = |start: usize, count: usize| match count {
+        0 => format!("{},0", start - 1),
+        count => format!("{},{}", start, count),
+    };
+    writeln!(
+        res,
+        "@@ -{} +{} @@",
+        range(current_start, current_count),
+        range(expected_start, expected_count)
+    )
+    .unwrap();
+    for line in lines {
+        match line {
+            DiffLine::Same(l) => writeln!(res, " {}", l),
+            DiffLine::Removed(l) => writeln!(res, "-{}", l),
+            DiffLine::Added(l) => writeln!(res, "+{}", l),
+        }
+        .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod test_unified_diff {
+    use std::path::Path;
+
+    use super::unified_diff;
+
+    #[test]
+    fn shows_changes_with_context() {
+        let current = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let expected = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\n";
+
+        assert_eq!(
+            unified_diff(Path::new("pkg/A/types.ts"), Some(current), Some(expected)),
+            "--- a/pkg/A/types.ts\n\
+             +++ b/pkg/A/types.ts\n\
+             @@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n\
+             @@ -8,3 +8,4 @@\n h\n i\n j\n+k\n"
+        );
+        assert_eq!(
+            unified_diff(Path::new("a.ts"), None, Some("x\n")),
+            "--- /dev/null\n+++ b/a.ts\n@@ -0,0 +1,1 @@\n+x\n"
+        );
+    }
+}
@@ -0,0 +1,302 @@
+use std::rc::Rc;
+
+use crate::proto::{
+    error::ProtoError,
+    package::Rpc,
+    proto_scope::{ProtoScope, root_scope::RootScope},
+};
+
+use super::{
+    ast::{self, Folder, MethodCall, Prop, StatementList, Type},
+    message_name_to_encode_type_name::message_name_to_encode_type_name,
+    rpc_name_to_method_name::rpc_name_to_method_name,
+    service_imports::{
+        import_decode_func, import_encode_func, import_message_type, is_empty_message,
+    },
+    ts_path::TsPath,
+};
+
+const RPC_INTERFACE_NAME: &str = "Rpc";
+const RPC_PROPERTY_NAME: &str = "rpc";
+const REQUEST_METHOD_NAME: &str = "request";
+const SERVER_STREAM_METHOD_NAME: &str = "serverStream";
+const CLIENT_STREAM_METHOD_NAME: &str = "clientStream";
+const BIDI_STREAM_METHOD_NAME: &str = "bidiStream";
+
+pub(super) fn compile_client(
+    root: &RootScope,
+    service_folder: &mut Folder,
+    service_scope: &ProtoScope,
+) -> Result<(), ProtoError> {
+    let service = match service_scope {
+        ProtoScope::Service(s) => s,
+        _ => unreachable!(),
+    };
+    let mut file = ast::File::new("client".into());
+    let service_path = root.get_declaration_path(service.id).unwrap();
+    let service_full_name = service_path.full_name();
+    let file_path = {
+        let mut res = TsPath::from(service_path);
+        res.push_file("client");
+        res
+    };
+
+    file.push_statement(create_rpc_interface(&service.rpcs).into());
+
+    let rpc_type = Type::from_id(RPC_INTERFACE_NAME);
+    let rpc_param_id: Rc<ast::Identifier> = Rc::new(RPC_PROPERTY_NAME.into());
+    let this_rpc_expr: Rc<ast::Expression> =
+        Rc::new(Rc::new(ast::Expression::from("this")).prop(RPC_PROPERTY_NAME));
+
+    let mut client_class = ast::ClassDeclaration::new_exported(&format!("{}Client", service.name));
+    client_class.add_member(
+        ast::PropertyDeclaration {
+            modifiers: vec![ast::Modifier::Private, ast::Modifier::Readonly],
+            name: RPC_PROPERTY_NAME.into(),
+            property_type: rpc_type.clone(),
+        }
+        .into(),
+    );
+    let mut constructor = ast::ConstructorDeclaration::new();
+    constructor.add_param(ast::Parameter::new(&rpc_param_id, rpc_type));
+    constructor.push_statement(
+        ast::BinaryOperator::Assign
+            .apply(
+                Rc::clone(&this_rpc_expr),
+                Rc::new(ast::Expression::from(rpc_param_id)),
+            )
+            .into(),
+    );
+    client_class.add_member(constructor.into());
+
+    let mut encode_stream_funcs = Vec::new();
+    for rpc in service.rpcs.iter() {
+        let (method, encode_stream_func) = compile_client_method(
+            root,
+            &mut file,
+            &file_path,
+            &service_full_name,
+            &this_rpc_expr,
+            rpc,
+        );
+        client_class.add_member(method.into());
+        encode_stream_funcs.extend(encode_stream_func);
+    }
+
+    file.push_statement(client_class.into());
+    for func in encode_stream_funcs {
+        file.push_statement(func.into());
+    }
+
+    service_folder.push_file(file);
+
+    Ok(())
+}
+
+/// Transport method of the rpc, requests and responses are either single messages or streams
+fn transport_method_name(rpc: &Rpc) -> &'static str {
+    match (rpc.client_streaming, rpc.server_streaming) {
+        (false, false) => REQUEST_METHOD_NAME,
+        (false, true) => SERVER_STREAM_METHOD_NAME,
+        (true, false) => CLIENT_STREAM_METHOD_NAME,
+        (true, true) => BIDI_STREAM_METHOD_NAME,
+    }
+}
+
+/// `Rpc` declares only the transport methods used by the rpcs of the service
+fn create_rpc_interface(rpcs: &[Rpc]) -> ast::InterfaceDeclaration {
+    let bytes_type = Type::from_id("Uint8Array");
+    let bytes_stream_type = Type::generic("AsyncIterable", vec![bytes_type.clone()]);
+    let mut rpc_interface = ast::InterfaceDeclaration::new_exported(RPC_INTERFACE_NAME.into());
+    for (client_streaming, server_streaming) in
+        [(false, false), (false, true), (true, false), (true, true)]
+    {
+        let rpc = match rpcs.iter().find(|rpc| {
+            rpc.client_streaming == client_streaming && rpc.server_streaming == server_streaming
+        }) {
+            Some(rpc) => rpc,
+            None => continue,
+        };
+        let data_type = match client_streaming {
+            true => bytes_stream_type.clone(),
+            false => bytes_type.clone(),
+        };
+        let return_type = match server_streaming {
+            true => bytes_stream_type.clone(),
+            false => Type::generic("Promise", vec![bytes_type.clone()]),
+        };
+        rpc_interface.members.push(
+            ast::MethodSignature::new(
+                transport_method_name(rpc),
+                vec![
+                    ast::Parameter::new("service", Type::String),
+                    ast::Parameter::new("method", Type::String),
+                    ast::Parameter::new("data", data_type),
+                ],
+                return_type,
+            )
+            .into(),
+        );
+    }
+    rpc_interface
+}
+
+fn compile_client_method(
+    root: &RootScope,
+    file: &mut ast::File,
+    file_path: &TsPath,
+    service_full_name: &str,
+    this_rpc_expr: &Rc<ast::Expression>,
+    rpc: &Rpc,
+) -> (ast::MethodDeclaration, Option<ast::FunctionDeclaration>) {
+    let mut method = ast::MethodDeclaration::new_async(&rpc_name_to_method_name(&rpc.name));
+    method.generator = rpc.server_streaming;
+
+    let request_id: Rc<ast::Identifier> = Rc::new("request".into());
+    let (input_type, request_bytes_expr): (Type, ast::Expression) =
+        if is_empty_message(root, rpc.input_type) {
+            (
+                Type::Void,
+                ast::NewExpression::new(Rc::new("Uint8Array".into())).into(),
+            )
+        } else {
+            let input_type_name = {
+                let message_name = root.get_declaration_name(rpc.input_type).unwrap();
+                message_name_to_encode_type_name(&message_name)
+            };
+            let input_type =
+                import_message_type(root, file, file_path, rpc.input_type, &input_type_name);
+            let request_bytes_expr = import_encode_func(root, file, file_path, rpc.input_type)
+                .into_call(vec![Rc::new(Rc::clone(&request_id).into())])
+                .into_method_call("finish", vec![]);
+            (input_type, request_bytes_expr)
+        };
+
+    // Streams of requests are encoded by a generator declared next to the client
+    let (request_data_expr, encode_stream_func) = if rpc.client_streaming {
+        let requests_id: Rc<ast::Identifier> = Rc::new("requests".into());
+        let requests_type = Type::generic("AsyncIterable", vec![input_type]);
+        method.add_param(ast::Parameter::new(&requests_id, requests_type.clone()));
+
+        let func_name = format!("encode{}Requests", rpc.name);
+        let mut func = ast::FunctionDeclaration::new_async(&func_name);
+        func.generator = true;
+        func.add_param(ast::Parameter::new(&requests_id, requests_type));
+        func.returns(Type::generic(
+            "AsyncIterable",
+            vec![Type::from_id("Uint8Array")],
+        ));
+        let mut for_await =
+            ast::ForOfStatement::for_await(request_id, Rc::new(Rc::clone(&requests_id).into()));
+        for_await.push_statement(ast::Expression::Yield(Some(Rc::new(request_bytes_expr))).into());
+        func.push_statement(for_await.into());
+
+        let request_data_expr =
+            ast::Expression::from(func_name.as_str()).into_call(vec![Rc::new(requests_id.into())]);
+        (request_data_expr, Some(func))
+    } else {
+        if input_type != Type::Void {
+            method.add_param(ast::Parameter::new(&request_id, input_type));
+        }
+        (request_bytes_expr, None)
+    };
+
+    let output_type = if is_empty_message(root, rpc.output_type) {
+        None
+    } else {
+        let output_type_name = root.get_declaration_name(rpc.output_type).unwrap();
+        Some(import_message_type(
+            root,
+            file,
+            file_path,
+            rpc.output_type,
+            &output_type_name,
+        ))
+    };
+
+    let transport_call = this_rpc_expr.method_call(
+        transport_method_name(rpc),
+        vec![
+            Rc::new(ast::StringLiteral::from(service_full_name).into()),
+            Rc::new(ast::StringLiteral::from(&rpc.name).into()),
+            Rc::new(request_data_expr),
+        ],
+    );
+
+    let data_id: Rc<ast::Identifier> = Rc::new("data".into());
+    let decoded_data_expr = output_type.as_ref().map(|_| {
+        import_decode_func(root, file, file_path, rpc.output_type)
+            .into_call(vec![Rc::new(Rc::clone(&data_id).into())])
+    });
+
+    if rpc.server_streaming {
+        method.returns(Type::generic(
+            "AsyncIterable",
+            vec![output_type.unwrap_or(Type::Void)],
+        ));
+        let mut for_await = ast::ForOfStatement::for_await(data_id, Rc::new(transport_call));
+        for_await.push_statement(ast::Expression::Yield(decoded_data_expr.map(Rc::new)).into());
+        method.push_statement(for_await.into());
+        return (method, encode_stream_func);
+    }
+
+    method.returns(Type::generic(
+        "Promise",
+        vec![output_type.unwrap_or(Type::Void)],
+    ));
+    match decoded_data_expr {
+        Some(decoded_data_expr) => {
+            method.push_statement(
+                ast::VariableDeclarationList::declare_const(data_id, transport_call.into_await())
+                    .into(),
+            );
+            method.push_statement(decoded_data_expr.into_return_statement());
+        }
+        None => {
+            method.push_statement(transport_call.into_await().into());
+        }
+    }
+    (method, encode_stream_func)
+}
+
+#[cfg(test)]
+mod test_client {
+    use std::path::{Path, PathBuf};
+
+    use crate::{GenerationOptions, MemoryFileSystem, build_root_scope, compile};
+
+    #[test]
+    fn generates_method_per_rpc() {
+        let mut files = MemoryFileSystem::new();
+        files.insert(
+            "app/greeter.proto",
+            r#"syntax = "proto3";
+package app;
+message HelloRequest { string name = 1; }
+message HelloReply { string message = 1; }
+service Greeter {
+  rpc SayHello (HelloRequest) returns (HelloReply);
+  rpc SayHellos (stream HelloRequest) returns (HelloReply);
+  rpc Chat (stream HelloRequest) returns (stream HelloReply);
+}
+"#,
+        );
+        let root =
+            build_root_scope(&files, &[PathBuf::from("app/greeter.proto")], &[], false).unwrap();
+        let output = compile(&root, &GenerationOptions::default()).unwrap();
+        let client = &output[Path::new("app/greeter/Greeter/client.ts")];
+        assert!(!client.contains("serverStream("));
+        assert!(client.contains(
+            "  clientStream(service: string, method: string, data: AsyncIterable<Uint8Array>): Promise<Uint8Array>\n"
+        ));
+        assert!(client.contains(
+            "  async sayHellos(requests: AsyncIterable<HelloRequestEncodeInput>): Promise<HelloReply> {\n    const data = await this.rpc.clientStream(\"app.Greeter\", \"SayHellos\", encodeSayHellosRequests(requests))\n"
+        ));
+        assert!(client.contains(
+            "  async *chat(requests: AsyncIterable<HelloRequestEncodeInput>): AsyncIterable<HelloReply> {\n    for await (const data of this.rpc.bidiStream(\"app.Greeter\", \"Chat\", encodeChatRequests(requests))) {\n"
+        ));
+        assert!(client.contains(
+            "async function* encodeChatRequests(requests: AsyncIterable<HelloRequestEncodeInput>): AsyncIterable<Uint8Array> {\n  for await (const request of requests) {\n    yield e1(request).finish()\n"
+        ));
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+/// Representation of int64, uint64, sint64, fixed64 and sfixed64 values in generated code
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Int64Mode {
+    /// `Long` of long.js, decoded values are numbers if long.js is missing.
+    /// fixed64 values are `number` in this mode, as in code generated before the other modes
+    #[default]
+    Long,
+    /// Native `bigint`
+    BigInt,
+    /// Decimal `string`
+    String,
+    /// `number`, values out of the safe integer range lose precision
+    Number,
+}
+
+impl Int64Mode {
+    /// Mode by its name in the config: `long`, `bigint`, `string` or `number`
+    pub fn from_name(name: &str) -> Option<Int64Mode> {
+        match name {
+            "long" => Some(Int64Mode::Long),
+            "bigint" => Some(Int64Mode::BigInt),
+            "string" => Some(Int64Mode::String),
+            "number" => Some(Int64Mode::Number),
+            _ => None,
+        }
+    }
+}
+
+/// Options of the code generated for a package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodegenOptions {
+    /// Generate code for the package at all
+    pub generate: bool,
+    /// Generate `client.ts` and `handler.ts` for services
+    pub services: bool,
+    /// Representation of 64-bit integer fields without the `(ts.int64)` option
+    pub int64: Int64Mode,
+}
+
+impl Default for CodegenOptions {
+    fn default() -> Self {
+        Self {
+            generate: true,
+            services: true,
+            int64: Int64Mode::Long,
+        }
+    }
+}
+
+/// Options which are set for a package and its subpackages, unset ones are inherited
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageOptions {
+    pub generate: Option<bool>,
+    pub services: Option<bool>,
+    pub int64: Option<Int64Mode>,
+}
+
+/// Options of the whole output with overrides per package
+#[derive(Debug, Clone, Default)]
+pub struct GenerationOptions {
+    pub codegen: CodegenOptions,
+    /// Overrides by full package name, e.g. `google.api`
+    pub packages: BTreeMap<String, PackageOptions>,
+}
+
+impl GenerationOptions {
+    /// Options of the package with the overrides of its parent packages applied
+    /// from the outermost to the package itself
+    pub fn for_package(&self, package: &str) -> CodegenOptions {
+        let mut res = self.codegen.clone();
+        let mut prefix = String::new();
+        for part in package.split('.').filter(|p| !p.is_empty()) {
+            if !prefix.is_empty() {
+                prefix.push('.');
+            }
+            prefix.push_str(part);
+            if let Some(options) = self.packages.get(&prefix) {
+                res.generate = options.generate.unwrap_or(res.generate);
+                res.services = options.services.unwrap_or(res.services);
+                res.int64 = options.int64.unwrap_or(res.int64);
+            }
+        }
+        res
+    }
+}
@@ -1,43 +1,283 @@
 use std::{
-    fs::{create_dir, remove_dir_all},
-    io::Write,
-    path::Path,
+    collections::HashSet,
+    fmt::{Display, Formatter},
+    fs::{create_dir_all, remove_dir, remove_dir_all, remove_file},
+    io::{self, Write},
+    path::{Component, Path, PathBuf},
 };
 
 use super::super::super::error::ProtoError;
 
-pub(crate) fn commit_folder(folder: &super::ast::Folder) -> Result<(), ProtoError> {
-    let folder_name = folder.name.to_string();
-    let destination_path = Path::new(&folder_name);
-    if destination_path.exists() {
-        remove_dir_all(&destination_path).map_err(ProtoError::IOError)?;
+/// File in the output directory which lists generated files, one relative path per line
+pub(crate) const MANIFEST_FILE_NAME: &str = ".protos-ts-manifest";
+
+/// Number of files of the destination per kind of change made by [`commit_folder`]
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct CommitSummary {
+    pub created: usize,
+    pub updated: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+}
+
+impl Display for CommitSummary {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} created, {} updated, {} removed, {} unchanged",
+            self.created, self.updated, self.removed, self.unchanged
+        )
+    }
+}
+
+/// Writes the folder into `destination`.
+/// Files whose content did not change are not touched, so that file watchers are not triggered.
+/// Files generated by the previous run that are not generated anymore are removed,
+/// other files of the destination are kept. With `clean` the whole destination is removed first
+pub(crate) fn commit_folder(
+    folder: &super::ast::Folder,
+    destination: &Path,
+    clean: bool,
+) -> Result<CommitSummary, ProtoError> {
+    let files = render_folder(folder)?;
+    if clean && destination.exists() {
+        remove_dir_all(destination).map_err(ProtoError::IOError)?;
+    }
+    create_dir_all(destination).map_err(ProtoError::IOError)?;
+    let previous_files = read_manifest(destination).map_err(ProtoError::IOError)?;
+
+    let mut summary = CommitSummary::default();
+    for (file_path, content) in &files {
+        let out_file_path = destination.join(file_path);
+        if let Some(parent) = out_file_path.parent() {
+            create_dir_all(parent).map_err(ProtoError::IOError)?;
+        }
+        match write_if_changed(&out_file_path, content.as_bytes()).map_err(ProtoError::IOError)? {
+            FileChange::Created => summary.created += 1,
+            FileChange::Updated => summary.updated += 1,
+            FileChange::Unchanged => summary.unchanged += 1,
+        }
+    }
+
+    let written_files = files.into_iter().map(|(path, _)| path).collect::<Vec<_>>();
+    let written: HashSet<&PathBuf> = written_files.iter().collect();
+    for stale_file in previous_files.iter().filter(|f| !written.contains(f)) {
+        if remove_stale_file(destination, stale_file).map_err(ProtoError::IOError)? {
+            summary.removed += 1;
+        }
     }
-    create_dir(destination_path).map_err(ProtoError::IOError)?;
-    destination_path
-        .canonicalize()
-        .map_err(ProtoError::IOError)?;
-    write_folder(&destination_path, folder)
+    write_manifest(destination, &written_files).map_err(ProtoError::IOError)?;
+    Ok(summary)
 }
 
-fn write_folder(dist: &Path, folder: &super::ast::Folder) -> Result<(), ProtoError> {
+/// Renders all files of the folder with their paths relative to the folder.
+/// Fails if several files have the same path, as one of them would replace the others
+pub(crate) fn render_folder(
+    folder: &super::ast::Folder,
+) -> Result<Vec<(PathBuf, String)>, ProtoError> {
+    let mut files = Vec::new();
+    render_folder_files(Path::new(""), folder, &mut files);
+    let mut paths = HashSet::new();
+    let errors = files
+        .iter()
+        .filter(|(path, _)| !paths.insert(path))
+        .map(|(path, _)| {
+            ProtoError::Default(format!("Several declarations generate {}", path.display()))
+        })
+        .collect::<Vec<_>>();
+    ProtoError::from_errors(errors)?;
+    Ok(files)
+}
+
+fn render_folder_files(
+    relative_path: &Path,
+    folder: &super::ast::Folder,
+    files: &mut Vec<(PathBuf, String)>,
+) {
     for entry in &folder.entries {
         match entry {
-            super::ast::FolderEntry::Folder(subfolder) => {
-                let destination_path = dist.join(&subfolder.name.to_string());
-                create_dir(&destination_path).map_err(ProtoError::IOError)?;
-                write_folder(&destination_path, subfolder)?;
-            }
+            super::ast::FolderEntry::Folder(subfolder) => render_folder_files(
+                &relative_path.join(subfolder.name.as_ref()),
+                subfolder,
+                files,
+            ),
             super::ast::FolderEntry::File(file) => {
-                let out_file_path = dist.join(format!("{}.ts", &file.name));
-                let mut out_file =
-                    std::fs::File::create(out_file_path).map_err(ProtoError::IOError)?;
-                let content: String = file.as_ref().into();
-                out_file
-                    .write_all(content.as_bytes())
-                    .map_err(ProtoError::IOError)?;
+                let file_path = relative_path.join(format!("{}.ts", &file.name));
+                files.push((file_path, file.as_ref().into()));
             }
         }
     }
+}
+
+enum FileChange {
+    Created,
+    Updated,
+    Unchanged,
+}
+
+fn write_if_changed(path: &Path, content: &[u8]) -> io::Result<FileChange> {
+    let change = match std::fs::read(path) {
+        Ok(current) if current == content => return Ok(FileChange::Unchanged),
+        Ok(_) => FileChange::Updated,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => FileChange::Created,
+        Err(err) => return Err(err),
+    };
+    std::fs::File::create(path)?.write_all(content)?;
+    Ok(change)
+}
+
+/// Paths listed in the manifest of the destination.
+/// Paths which lead outside of the destination are ignored, so that a broken manifest
+/// never removes foreign files
+pub(super) fn read_manifest(destination: &Path) -> io::Result<Vec<PathBuf>> {
+    let content = match std::fs::read_to_string(destination.join(MANIFEST_FILE_NAME)) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    Ok(content
+        .lines()
+        .map(PathBuf::from)
+        .filter(|path| {
+            path.components().next().is_some()
+                && path.components().all(|c| matches!(c, Component::Normal(_)))
+        })
+        .collect())
+}
+
+fn write_manifest(destination: &Path, files: &[PathBuf]) -> io::Result<()> {
+    let mut content = String::new();
+    for file in files {
+        let components = file
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>();
+        content.push_str(&components.join("/"));
+        content.push('\n');
+    }
+    write_if_changed(&destination.join(MANIFEST_FILE_NAME), content.as_bytes()).map(|_| ())
+}
+
+/// Removes the file and then its parent folders which became empty.
+/// Returns false if the file was already removed
+fn remove_stale_file(destination: &Path, file: &Path) -> io::Result<bool> {
+    match remove_file(destination.join(file)) {
+        Ok(_) => {}
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err),
+    }
+    for parent in file.ancestors().skip(1) {
+        if parent.as_os_str().is_empty() || remove_dir(destination.join(parent)).is_err() {
+            break;
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod test_commit_folder {
+    use std::{fs, path::Path};
+
+    use crate::{
+        proto::compiler::ts::ast::{File, Folder},
+        test_utils::TempDir,
+    };
+
+    use super::{CommitSummary, MANIFEST_FILE_NAME, commit_folder};
+
+    fn folder(files: &[&str]) -> Folder {
+        let mut root = Folder::new("out".into());
+        let mut package = Folder::new("package".into());
+        for file in files {
+            package.push_file(File::new((*file).into()));
+        }
+        root.push_folder(package);
+        root
+    }
+
+    #[test]
+    fn removes_only_previously_generated_files() {
+        let temp_dir = TempDir::new("commit_folder_removes_generated_files");
+        let destination = temp_dir.path().join("gen/ts");
+        fs::create_dir_all(&destination).unwrap();
+        fs::write(destination.join("index.ts"), "").unwrap();
 
-    Ok(())
+        let summary = commit_folder(&folder(&["a", "b"]), &destination, false).unwrap();
+        assert_eq!(summary.created, 2);
+        assert!(destination.join("package/a.ts").is_file());
+        assert!(destination.join("package/b.ts").is_file());
+
+        let summary = commit_folder(&folder(&["a"]), &destination, false).unwrap();
+        assert_eq!(
+            summary,
+            CommitSummary {
+                created: 0,
+                updated: 0,
+                removed: 1,
+                unchanged: 1,
+            }
+        );
+        assert!(destination.join("package/a.ts").is_file());
+        assert!(!destination.join("package/b.ts").exists());
+        assert!(destination.join("index.ts").is_file());
+        assert_eq!(
+            fs::read_to_string(destination.join(MANIFEST_FILE_NAME)).unwrap(),
+            "package/a.ts\n"
+        );
+
+        commit_folder(&Folder::new("out".into()), &destination, false).unwrap();
+        assert!(!destination.join("package").exists());
+        assert!(destination.join("index.ts").is_file());
+        assert!(Path::new(&destination).is_dir());
+    }
+
+    #[test]
+    fn counts_changed_files() {
+        let temp_dir = TempDir::new("commit_folder_counts_changed_files");
+        let destination = temp_dir.path().join("changes");
+
+        let summary = commit_folder(&folder(&["a", "b"]), &destination, false).unwrap();
+        assert_eq!(
+            summary.to_string(),
+            "2 created, 0 updated, 0 removed, 0 unchanged"
+        );
+
+        // Unchanged files are not written, so their modification time is kept
+        let old_time = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1);
+        let a_path = destination.join("package/a.ts");
+        fs::File::options()
+            .write(true)
+            .open(&a_path)
+            .unwrap()
+            .set_modified(old_time)
+            .unwrap();
+        fs::write(destination.join("package/b.ts"), "// edited").unwrap();
+
+        let summary = commit_folder(&folder(&["a", "b"]), &destination, false).unwrap();
+        assert_eq!(
+            summary.to_string(),
+            "0 created, 1 updated, 0 removed, 1 unchanged"
+        );
+        assert_eq!(fs::metadata(&a_path).unwrap().modified().unwrap(), old_time);
+        assert_eq!(
+            fs::read_to_string(destination.join("package/b.ts")).unwrap(),
+            ""
+        );
+    }
+
+    #[test]
+    fn fails_on_files_with_the_same_path() {
+        let temp_dir = TempDir::new("commit_folder_fails_on_same_path");
+        let destination = temp_dir.path().join("out");
+
+        let err = commit_folder(&folder(&["a", "b", "a"]), &destination, false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Several declarations generate {}",
+                Path::new("package/a.ts").display()
+            )
+        );
+        assert!(!destination.exists());
+    }
 }
@@ -1,6 +1,8 @@
 pub(super) const PROTOBUF_MODULE: &'static str = "protobufjs/minimal";
 pub(super) const ENCODE_FUNCTION_NAME: &'static str = "encode";
 pub(super) const DECODE_FUNCTION_NAME: &'static str = "decode";
+pub(super) const TO_JSON_FUNCTION_NAME: &str = "toJSON";
+pub(super) const FROM_JSON_FUNCTION_NAME: &str = "fromJSON";
 
 // {
 //     long: {
@@ -1,4 +1,4 @@
-use std::{ops::Deref, rc::Rc};
+use std::{collections::BTreeSet, ops::Deref, rc::Rc};
 
 use crate::proto::{
     compiler::ts::{ast::ElementAccess, ts_path::TsPath},
@@ -12,17 +12,21 @@ use super::{
         self, BinaryOperator, Block, CaseClause, ExpressionChain, MethodCall, ObjectLiteralMember,
         Prop, StatementList, StatementPlacer, VariableDeclarationList,
     },
+    codegen_options::{CodegenOptions, Int64Mode},
     constants::{DECODE_FUNCTION_NAME, PROTOBUF_MODULE},
     ensure_import::ensure_import,
     get_relative_import::get_relative_import_string,
+    int64_mode::{default_value, field_int64_mode, helper_declaration, read_value},
 };
 
 pub(super) fn compile_decode(
     root: &RootScope,
     message_folder: &mut ast::Folder,
     message_scope: &ProtoScope,
+    options: &CodegenOptions,
 ) -> Result<(), ProtoError> {
     let mut file = super::ast::File::new("decode".into());
+    let mut int64_helpers = BTreeSet::new();
 
     let reader_type_id: Rc<ast::Identifier> = ast::Identifier::from("Reader").into();
     let util_id: Rc<ast::Identifier> = ast::Identifier::from("util").into();
@@ -135,7 +139,7 @@ pub(super) fn compile_decode(
         .into(),
     ));
 
-    let default_message_value = get_default_message_value(message_scope);
+    let default_message_value = get_default_message_value(message_scope, options)?;
 
     decode_function_declaration.push_statement(ast::Statement::VariableStatement(
         ast::VariableDeclarationList::declare_typed_const(
@@ -206,7 +210,12 @@ pub(super) fn compile_decode(
             for field in fields {
                 let name = field.json_name();
                 let id = field.tag;
+                let int64 = field_int64_mode(field, options)?;
                 let field_type = match &field.field_type {
+                    package::Type::Optional(t) => t.deref(),
+                    t => t,
+                };
+                let field_type = match field_type {
                     package::Type::Enum(_) => &package::Type::Int32,
                     t => t,
                 };
@@ -268,9 +277,13 @@ pub(super) fn compile_decode(
                             Some(_) => {
                                 let parse_element_expr = Rc::new(field_value_ref.method_call(
                                     "push",
-                                    vec![reader_var_expr
-                                        .method_call(&element_type.to_string(), vec![])
-                                        .into()],
+                                    vec![read_value(
+                                        &reader_var_expr,
+                                        &element_type,
+                                        int64,
+                                        &mut int64_helpers,
+                                    )
+                                    .into()],
                                 ));
 
                                 let mut packed_block = Block::new();
@@ -353,13 +366,16 @@ pub(super) fn compile_decode(
                                     ))
                                 }
                                 basic => {
-                                    let basic_str = basic.to_string();
                                     case_clause.push_statement(ast::Statement::from(
                                         field_value_ref.method_call(
                                             "push",
-                                            vec![reader_var_expr
-                                                .method_call(&basic_str, vec![])
-                                                .into()],
+                                            vec![read_value(
+                                                &reader_var_expr,
+                                                basic,
+                                                int64,
+                                                &mut int64_helpers,
+                                            )
+                                            .into()],
                                         ),
                                     ))
                                 }
@@ -409,13 +425,19 @@ pub(super) fn compile_decode(
                             package::Type::Enum(_) => package::Type::Int32.into(),
                             _ => Rc::clone(vt),
                         };
+                        // Keys of objects are strings, 64-bit keys are converted to them
+                        // unless they are `Long`
+                        let key_int64 = match int64 {
+                            Int64Mode::Long => Int64Mode::Long,
+                            _ => Int64Mode::String,
+                        };
 
                         case_clause.push_statement(
                             ast::Statement::VariableStatement(
                                 VariableDeclarationList::declare_typed_let(
                                     Rc::clone(&key_id),
                                     ast::Type::Any.into(),
-                                    kt.default_expression().into(),
+                                    default_value(kt, key_int64),
                                 )
                                 .into(),
                             )
@@ -426,7 +448,7 @@ pub(super) fn compile_decode(
                                 VariableDeclarationList::declare_typed_let(
                                     Rc::clone(&val_id),
                                     ast::Type::Any.into(),
-                                    value_type.default_expression().into(),
+                                    default_value(&value_type, int64),
                                 )
                                 .into(),
                             )
@@ -474,12 +496,17 @@ pub(super) fn compile_decode(
                                     ));
 
                                 let mut key_case = CaseClause::new(Rc::new(1.into()));
-                                let kt_string = kt.to_string();
                                 key_case.push_statement(ast::Statement::Expression(
                                     BinaryOperator::Assign
                                         .apply(
                                             Rc::clone(&key_expr),
-                                            reader_var_expr.method_call(&kt_string, vec![]).into(),
+                                            read_value(
+                                                &reader_var_expr,
+                                                kt,
+                                                key_int64,
+                                                &mut int64_helpers,
+                                            )
+                                            .into(),
                                         )
                                         .into(),
                                 ));
@@ -512,14 +539,17 @@ pub(super) fn compile_decode(
                                     }
                                     basic => {
                                         assert!(basic.is_basic());
-                                        let b_str = basic.to_string();
                                         val_case.push_statement(ast::Statement::Expression(
                                             BinaryOperator::Assign
                                                 .apply(
                                                     Rc::clone(&val_expr),
-                                                    reader_var_expr
-                                                        .method_call(&b_str, vec![])
-                                                        .into(),
+                                                    read_value(
+                                                        &reader_var_expr,
+                                                        basic,
+                                                        int64,
+                                                        &mut int64_helpers,
+                                                    )
+                                                    .into(),
                                                 )
                                                 .into(),
                                         ));
@@ -531,7 +561,7 @@ pub(super) fn compile_decode(
                         }
 
                         match kt.long_wire_type() {
-                            Some(_) => case_clause.push_statement(ast::Statement::Expression(
+                            Some(_) if key_int64 == Int64Mode::Long => case_clause.push_statement(ast::Statement::Expression(
                                 BinaryOperator::Assign
                                     .apply(
                                         Rc::clone(&key_expr),
@@ -557,7 +587,7 @@ pub(super) fn compile_decode(
                                     )
                                     .into(),
                             )),
-                            None => {}
+                            _ => {}
                         }
                         case_clause.push_statement(ast::Statement::Expression(
                             BinaryOperator::Assign
@@ -572,7 +602,12 @@ pub(super) fn compile_decode(
                         ast::BinaryOperator::Assign
                             .apply(
                                 Rc::clone(&field_value_ref),
-                                Rc::new(reader_var_expr.method_call(&basic.to_string(), vec![])),
+                                Rc::new(read_value(
+                                    &reader_var_expr,
+                                    basic,
+                                    int64,
+                                    &mut int64_helpers,
+                                )),
                             )
                             .into(),
                     ),
@@ -591,26 +626,36 @@ pub(super) fn compile_decode(
     file.push_statement(ast::Statement::FunctionDeclaration(
         decode_function_declaration.into(),
     ));
+    for helper in int64_helpers {
+        file.push_statement(helper_declaration(helper).into());
+    }
 
     message_folder.push_file(file);
     Ok(())
 }
 
-fn get_default_message_value(message_scope: &ProtoScope) -> ast::Expression {
-    ast::Expression::ObjectLiteralExpression(
-        message_scope
-            .get_message_declaration()
-            .unwrap()
-            .get_fields()
-            .into_iter()
-            .map(|f| {
-                let n = f.json_name();
-                let default_value = f.field_type.default_expression();
+pub(super) fn get_default_message_value(
+    message_scope: &ProtoScope,
+    options: &CodegenOptions,
+) -> Result<ast::Expression, ProtoError> {
+    let message_declaration = message_scope.get_message_declaration().unwrap();
+    let members = message_declaration
+        .get_fields()
+        .into_iter()
+        // Extensions and members of oneofs are present in decoded message only if they were encoded
+        .filter(|f| {
+            !message_declaration.is_extension(f) && !message_declaration.is_one_of_member(f)
+        })
+        .map(|f| {
+            let n = f.json_name();
+            let default_value = default_value(&f.field_type, field_int64_mode(f, options)?);
+            Ok(
                 ObjectLiteralMember::PropertyAssignment(Rc::new(n.into()), default_value.into())
-                    .into()
-            })
-            .collect(),
-    )
+                    .into(),
+            )
+        })
+        .collect::<Result<_, ProtoError>>()?;
+    Ok(ast::Expression::ObjectLiteralExpression(members))
 }
 
 fn import_decode_func(
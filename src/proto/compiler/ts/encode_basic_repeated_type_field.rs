@@ -5,13 +5,19 @@ use crate::proto::{
     package::{self},
 };
 
-use super::ast::{self, ForStatement, Prop};
+use super::{
+    ast::{self, ForStatement, Prop},
+    codegen_options::Int64Mode,
+    int64_mode::encoded_value,
+};
 
 pub(super) fn encode_basic_repeated_type_field(
     field_value: &Rc<ast::Expression>,
     field_type: &package::Type,
     field_tag: i64,
     writer_var: &Rc<ast::Identifier>,
+    packed: bool,
+    int64: Int64Mode,
 ) -> ast::Statement {
     let field_exists_expression = ast::BinaryOperator::LogicalAnd
         .apply(
@@ -28,8 +34,10 @@ pub(super) fn encode_basic_repeated_type_field(
         package::Type::Repeated(_) => unreachable!(),
         package::Type::Map(_, _) => unreachable!(),
         basic => match basic.packed_wire_type() {
-            Some(_) => encode_packed_elements(&field_value, basic, field_tag, &writer_var),
-            None => encode_non_packed_elements(&field_value, basic, field_tag, &writer_var),
+            Some(_) if packed => {
+                encode_packed_elements(field_value, basic, field_tag, writer_var, int64)
+            }
+            _ => encode_non_packed_elements(field_value, basic, field_tag, writer_var, int64),
         },
     };
 
@@ -45,6 +53,7 @@ fn encode_non_packed_elements(
     element_type: &package::Type,
     field_tag: i64,
     writer_var: &Rc<ast::Identifier>,
+    int64: Int64Mode,
 ) -> ast::Statement {
     assert!(element_type.is_basic());
     let mut res = ast::Block::new();
@@ -66,7 +75,8 @@ fn encode_non_packed_elements(
     let i_id = Rc::new(ast::Identifier::new("i"));
     let i_id_expr = Rc::new(Rc::clone(&i_id).into());
 
-    let element_value_expr: Rc<ast::Expression> = field_value.element(i_id_expr).into();
+    let element_value_expr =
+        encoded_value(field_value.element(i_id_expr).into(), element_type, int64);
 
     let type_str = element_type.to_string();
     let encode_element_expr: Rc<ast::Expression> = Rc::new(tag_encoding_expr)
@@ -85,6 +95,7 @@ fn encode_packed_elements(
     element_type: &package::Type,
     field_tag: i64,
     writer_var: &Rc<ast::Identifier>,
+    int64: Int64Mode,
 ) -> ast::Statement {
     assert!(element_type.is_basic());
     let mut res = ast::Block::new();
@@ -110,7 +121,8 @@ fn encode_packed_elements(
     let i_id_expr = Rc::new(ast::Expression::Identifier(Rc::clone(&i_id)));
     let mut for_stmt = ForStatement::for_each(i_id, Rc::clone(&field_value));
 
-    let element_value_expr: Rc<ast::Expression> = field_value.element(i_id_expr).into();
+    let element_value_expr =
+        encoded_value(field_value.element(i_id_expr).into(), element_type, int64);
 
     let type_str = element_type.to_string();
     let encode_element_expr: Rc<ast::Expression> = writer_expr
@@ -5,7 +5,11 @@ use crate::proto::{
     package::{self},
 };
 
-use super::ast::{self, Identifier, MethodCall};
+use super::{
+    ast::{self, Identifier, MethodCall},
+    codegen_options::Int64Mode,
+    int64_mode::encoded_value,
+};
 
 pub(crate) fn encode_basic_type_field(
     field_value: &Rc<ast::Expression>,
@@ -14,6 +18,7 @@ pub(crate) fn encode_basic_type_field(
     writer_var: &Rc<Identifier>,
     field_type: &package::Type,
     field_tag: i64,
+    int64: Int64Mode,
 ) -> ast::Statement {
     let wire_type = field_type.get_basic_wire_type();
     let field_prefix = (field_tag << 3) | (wire_type as i64);
@@ -38,8 +43,10 @@ pub(crate) fn encode_basic_type_field(
     );
 
     let type_str = field_type.to_string();
-    let encode_field_stmt =
-        Rc::new(tag_encoding_expr).method_call(&type_str, vec![Rc::clone(&field_value)]);
+    let encode_field_stmt = Rc::new(tag_encoding_expr).method_call(
+        &type_str,
+        vec![encoded_value(Rc::clone(field_value), field_type, int64)],
+    );
     ast::Statement::IfStatement(ast::IfStatement {
         expression: field_exists_expression,
         then_statement: ast::Statement::from(ast::Block {
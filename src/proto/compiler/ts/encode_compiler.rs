@@ -8,18 +8,20 @@ use crate::proto::{
         encode_message_expr::encode_message_expr,
     },
     error::ProtoError,
-    package::{self, Field},
+    package,
     proto_scope::{ProtoScope, root_scope::RootScope},
 };
 
 use super::{
     ast::{self, ElementAccess, Folder, MethodCall, Prop, StatementList, Type},
+    codegen_options::{CodegenOptions, Int64Mode},
     constants::{ENCODE_FUNCTION_NAME, PROTOBUF_MODULE},
     encode_basic_type_field::encode_basic_type_field,
     encode_enum_field::encode_enum_field,
     encode_map_field::encode_map_field,
     ensure_import::ensure_import,
     has_property::has_property,
+    int64_mode::{Int64Helper, field_int64_mode, has_int64, helper_declaration},
     message_name_to_encode_type_name::message_name_to_encode_type_name,
 };
 
@@ -27,6 +29,7 @@ pub(super) fn compile_encode(
     root: &RootScope,
     message_folder: &mut Folder,
     message_scope: &ProtoScope,
+    options: &CodegenOptions,
 ) -> Result<(), ProtoError> {
     let mut file = super::ast::File::new("encode".into());
 
@@ -92,15 +95,27 @@ pub(super) fn compile_encode(
     };
 
     let fields = message_declaration.get_fields();
+    let mut uses_long_bits = false;
 
     for field in fields {
-        compile_encode_field(
+        let int64 = field_int64_mode(field, options)?;
+        uses_long_bits |= int64 != Int64Mode::Long && has_int64(&field.field_type);
+        let js_name = field.json_name();
+        let js_name_id: Rc<ast::Identifier> = ast::Identifier::new(&js_name).into();
+        let message_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&message_parameter_id).into());
+        let field_value = Rc::new(message_expr.prop(&js_name));
+        compile_encode_field_value(
             root,
             message_scope,
             &mut file,
             &message_parameter_id,
             &writer_var,
-            field,
+            js_name_id,
+            &field.field_type,
+            field_value,
+            field.tag,
+            field.packed,
+            int64,
             &mut encode_func,
         )?;
     }
@@ -112,40 +127,15 @@ pub(super) fn compile_encode(
     );
 
     file.push_statement(encode_func.into());
+    if uses_long_bits {
+        file.push_statement(helper_declaration(Int64Helper::ToLongBits).into());
+    }
 
     message_folder.push_file(file);
 
     Ok(())
 }
 
-fn compile_encode_field(
-    root: &RootScope,
-    message_scope: &ProtoScope,
-    file: &mut super::ast::File,
-    message_parameter_id: &Rc<ast::Identifier>,
-    writer_var: &Rc<ast::Identifier>,
-    field: &Field,
-    encode_func: &mut FunctionDeclaration,
-) -> Result<(), ProtoError> {
-    let js_name = field.json_name();
-    let js_name_id: Rc<ast::Identifier> = ast::Identifier::new(&js_name).into();
-    let message_expr: Rc<ast::Expression> = Rc::new(Rc::clone(message_parameter_id).into());
-    let field_value = Rc::new(message_expr.prop(&js_name));
-
-    compile_encode_field_value(
-        root,
-        message_scope,
-        file,
-        message_parameter_id,
-        writer_var,
-        js_name_id,
-        &field.field_type,
-        field_value,
-        field.tag,
-        encode_func,
-    )
-}
-
 fn compile_encode_field_value(
     root: &RootScope,
     message_scope: &ProtoScope,
@@ -156,6 +146,8 @@ fn compile_encode_field_value(
     field_type: &package::Type,
     field_value: Rc<Expression>,
     field_tag: i64,
+    packed: bool,
+    int64: Int64Mode,
     encode_func: &mut FunctionDeclaration,
 ) -> Result<(), ProtoError> {
     match field_type {
@@ -214,6 +206,8 @@ fn compile_encode_field_value(
             element_type,
             field_value,
             field_tag,
+            packed,
+            int64,
             encode_func,
         )?,
         package::Type::Repeated(element_type) => match element_type.deref() {
@@ -261,17 +255,24 @@ fn compile_encode_field_value(
                         &field_value,
                         &package::Type::Int32,
                         field_tag,
-                        &writer_var,
-                    )
-                    .into(),
+                        writer_var,
+                        packed,
+                        int64,
+                    ),
                 );
             }
             basic => {
                 assert!(basic.is_basic());
 
                 encode_func.push_statement(
-                    encode_basic_repeated_type_field(&field_value, basic, field_tag, &writer_var)
-                        .into(),
+                    encode_basic_repeated_type_field(
+                        &field_value,
+                        basic,
+                        field_tag,
+                        writer_var,
+                        packed,
+                        int64,
+                    ),
                 )
             }
         },
@@ -287,6 +288,7 @@ fn compile_encode_field_value(
                 field_tag,
                 kt,
                 vt,
+                int64,
             )?
             .into(),
         ),
@@ -301,6 +303,7 @@ fn compile_encode_field_value(
                     &writer_var,
                     t,
                     field_tag,
+                    int64,
                 )
                 .into(),
             );
@@ -308,3 +311,55 @@ fn compile_encode_field_value(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod test_encode {
+    use std::path::{Path, PathBuf};
+
+    use crate::{GenerationOptions, MemoryFileSystem, build_root_scope, compile};
+
+    #[test]
+    fn packs_repeated_scalars_by_syntax() {
+        let mut files = MemoryFileSystem::new();
+        files.insert(
+            "app/legacy.proto",
+            r#"syntax = "proto2";
+package app;
+message Legacy {
+  repeated int32 ids = 1;
+  repeated int32 packed_ids = 2 [packed = true];
+  extensions 100 to 199;
+}
+extend Legacy {
+  repeated int32 tags = 101;
+}
+"#,
+        );
+        files.insert(
+            "app/user.proto",
+            r#"syntax = "proto3";
+package app;
+message User {
+  repeated int32 ids = 1;
+  repeated int32 unpacked_ids = 2 [packed = false];
+}
+"#,
+        );
+        let entry_files = [
+            PathBuf::from("app/legacy.proto"),
+            PathBuf::from("app/user.proto"),
+        ];
+        let root = build_root_scope(&files, &entry_files, &[], false).unwrap();
+        let output = compile(&root, &GenerationOptions::default()).unwrap();
+
+        let legacy = &output[Path::new("app/legacy/Legacy/encode.ts")];
+        assert!(legacy.contains("w.uint32(8).int32(message.ids[i])"));
+        assert!(legacy.contains("w.uint32(18).fork()"));
+        assert!(legacy.contains("w.uint32(808).int32(message.tags[i])"));
+        assert!(!legacy.contains("w.uint32(810).fork()"));
+
+        let user = &output[Path::new("app/user/User/encode.ts")];
+        assert!(user.contains("w.uint32(10).fork()"));
+        assert!(user.contains("w.uint32(16).int32(message.unpacked_ids[i])"));
+    }
+}
@@ -9,8 +9,10 @@ use crate::proto::{
 
 use super::{
     ast::{self, MethodCall, MethodChain, StatementList},
+    codegen_options::Int64Mode,
     encode_message_expr::encode_message_expr,
     has_property::has_property,
+    int64_mode::encoded_value,
 };
 
 pub(super) fn encode_map_field(
@@ -24,6 +26,7 @@ pub(super) fn encode_map_field(
     field_tag: i64,
     key_type: &package::Type,
     value_type: &package::Type,
+    int64: Int64Mode,
 ) -> Result<ast::Statement, ProtoError> {
     let field_exists_expression = ast::BinaryOperator::LogicalAnd
         .apply(
@@ -82,7 +85,7 @@ pub(super) fn encode_map_field(
         Rc::clone(&writer_var_expr),
         field_tag,
         key_type,
-        key_expr,
+        encoded_value(key_expr, key_type, int64),
     ));
 
     match value_type {
@@ -118,6 +121,7 @@ pub(super) fn encode_map_field(
             for_stmt.push_statement(key_value_expr.into());
         }
         basic => {
+            let value_expr = encoded_value(value_expr, basic, int64);
             for_stmt
                 .push_statement(encode_basic_key_value(basic, encode_key_expr, value_expr).into());
         }
@@ -1,9 +1,11 @@
 use std::ops::Deref;
 
 use super::{
-    ast::Folder, decode_compiler::compile_decode, encode_compiler::compile_encode,
-    enum_compiler::insert_enum_declaration, file_name_to_folder_name::file_name_to_folder_name,
-    types_compiler::insert_message_types,
+    ast::Folder, client_compiler::compile_client, codegen_options::CodegenOptions, decode_compiler::compile_decode,
+    encode_compiler::compile_encode, enum_compiler::insert_enum_declaration,
+    file_name_to_folder_name::file_name_to_folder_name, from_json_compiler::compile_from_json,
+    handler_compiler::compile_handler,
+    to_json_compiler::compile_to_json, types_compiler::insert_message_types,
 };
 use crate::proto::{
     error::ProtoError,
@@ -13,6 +15,7 @@ use crate::proto::{
 pub(super) fn file_to_folder(
     root: &RootScope,
     file_scope: &ProtoScope,
+    options: &CodegenOptions,
 ) -> Result<Folder, ProtoError> {
     let folder_name = file_name_to_folder_name(&file_scope.name());
     let mut res = Folder::new(folder_name);
@@ -23,7 +26,12 @@ pub(super) fn file_to_folder(
             ProtoScope::File(_) => unreachable!(),
             e @ ProtoScope::Enum(_) => insert_enum_declaration(&mut res, e),
             m @ ProtoScope::Message(_) => {
-                insert_message_declaration(&root, &mut res, m)?;
+                insert_message_declaration(root, &mut res, m, options)?;
+            }
+            s @ ProtoScope::Service(_) => {
+                if options.services {
+                    insert_service_declaration(root, &mut res, s)?;
+                }
             }
         };
     }
@@ -34,6 +42,7 @@ fn insert_message_declaration(
     root: &RootScope,
     message_parent_folder: &mut Folder,
     message_scope: &ProtoScope,
+    options: &CodegenOptions,
 ) -> Result<(), ProtoError> {
     let message_name = message_scope.name();
     let mut message_folder = Folder::new(message_name);
@@ -42,20 +51,36 @@ fn insert_message_declaration(
         .map(|d| d.entries.is_empty())
         .unwrap_or(false)
     {
-        insert_message_types(&root, &mut message_folder, &message_scope)?;
-        compile_encode(&root, &mut message_folder, &message_scope)?;
-        compile_decode(&root, &mut message_folder, &message_scope)?;
+        insert_message_types(root, &mut message_folder, message_scope, options)?;
+        compile_encode(root, &mut message_folder, message_scope, options)?;
+        compile_decode(root, &mut message_folder, message_scope, options)?;
+        compile_to_json(root, &mut message_folder, message_scope)?;
+        compile_from_json(root, &mut message_folder, message_scope, options)?;
     }
-    insert_children(&root, &mut message_folder, &message_scope)?;
+    insert_children(root, &mut message_folder, message_scope, options)?;
     message_parent_folder.entries.push(message_folder.into());
 
     Ok(())
 }
 
+fn insert_service_declaration(
+    root: &RootScope,
+    service_parent_folder: &mut Folder,
+    service_scope: &ProtoScope,
+) -> Result<(), ProtoError> {
+    let mut service_folder = Folder::new(service_scope.name());
+    compile_client(root, &mut service_folder, service_scope)?;
+    compile_handler(root, &mut service_folder, service_scope)?;
+    service_parent_folder.entries.push(service_folder.into());
+
+    Ok(())
+}
+
 fn insert_children(
     root: &RootScope,
     message_folder: &mut Folder,
     message_scope: &ProtoScope,
+    options: &CodegenOptions,
 ) -> Result<(), ProtoError> {
     let message_declaration = match message_scope {
         ProtoScope::Message(m) => m,
@@ -69,8 +94,9 @@ fn insert_children(
             ProtoScope::File(_) => unreachable!(),
             e @ ProtoScope::Enum(_) => insert_enum_declaration(message_folder, e),
             m @ ProtoScope::Message(_) => {
-                insert_message_declaration(&root, message_folder, m)?;
+                insert_message_declaration(root, message_folder, m, options)?;
             }
+            ProtoScope::Service(_) => unreachable!(),
         }
     }
     Ok(())
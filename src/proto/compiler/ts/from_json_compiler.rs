@@ -0,0 +1,1352 @@
+use std::{collections::BTreeSet, ops::Deref, rc::Rc};
+
+use crate::proto::{
+    error::ProtoError,
+    package::{self, Field, MessageEntry},
+    proto_scope::{ProtoScope, message::MessageScope, root_scope::RootScope},
+};
+
+use super::{
+    ast::{
+        self, BinaryOperator, ElementAccess, ExpressionChain, Folder, FunctionDeclaration,
+        MethodCall, Prop, StatementList, Type,
+    },
+    codegen_options::{CodegenOptions, Int64Mode},
+    constants::FROM_JSON_FUNCTION_NAME,
+    decode_compiler::get_default_message_value,
+    ensure_import::ensure_import,
+    get_relative_import::get_relative_import_string,
+    int64_mode::field_int64_mode,
+    service_imports::is_empty_message,
+    to_json_compiler::{
+        assign, declare_any, find_field, if_statement, import_enum, import_util, to_string,
+    },
+    ts_path::TsPath,
+};
+
+/// Generates `fromJSON.ts` parsing the proto3 JSON form into the message as `decode` returns it.
+/// Both `json_name` and original names of fields are accepted, and values of unexpected types
+/// throw errors with their paths in the JSON
+pub(super) fn compile_from_json(
+    root: &RootScope,
+    message_folder: &mut Folder,
+    message_scope: &ProtoScope,
+    options: &CodegenOptions,
+) -> Result<(), ProtoError> {
+    let message_declaration = match message_scope {
+        ProtoScope::Message(m) => m,
+        _ => unreachable!(),
+    };
+    let mut compiler = FromJsonCompiler {
+        root,
+        message_scope,
+        options,
+        file: ast::File::new(FROM_JSON_FUNCTION_NAME.into()),
+        helpers: BTreeSet::new(),
+    };
+
+    let message_type_id: Rc<ast::Identifier> = Rc::new(message_scope.name().deref().into());
+    ensure_import(
+        &mut compiler.file,
+        ast::ImportDeclaration::import(
+            vec![ast::ImportSpecifier::new(Rc::clone(&message_type_id))],
+            "./types".into(),
+        ),
+    );
+    // Defaults of repeated and map fields are `util.emptyArray` and `util.emptyObject`
+    let has_collections = message_declaration.get_fields().iter().any(|f| {
+        matches!(
+            f.field_type,
+            package::Type::Repeated(_) | package::Type::Map(_, _)
+        )
+    });
+    if has_collections {
+        import_util(&mut compiler.file);
+    }
+
+    let mut from_json_func = FunctionDeclaration::new_exported(FROM_JSON_FUNCTION_NAME);
+    from_json_func.add_param(ast::Parameter::new("json", Type::Any));
+    from_json_func.add_param(ast::Parameter::new_with_default(
+        "path",
+        Type::String,
+        ast::StringLiteral::from("$").into(),
+    ));
+    from_json_func.returns(Type::reference(message_type_id));
+    let json_expr: Rc<ast::Expression> = Rc::new("json".into());
+    let path_expr: Rc<ast::Expression> = Rc::new("path".into());
+    let message_id: Rc<ast::Identifier> = Rc::new("message".into());
+    let message_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&message_id).into());
+    // Optional fields and members of oneofs are left unset, only parsed ones are assigned
+    from_json_func.push_statement(
+        ast::VariableDeclarationList::declare_typed_const(
+            Rc::clone(&message_id),
+            Rc::new(Type::Any),
+            get_default_message_value(message_scope, options)?,
+        )
+        .into(),
+    );
+
+    let full_name = root
+        .get_declaration_path(message_declaration.id)
+        .unwrap()
+        .full_name();
+    match full_name.as_str() {
+        "google.protobuf.Timestamp" => timestamp_from_json(
+            &mut compiler,
+            &mut from_json_func,
+            &json_expr,
+            &path_expr,
+            &message_expr,
+            field_int64_mode(find_field(message_declaration, "seconds"), options)?,
+        ),
+        "google.protobuf.Duration" => duration_from_json(
+            &mut compiler,
+            &mut from_json_func,
+            &json_expr,
+            &path_expr,
+            &message_expr,
+            field_int64_mode(find_field(message_declaration, "seconds"), options)?,
+        ),
+        "google.protobuf.FieldMask" => {
+            field_mask_from_json(&mut from_json_func, &json_expr, &path_expr, &message_expr)
+        }
+        "google.protobuf.Struct" => {
+            let fields = find_field(message_declaration, "fields");
+            let (key_type, value_type) = match &fields.field_type {
+                package::Type::Map(key_type, value_type) => (key_type, value_type),
+                _ => unreachable!(),
+            };
+            let target = Rc::new(message_expr.prop(&fields.json_name()));
+            let int64 = field_int64_mode(fields, options)?;
+            for statement in
+                compiler.parse_map(key_type, value_type, int64, &json_expr, &path_expr, &target)
+            {
+                from_json_func.push_statement(statement);
+            }
+        }
+        "google.protobuf.ListValue" => {
+            let values = find_field(message_declaration, "values");
+            let element_type = match &values.field_type {
+                package::Type::Repeated(element_type) => element_type,
+                _ => unreachable!(),
+            };
+            let target = Rc::new(message_expr.prop(&values.json_name()));
+            let int64 = field_int64_mode(values, options)?;
+            for statement in
+                compiler.parse_repeated(element_type, int64, &json_expr, &path_expr, &target)
+            {
+                from_json_func.push_statement(statement);
+            }
+        }
+        "google.protobuf.Value" => {
+            let statement = value_from_json(
+                &mut compiler,
+                message_declaration,
+                &json_expr,
+                &path_expr,
+                &message_expr,
+            );
+            from_json_func.push_statement(statement);
+        }
+        "google.protobuf.DoubleValue"
+        | "google.protobuf.FloatValue"
+        | "google.protobuf.Int64Value"
+        | "google.protobuf.UInt64Value"
+        | "google.protobuf.Int32Value"
+        | "google.protobuf.UInt32Value"
+        | "google.protobuf.BoolValue"
+        | "google.protobuf.StringValue"
+        | "google.protobuf.BytesValue" => {
+            // Wrappers are read from their unwrapped values
+            let value_field = find_field(message_declaration, "value");
+            let value = compiler.parse_value(
+                &value_field.field_type,
+                field_int64_mode(value_field, options)?,
+                Rc::clone(&json_expr),
+                Rc::clone(&path_expr),
+            );
+            from_json_func.push_statement(if_statement(
+                not_null_strict(&json_expr),
+                assign(&Rc::new(message_expr.prop(&value_field.json_name())), value),
+            ));
+        }
+        _ => {
+            from_json_func.push_statement(
+                compiler
+                    .call(
+                        Helper::Object,
+                        vec![Rc::clone(&json_expr), Rc::clone(&path_expr)],
+                    )
+                    .into(),
+            );
+            let key_id: Rc<ast::Identifier> = Rc::new("key".into());
+            let key_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&key_id).into());
+            let mut for_of = ast::ForOfStatement::new(
+                Rc::clone(&key_id),
+                Rc::new(
+                    ast::Expression::from("Object")
+                        .into_method_call("keys", vec![Rc::clone(&json_expr)]),
+                ),
+            );
+            let value_id: Rc<ast::Identifier> = Rc::new("value".into());
+            for_of.push_statement(
+                ast::VariableDeclarationList::declare_const(
+                    Rc::clone(&value_id),
+                    json_expr.element(Rc::clone(&key_expr)),
+                )
+                .into(),
+            );
+            let unknown_field = throw_error(Rc::new(concat(vec![
+                Rc::clone(&path_expr),
+                Rc::new(ast::StringLiteral::from(": unknown field ").into()),
+                Rc::clone(&key_expr),
+            ])));
+            let mut switch =
+                ast::SwitchStatement::new(Rc::clone(&key_expr), vec![unknown_field].into());
+            let value_expr: Rc<ast::Expression> = Rc::new(value_id.into());
+            for entry in &message_declaration.entries {
+                let fields = match entry {
+                    MessageEntry::Field(field) => vec![field],
+                    MessageEntry::OneOf(one_of) => one_of.fields.iter().collect(),
+                    // Extensions are not read, their JSON keys need the full names
+                    MessageEntry::Extension(_) => Vec::new(),
+                };
+                for field in fields {
+                    let int64 = field_int64_mode(field, options)?;
+                    for case in
+                        compiler.field_cases(field, int64, &value_expr, &path_expr, &message_expr)
+                    {
+                        switch.add_case(case);
+                    }
+                }
+            }
+            for_of.push_statement(switch.into());
+            from_json_func.push_statement(for_of.into());
+        }
+    };
+    from_json_func.push_statement(ast::Statement::ReturnStatement(Some(message_id.into())));
+
+    let FromJsonCompiler {
+        mut file, helpers, ..
+    } = compiler;
+    file.push_statement(from_json_func.into());
+    for helper in helpers {
+        let declaration = helper_declaration(&mut file, helper);
+        file.push_statement(declaration.into());
+    }
+    message_folder.push_file(file);
+    Ok(())
+}
+
+/// Functions validating JSON values, declared in the file only if they are used
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Helper {
+    Object,
+    Array,
+    Empty,
+    Bool,
+    String,
+    Int32,
+    Uint32,
+    Number,
+    Long,
+    BigInt,
+    Bytes,
+    Enum,
+}
+
+impl Helper {
+    fn name(self) -> &'static str {
+        match self {
+            Helper::Object => "parseObject",
+            Helper::Array => "parseArray",
+            Helper::Empty => "parseEmpty",
+            Helper::Bool => "parseBool",
+            Helper::String => "parseString",
+            Helper::Int32 => "parseInt32",
+            Helper::Uint32 => "parseUint32",
+            Helper::Number => "parseNumber",
+            Helper::Long => "parseLong",
+            Helper::BigInt => "parseBigInt",
+            Helper::Bytes => "parseBytes",
+            Helper::Enum => "parseEnum",
+        }
+    }
+}
+
+struct FromJsonCompiler<'a> {
+    root: &'a RootScope,
+    message_scope: &'a ProtoScope,
+    options: &'a CodegenOptions,
+    file: ast::File,
+    helpers: BTreeSet<Helper>,
+}
+
+impl FromJsonCompiler<'_> {
+    fn call(&mut self, helper: Helper, args: Vec<Rc<ast::Expression>>) -> ast::Expression {
+        if helper == Helper::Empty {
+            self.helpers.insert(Helper::Object);
+        }
+        self.helpers.insert(helper);
+        ast::Expression::from(helper.name()).into_call(args)
+    }
+
+    /// Case clauses of the keys of the field, the JSON name and the name in the schema
+    fn field_cases(
+        &mut self,
+        field: &Field,
+        int64: Int64Mode,
+        value: &Rc<ast::Expression>,
+        path: &Rc<ast::Expression>,
+        message_expr: &Rc<ast::Expression>,
+    ) -> Vec<ast::CaseClause> {
+        let json_key = field.json_key();
+        let target = Rc::new(message_expr.prop(&field.json_name()));
+        let field_path: Rc<ast::Expression> = Rc::new(BinaryOperator::Plus.apply(
+            Rc::clone(path),
+            Rc::new(ast::StringLiteral::from(format!(".{}", json_key)).into()),
+        ));
+
+        let mut statements = Vec::new();
+        match &field.field_type {
+            package::Type::Repeated(element_type) => {
+                let p_id: Rc<ast::Identifier> = Rc::new("p".into());
+                statements.push(
+                    ast::VariableDeclarationList::declare_const(
+                        Rc::clone(&p_id),
+                        Rc::try_unwrap(field_path).unwrap(),
+                    )
+                    .into(),
+                );
+                let p_expr = Rc::new(p_id.into());
+                for statement in self.parse_repeated(element_type, int64, value, &p_expr, &target) {
+                    statements.push(statement);
+                }
+            }
+            package::Type::Map(key_type, value_type) => {
+                let p_id: Rc<ast::Identifier> = Rc::new("p".into());
+                statements.push(
+                    ast::VariableDeclarationList::declare_const(
+                        Rc::clone(&p_id),
+                        Rc::try_unwrap(field_path).unwrap(),
+                    )
+                    .into(),
+                );
+                let p_expr = Rc::new(p_id.into());
+                for statement in
+                    self.parse_map(key_type, value_type, int64, value, &p_expr, &target)
+                {
+                    statements.push(statement);
+                }
+            }
+            field_type => {
+                let parsed = self.parse_value(field_type, int64, Rc::clone(value), field_path);
+                statements.push(assign(&target, parsed));
+            }
+        }
+        // `null` is the default value of fields, except for `Value` where it is a JSON null
+        if !self.is_value_message(&field.field_type) {
+            let mut block = ast::Block::new();
+            for statement in statements.drain(..) {
+                block.push_statement(statement);
+            }
+            statements.push(if_statement(not_null_strict(value), block.into()));
+        }
+
+        let mut cases = Vec::new();
+        if json_key.deref() != field.name.deref() {
+            cases.push(ast::CaseClause::new(Rc::new(
+                ast::StringLiteral::new(Rc::clone(&field.name)).into(),
+            )));
+        }
+        let mut case = ast::CaseClause::new(Rc::new(ast::StringLiteral::new(json_key).into()));
+        for statement in statements {
+            case.push_statement(statement);
+        }
+        case.push_statement(ast::Statement::Break);
+        cases.push(case);
+        cases
+    }
+
+    /// `target = values` with elements of the JSON array parsed into `values`
+    fn parse_repeated(
+        &mut self,
+        element_type: &package::Type,
+        int64: Int64Mode,
+        value: &Rc<ast::Expression>,
+        path: &Rc<ast::Expression>,
+        target: &Rc<ast::Expression>,
+    ) -> Vec<ast::Statement> {
+        let items_id: Rc<ast::Identifier> = Rc::new("items".into());
+        let items_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&items_id).into());
+        let values_id: Rc<ast::Identifier> = Rc::new("values".into());
+        let values_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&values_id).into());
+        let i_id: Rc<ast::Identifier> = Rc::new("i".into());
+        let i_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&i_id).into());
+
+        let items = self.call(Helper::Array, vec![Rc::clone(value), Rc::clone(path)]);
+        let element_path = concat(vec![
+            Rc::clone(path),
+            Rc::new(ast::StringLiteral::from("[").into()),
+            Rc::clone(&i_expr),
+            Rc::new(ast::StringLiteral::from("]").into()),
+        ]);
+        let element = self.parse_value(
+            element_type,
+            int64,
+            Rc::new(items_expr.element(i_expr)),
+            Rc::new(element_path),
+        );
+        let mut for_stmt = ast::ForStatement::for_each(i_id, Rc::clone(&items_expr));
+        for_stmt.push_statement(values_expr.method_call("push", vec![element]).into());
+        vec![
+            ast::VariableDeclarationList::declare_const(items_id, items).into(),
+            declare_any(&values_id, true),
+            for_stmt.into(),
+            assign(target, values_expr),
+        ]
+    }
+
+    /// `target = values` with values of the JSON object parsed into `values`.
+    /// Keys of maps are always strings in JSON, integer keys are validated
+    fn parse_map(
+        &mut self,
+        key_type: &package::Type,
+        value_type: &package::Type,
+        int64: Int64Mode,
+        value: &Rc<ast::Expression>,
+        path: &Rc<ast::Expression>,
+        target: &Rc<ast::Expression>,
+    ) -> Vec<ast::Statement> {
+        let entries_id: Rc<ast::Identifier> = Rc::new("entries".into());
+        let entries_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&entries_id).into());
+        let values_id: Rc<ast::Identifier> = Rc::new("values".into());
+        let values_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&values_id).into());
+        let keys_id: Rc<ast::Identifier> = Rc::new("ks".into());
+        let keys_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&keys_id).into());
+        let i_id: Rc<ast::Identifier> = Rc::new("i".into());
+
+        let entries = self.call(Helper::Object, vec![Rc::clone(value), Rc::clone(path)]);
+        let key: Rc<ast::Expression> = Rc::new(keys_expr.element(Rc::new(Rc::clone(&i_id).into())));
+        let entry_path: Rc<ast::Expression> = Rc::new(concat(vec![
+            Rc::clone(path),
+            Rc::new(ast::StringLiteral::from(".").into()),
+            Rc::clone(&key),
+        ]));
+        let mut for_stmt = ast::ForStatement::for_each(i_id, Rc::clone(&keys_expr));
+        let parsed_key = match key_type {
+            package::Type::String => Rc::clone(&key),
+            package::Type::Bool => {
+                let is_bool = |text: &str| {
+                    Rc::new(BinaryOperator::StrictNotEqual.apply(
+                        Rc::clone(&key),
+                        Rc::new(ast::StringLiteral::from(text).into()),
+                    ))
+                };
+                for_stmt.push_statement(if_statement(
+                    Rc::new(is_bool("true").and(is_bool("false"))),
+                    throw_error(Rc::new(BinaryOperator::Plus.apply(
+                        Rc::clone(&entry_path),
+                        Rc::new(ast::StringLiteral::from(": boolean key expected").into()),
+                    ))),
+                ));
+                Rc::clone(&key)
+            }
+            // Keys of objects are strings whatever the mode is
+            key_type => self.parse_value(
+                key_type,
+                Int64Mode::String,
+                Rc::clone(&key),
+                Rc::clone(&entry_path),
+            ),
+        };
+        let parsed_value = self.parse_value(
+            value_type,
+            int64,
+            Rc::new(entries_expr.element(key)),
+            entry_path,
+        );
+        for_stmt.push_statement(assign(
+            &Rc::new(values_expr.element(parsed_key)),
+            parsed_value,
+        ));
+        vec![
+            ast::VariableDeclarationList::declare_const(entries_id, entries).into(),
+            declare_any(&values_id, false),
+            ast::VariableDeclarationList::declare_const(
+                keys_id,
+                ast::Expression::from("Object").into_method_call("keys", vec![entries_expr]),
+            )
+            .into(),
+            for_stmt.into(),
+            assign(target, values_expr),
+        ]
+    }
+
+    /// Parsed value of a singular field or of an element of a repeated field
+    fn parse_value(
+        &mut self,
+        field_type: &package::Type,
+        int64: Int64Mode,
+        value: Rc<ast::Expression>,
+        path: Rc<ast::Expression>,
+    ) -> Rc<ast::Expression> {
+        let res = match field_type {
+            package::Type::Optional(t) => return self.parse_value(t, int64, value, path),
+            // Files are not generated for messages without fields
+            package::Type::Message(m_id) if is_empty_message(self.root, *m_id) => {
+                self.call(Helper::Empty, vec![value, path])
+            }
+            package::Type::Message(m_id) => {
+                let from_json_func = self.import_from_json_func(*m_id);
+                from_json_func.into_call(vec![value, path])
+            }
+            package::Type::Enum(e_id) => {
+                let enum_expr = import_enum(self.root, self.message_scope, &mut self.file, *e_id);
+                self.call(Helper::Enum, vec![Rc::new(enum_expr), value, path])
+            }
+            package::Type::Bool => self.call(Helper::Bool, vec![value, path]),
+            package::Type::String => self.call(Helper::String, vec![value, path]),
+            package::Type::Bytes => self.call(Helper::Bytes, vec![value, path]),
+            package::Type::Double | package::Type::Float => {
+                self.call(Helper::Number, vec![value, path])
+            }
+            package::Type::Uint32 | package::Type::Fixed32 => {
+                self.call(Helper::Uint32, vec![value, path])
+            }
+            t if t.long_wire_type().is_some() => {
+                let unsigned = match t {
+                    package::Type::Uint64 | package::Type::Fixed64 => ast::Expression::True,
+                    _ => ast::Expression::False,
+                };
+                let args = vec![value, path, Rc::new(unsigned)];
+                match int64 {
+                    Int64Mode::Long => self.call(Helper::Long, args),
+                    Int64Mode::BigInt => self.call(Helper::BigInt, args),
+                    Int64Mode::String => to_string(Rc::new(self.call(Helper::BigInt, args))),
+                    Int64Mode::Number => ast::Expression::from("Number")
+                        .into_call(vec![Rc::new(self.call(Helper::BigInt, args))]),
+                }
+            }
+            package::Type::Repeated(_) | package::Type::Map(_, _) => unreachable!(),
+            _ => self.call(Helper::Int32, vec![value, path]),
+        };
+        Rc::new(res)
+    }
+
+    fn is_value_message(&self, field_type: &package::Type) -> bool {
+        match field_type {
+            package::Type::Message(m_id) => {
+                self.root.get_declaration_path(*m_id).unwrap().full_name()
+                    == "google.protobuf.Value"
+            }
+            _ => false,
+        }
+    }
+
+    fn import_from_json_func(&mut self, m_id: usize) -> ast::Expression {
+        let from_json_path = {
+            let mut res = TsPath::from(self.root.get_declaration_path(m_id).unwrap());
+            res.push_file(FROM_JSON_FUNCTION_NAME);
+            res.push_function(FROM_JSON_FUNCTION_NAME);
+            res
+        };
+        let current_file_path = {
+            let mut res = TsPath::from(
+                self.root
+                    .get_declaration_path(self.message_scope.id().unwrap())
+                    .unwrap(),
+            );
+            res.push_file(FROM_JSON_FUNCTION_NAME);
+            res
+        };
+        match get_relative_import_string(&current_file_path, &from_json_path) {
+            Some(import_string) => {
+                let imported_name = Rc::new(ast::Identifier::from(format!("f{}", m_id)));
+                ensure_import(
+                    &mut self.file,
+                    ast::ImportDeclaration::import(
+                        vec![ast::ImportSpecifier {
+                            name: Rc::clone(&imported_name),
+                            property_name: Some(Rc::new(FROM_JSON_FUNCTION_NAME.into())),
+                        }],
+                        import_string.into(),
+                    ),
+                );
+                ast::Expression::from(imported_name)
+            }
+            None => FROM_JSON_FUNCTION_NAME.into(),
+        }
+    }
+}
+
+/// `Timestamp` is read from RFC 3339 with up to 9 fractional digits and any UTC offset
+fn timestamp_from_json(
+    compiler: &mut FromJsonCompiler,
+    from_json_func: &mut FunctionDeclaration,
+    json_expr: &Rc<ast::Expression>,
+    path_expr: &Rc<ast::Expression>,
+    message_expr: &Rc<ast::Expression>,
+    seconds_int64: Int64Mode,
+) {
+    let match_id: Rc<ast::Identifier> = Rc::new("match".into());
+    let match_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&match_id).into());
+    from_json_func.push_statement(declare_match(
+        &match_id,
+        json_expr,
+        r"/^(\d{4}-\d\d-\d\dT\d\d:\d\d:\d\d)(\.\d{1,9})?(Z|[+-]\d\d:\d\d)$/",
+    ));
+    let millis_id: Rc<ast::Identifier> = Rc::new("millis".into());
+    let millis_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&millis_id).into());
+    let date = ast::Expression::from("Date").into_method_call(
+        "parse",
+        vec![Rc::new(BinaryOperator::Plus.apply(
+            Rc::new(match_expr.element(Rc::new(1f64.into()))),
+            Rc::new(match_expr.element(Rc::new(3f64.into()))),
+        ))],
+    );
+    from_json_func.push_statement(
+        ast::VariableDeclarationList::declare_const(
+            Rc::clone(&millis_id),
+            ast::Expression::conditional(
+                Rc::clone(&match_expr),
+                Rc::new(date),
+                Rc::new("NaN".into()),
+            ),
+        )
+        .into(),
+    );
+    from_json_func.push_statement(if_statement(
+        Rc::new(Rc::new(match_expr.not()).or(Rc::new(
+            ast::Expression::from("isNaN").into_call(vec![Rc::clone(&millis_expr)]),
+        ))),
+        throw_error(Rc::new(BinaryOperator::Plus.apply(
+            Rc::clone(path_expr),
+            Rc::new(ast::StringLiteral::from(": RFC 3339 timestamp expected").into()),
+        ))),
+    ));
+    let seconds = Rc::new(to_string(Rc::new(
+        ast::Expression::from("Math").into_method_call(
+            "floor",
+            vec![Rc::new(
+                BinaryOperator::Divide.apply(millis_expr, Rc::new(1000f64.into())),
+            )],
+        ),
+    )));
+    from_json_func.push_statement(assign(
+        &Rc::new(message_expr.prop("seconds")),
+        compiler.parse_value(
+            &package::Type::Int64,
+            seconds_int64,
+            seconds,
+            Rc::clone(path_expr),
+        ),
+    ));
+    let fraction = Rc::new(match_expr.element(Rc::new(2f64.into())));
+    from_json_func.push_statement(if_statement(
+        Rc::clone(&fraction),
+        assign(
+            &Rc::new(message_expr.prop("nanos")),
+            Rc::new(nanos_from_fraction(None, &fraction)),
+        ),
+    ));
+}
+
+/// `Duration` is read from seconds with up to 9 fractional digits, e.g. `-1.5s`
+fn duration_from_json(
+    compiler: &mut FromJsonCompiler,
+    from_json_func: &mut FunctionDeclaration,
+    json_expr: &Rc<ast::Expression>,
+    path_expr: &Rc<ast::Expression>,
+    message_expr: &Rc<ast::Expression>,
+    seconds_int64: Int64Mode,
+) {
+    let match_id: Rc<ast::Identifier> = Rc::new("match".into());
+    let match_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&match_id).into());
+    from_json_func.push_statement(declare_match(
+        &match_id,
+        json_expr,
+        r"/^(-?)(\d+)(\.\d{1,9})?s$/",
+    ));
+    from_json_func.push_statement(if_statement(
+        Rc::new(match_expr.not()),
+        throw_error(Rc::new(BinaryOperator::Plus.apply(
+            Rc::clone(path_expr),
+            Rc::new(ast::StringLiteral::from(": duration expected").into()),
+        ))),
+    ));
+    let sign = Rc::new(match_expr.element(Rc::new(1f64.into())));
+    let seconds = Rc::new(BinaryOperator::Plus.apply(
+        Rc::clone(&sign),
+        Rc::new(match_expr.element(Rc::new(2f64.into()))),
+    ));
+    from_json_func.push_statement(assign(
+        &Rc::new(message_expr.prop("seconds")),
+        compiler.parse_value(
+            &package::Type::Int64,
+            seconds_int64,
+            seconds,
+            Rc::clone(path_expr),
+        ),
+    ));
+    // The sign of durations shorter than a second is kept in nanos
+    let fraction = Rc::new(match_expr.element(Rc::new(3f64.into())));
+    from_json_func.push_statement(if_statement(
+        Rc::clone(&fraction),
+        assign(
+            &Rc::new(message_expr.prop("nanos")),
+            Rc::new(nanos_from_fraction(Some(sign), &fraction)),
+        ),
+    ));
+}
+
+/// `FieldMask` is read from comma separated paths in lowerCamelCase
+fn field_mask_from_json(
+    from_json_func: &mut FunctionDeclaration,
+    json_expr: &Rc<ast::Expression>,
+    path_expr: &Rc<ast::Expression>,
+    message_expr: &Rc<ast::Expression>,
+) {
+    let is_not_string = BinaryOperator::StrictNotEqual.apply(
+        Rc::new(json_expr.type_of()),
+        Rc::new(ast::StringLiteral::from("string").into()),
+    );
+    from_json_func.push_statement(if_statement(
+        Rc::new(is_not_string),
+        throw_error(Rc::new(BinaryOperator::Plus.apply(
+            Rc::clone(path_expr),
+            Rc::new(ast::StringLiteral::from(": field mask expected").into()),
+        ))),
+    ));
+    let paths_id: Rc<ast::Identifier> = Rc::new("paths".into());
+    let paths_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&paths_id).into());
+    from_json_func.push_statement(
+        ast::VariableDeclarationList::declare_typed_const(
+            Rc::clone(&paths_id),
+            Rc::new(Type::String.array()),
+            ast::Expression::conditional(
+                Rc::clone(json_expr),
+                Rc::new(
+                    json_expr
+                        .method_call("split", vec![Rc::new(ast::StringLiteral::from(",").into())]),
+                ),
+                Rc::new(Vec::new().into()),
+            ),
+        )
+        .into(),
+    );
+    let i_id: Rc<ast::Identifier> = Rc::new("i".into());
+    let path = Rc::new(paths_expr.element(Rc::new(Rc::clone(&i_id).into())));
+    let snake_case = Rc::new(path.method_call(
+        "replace",
+        vec![
+            Rc::new(ast::Expression::RegularExpressionLiteral("/[A-Z]/g".into())),
+            Rc::new(ast::StringLiteral::from("_$&").into()),
+        ],
+    ))
+    .method_call("toLowerCase", vec![]);
+    let mut for_stmt = ast::ForStatement::for_each(i_id, Rc::clone(&paths_expr));
+    for_stmt.push_statement(assign(&path, Rc::new(snake_case)));
+    from_json_func.push_statement(for_stmt.into());
+    from_json_func.push_statement(assign(&Rc::new(message_expr.prop("paths")), paths_expr));
+}
+
+/// `Value` holds the kind of the JSON value in the oneof
+fn value_from_json(
+    compiler: &mut FromJsonCompiler,
+    message_declaration: &MessageScope,
+    json_expr: &Rc<ast::Expression>,
+    path_expr: &Rc<ast::Expression>,
+    message_expr: &Rc<ast::Expression>,
+) -> ast::Statement {
+    let kind =
+        |name: &str| Rc::new(message_expr.prop(&find_field(message_declaration, name).json_name()));
+    let is_type = |type_name: &str| {
+        Rc::new(BinaryOperator::StrictEqual.apply(
+            Rc::new(json_expr.type_of()),
+            Rc::new(ast::StringLiteral::from(type_name).into()),
+        ))
+    };
+    let mut branches = vec![
+        (
+            Rc::new(
+                BinaryOperator::StrictEqual
+                    .apply(Rc::clone(json_expr), Rc::new(ast::Expression::Null)),
+            ),
+            assign(&kind("null_value"), Rc::new(0f64.into())),
+        ),
+        (
+            is_type("number"),
+            assign(&kind("number_value"), Rc::clone(json_expr)),
+        ),
+        (
+            is_type("string"),
+            assign(&kind("string_value"), Rc::clone(json_expr)),
+        ),
+        (
+            is_type("boolean"),
+            assign(&kind("bool_value"), Rc::clone(json_expr)),
+        ),
+    ];
+    for (condition, name) in [
+        (
+            Rc::new(
+                ast::Expression::from("Array")
+                    .into_method_call("isArray", vec![Rc::clone(json_expr)]),
+            ),
+            "list_value",
+        ),
+        (is_type("object"), "struct_value"),
+    ] {
+        let field = find_field(message_declaration, name);
+        let value = compiler.parse_value(
+            &field.field_type,
+            compiler.options.int64,
+            Rc::clone(json_expr),
+            Rc::clone(path_expr),
+        );
+        branches.push((condition, assign(&kind(name), value)));
+    }
+    let mut res: ast::Statement = throw_error(Rc::new(BinaryOperator::Plus.apply(
+        Rc::clone(path_expr),
+        Rc::new(ast::StringLiteral::from(": JSON value expected").into()),
+    )));
+    for (condition, statement) in branches.into_iter().rev() {
+        let mut block = ast::Block::new();
+        block.push_statement(statement);
+        res = ast::Statement::IfStatement(ast::IfStatement {
+            expression: condition,
+            then_statement: Rc::new(block.into()),
+            else_statement: Some(Rc::new(match res {
+                s @ ast::Statement::IfStatement(_) => s,
+                s => {
+                    let mut block = ast::Block::new();
+                    block.push_statement(s);
+                    block.into()
+                }
+            })),
+        });
+    }
+    res
+}
+
+/// `const match = typeof json === "string" ? /.../.exec(json) : null`
+fn declare_match(
+    match_id: &Rc<ast::Identifier>,
+    json_expr: &Rc<ast::Expression>,
+    pattern: &str,
+) -> ast::Statement {
+    let is_string = BinaryOperator::StrictEqual.apply(
+        Rc::new(json_expr.type_of()),
+        Rc::new(ast::StringLiteral::from("string").into()),
+    );
+    let exec = ast::Expression::RegularExpressionLiteral(pattern.into())
+        .into_method_call("exec", vec![Rc::clone(json_expr)]);
+    ast::VariableDeclarationList::declare_const(
+        Rc::clone(match_id),
+        ast::Expression::conditional(
+            Rc::new(is_string),
+            Rc::new(exec),
+            Rc::new(ast::Expression::Null),
+        ),
+    )
+    .into()
+}
+
+/// Nanoseconds of the fraction matched with its dot, e.g. `.5` is 500000000
+fn nanos_from_fraction(
+    sign: Option<Rc<ast::Expression>>,
+    fraction: &Rc<ast::Expression>,
+) -> ast::Expression {
+    let digits = BinaryOperator::Plus
+        .apply(
+            Rc::new(fraction.method_call("slice", vec![Rc::new(1f64.into())])),
+            Rc::new(ast::StringLiteral::from("00000000").into()),
+        )
+        .into_parentheses()
+        .into_method_call("slice", vec![Rc::new(0f64.into()), Rc::new(9f64.into())]);
+    let digits = match sign {
+        Some(sign) => BinaryOperator::Plus.apply(sign, Rc::new(digits)),
+        None => digits,
+    };
+    ast::Expression::from("Number").into_call(vec![Rc::new(digits)])
+}
+
+fn helper_declaration(file: &mut ast::File, helper: Helper) -> FunctionDeclaration {
+    let value: Rc<ast::Expression> = Rc::new("value".into());
+    let path: Rc<ast::Expression> = Rc::new("path".into());
+    let mut func = FunctionDeclaration::new(helper.name());
+    if helper == Helper::Enum {
+        func.add_param(ast::Parameter::new("values", Type::Any));
+    }
+    func.add_param(ast::Parameter::new("value", Type::Any));
+    func.add_param(ast::Parameter::new("path", Type::String));
+    let fail = |expected: &str| {
+        throw_error(Rc::new(BinaryOperator::Plus.apply(
+            Rc::clone(&path),
+            Rc::new(ast::StringLiteral::from(format!(": {} expected", expected)).into()),
+        )))
+    };
+    let type_of_is_not = |expr: &Rc<ast::Expression>, type_name: &str| {
+        Rc::new(BinaryOperator::StrictNotEqual.apply(
+            Rc::new(expr.type_of()),
+            Rc::new(ast::StringLiteral::from(type_name).into()),
+        ))
+    };
+    let is_array = |expr: &Rc<ast::Expression>| {
+        ast::Expression::from("Array").into_method_call("isArray", vec![Rc::clone(expr)])
+    };
+    let test = |pattern: &str, expr: &Rc<ast::Expression>| {
+        Rc::new(
+            ast::Expression::RegularExpressionLiteral(pattern.into())
+                .into_method_call("test", vec![Rc::clone(expr)]),
+        )
+    };
+    let n_id: Rc<ast::Identifier> = Rc::new("n".into());
+    let n: Rc<ast::Expression> = Rc::new(Rc::clone(&n_id).into());
+    // `const n = typeof value === "string" && <valid> ? Number(value) : value`
+    let declare_n = |is_valid_string: Rc<ast::Expression>| -> ast::Statement {
+        let is_string = BinaryOperator::StrictEqual.apply(
+            Rc::new(value.type_of()),
+            Rc::new(ast::StringLiteral::from("string").into()),
+        );
+        ast::VariableDeclarationList::declare_const(
+            Rc::clone(&n_id),
+            ast::Expression::conditional(
+                Rc::new(Rc::new(is_string).and(is_valid_string)),
+                Rc::new(ast::Expression::from("Number").into_call(vec![Rc::clone(&value)])),
+                Rc::clone(&value),
+            ),
+        )
+        .into()
+    };
+    // `(n | 0) !== n` and `(n >>> 0) !== n` are true for numbers out of the range of 32 bits
+    let is_not_32_bits = |expr: &Rc<ast::Expression>, operator: BinaryOperator| {
+        Rc::new(
+            BinaryOperator::StrictNotEqual.apply(
+                Rc::new(
+                    operator
+                        .apply(Rc::clone(expr), Rc::new(0f64.into()))
+                        .into_parentheses(),
+                ),
+                Rc::clone(expr),
+            ),
+        )
+    };
+
+    match helper {
+        Helper::Object => {
+            let condition = Rc::new(
+                type_of_is_not(&value, "object").or(Rc::new(
+                    BinaryOperator::StrictEqual
+                        .apply(Rc::clone(&value), Rc::new(ast::Expression::Null)),
+                )),
+            )
+            .or(Rc::new(is_array(&value)));
+            func.push_statement(if_statement(Rc::new(condition), fail("object")));
+            func.push_statement(ast::Statement::ReturnStatement(Some("value".into())));
+            func.returns(Type::Any);
+        }
+        Helper::Array => {
+            func.push_statement(if_statement(Rc::new(is_array(&value).not()), fail("array")));
+            func.push_statement(ast::Statement::ReturnStatement(Some("value".into())));
+            func.returns(Type::Any.array());
+        }
+        Helper::Empty => {
+            let keys_id: Rc<ast::Identifier> = Rc::new("keys".into());
+            let keys: Rc<ast::Expression> = Rc::new(Rc::clone(&keys_id).into());
+            func.push_statement(
+                ast::VariableDeclarationList::declare_const(
+                    keys_id,
+                    ast::Expression::from("Object").into_method_call(
+                        "keys",
+                        vec![Rc::new(
+                            ast::Expression::from(Helper::Object.name())
+                                .into_call(vec![Rc::clone(&value), Rc::clone(&path)]),
+                        )],
+                    ),
+                )
+                .into(),
+            );
+            func.push_statement(if_statement(
+                Rc::new(keys.prop("length")),
+                throw_error(Rc::new(concat(vec![
+                    Rc::clone(&path),
+                    Rc::new(ast::StringLiteral::from(": unknown field ").into()),
+                    Rc::new(keys.element(Rc::new(0f64.into()))),
+                ]))),
+            ));
+            func.push_statement(
+                ast::Expression::ObjectLiteralExpression(Vec::new()).into_return_statement(),
+            );
+            func.returns(Type::Any);
+        }
+        Helper::Bool => {
+            func.push_statement(if_statement(
+                type_of_is_not(&value, "boolean"),
+                fail("boolean"),
+            ));
+            func.push_statement(ast::Statement::ReturnStatement(Some("value".into())));
+            func.returns(Type::Boolean);
+        }
+        Helper::String => {
+            func.push_statement(if_statement(
+                type_of_is_not(&value, "string"),
+                fail("string"),
+            ));
+            func.push_statement(ast::Statement::ReturnStatement(Some("value".into())));
+            func.returns(Type::String);
+        }
+        Helper::Int32 | Helper::Uint32 => {
+            let (pattern, operator, expected) = match helper {
+                Helper::Int32 => (r"/^-?\d+$/", BinaryOperator::BinaryOr, "int32"),
+                _ => (r"/^\d+$/", BinaryOperator::UnsignedRightShift, "uint32"),
+            };
+            func.push_statement(declare_n(test(pattern, &value)));
+            func.push_statement(if_statement(
+                Rc::new(type_of_is_not(&n, "number").or(is_not_32_bits(&n, operator))),
+                fail(expected),
+            ));
+            func.push_statement(ast::Statement::ReturnStatement(Some("n".into())));
+            func.returns(Type::Number);
+        }
+        Helper::Number => {
+            // NaN and infinities are written as strings
+            let is_special = ["NaN", "Infinity", "-Infinity"]
+                .into_iter()
+                .map(|text| {
+                    Rc::new(BinaryOperator::StrictEqual.apply(
+                        Rc::clone(&value),
+                        Rc::new(ast::StringLiteral::from(text).into()),
+                    ))
+                })
+                .reduce(|left, right| Rc::new(left.or(right)))
+                .unwrap();
+            func.push_statement(if_statement(
+                is_special,
+                ast::Expression::from("Number")
+                    .into_call(vec![Rc::clone(&value)])
+                    .into_return_statement(),
+            ));
+            let is_not_blank = BinaryOperator::StrictNotEqual.apply(
+                Rc::new(value.method_call("trim", vec![])),
+                Rc::new(ast::StringLiteral::from("").into()),
+            );
+            func.push_statement(declare_n(Rc::new(is_not_blank)));
+            let is_not_finite = ast::Expression::from("isFinite")
+                .into_call(vec![Rc::clone(&n)])
+                .not();
+            func.push_statement(if_statement(
+                Rc::new(type_of_is_not(&n, "number").or(Rc::new(is_not_finite))),
+                fail("number"),
+            ));
+            func.push_statement(ast::Statement::ReturnStatement(Some("n".into())));
+            func.returns(Type::Number);
+        }
+        Helper::Long | Helper::BigInt => {
+            // Numbers are accepted only if they are written as integers
+            func.add_param(ast::Parameter::new("unsigned", Type::Boolean));
+            let unsigned: Rc<ast::Expression> = Rc::new("unsigned".into());
+            let text_id: Rc<ast::Identifier> = Rc::new("text".into());
+            let text: Rc<ast::Expression> = Rc::new(Rc::clone(&text_id).into());
+            func.push_statement(
+                ast::VariableDeclarationList::declare_const(
+                    text_id,
+                    ast::Expression::conditional(
+                        Rc::new(BinaryOperator::StrictEqual.apply(
+                            Rc::new(value.type_of()),
+                            Rc::new(ast::StringLiteral::from("number").into()),
+                        )),
+                        Rc::new(to_string(Rc::clone(&value))),
+                        Rc::clone(&value),
+                    ),
+                )
+                .into(),
+            );
+            let is_negative = BinaryOperator::StrictEqual.apply(
+                Rc::new(text.method_call("charAt", vec![Rc::new(0f64.into())])),
+                Rc::new(ast::StringLiteral::from("-").into()),
+            );
+            let condition = Rc::new(
+                type_of_is_not(&text, "string").or(Rc::new(test(r"/^-?\d+$/", &text).not())),
+            )
+            .or(Rc::new(unsigned.and(Rc::new(is_negative))));
+            let expected = ast::Expression::conditional(
+                Rc::clone(&unsigned),
+                Rc::new(ast::StringLiteral::from("uint64").into()),
+                Rc::new(ast::StringLiteral::from("int64").into()),
+            );
+            func.push_statement(if_statement(
+                Rc::new(condition),
+                throw_error(Rc::new(concat(vec![
+                    Rc::clone(&path),
+                    Rc::new(ast::StringLiteral::from(": ").into()),
+                    Rc::new(expected.into_parentheses()),
+                    Rc::new(ast::StringLiteral::from(" expected").into()),
+                ]))),
+            ));
+            if helper == Helper::BigInt {
+                // Values out of the range wrap around as `Long.fromString` does
+                let as_n = |method: &str| {
+                    Rc::new(ast::Expression::from("BigInt").into_method_call(
+                        method,
+                        vec![
+                            Rc::new(64f64.into()),
+                            Rc::new(
+                                ast::Expression::from("BigInt").into_call(vec![Rc::clone(&text)]),
+                            ),
+                        ],
+                    ))
+                };
+                func.push_statement(
+                    ast::Expression::conditional(unsigned, as_n("asUintN"), as_n("asIntN"))
+                        .into_return_statement(),
+                );
+                func.returns(Type::BigInt);
+            } else {
+                // 64-bit integers are decoded as `Long` unless long.js is missing
+                let long = Rc::new(import_util(file).prop("Long"));
+                func.push_statement(
+                    ast::Expression::conditional(
+                        Rc::clone(&long),
+                        Rc::new(long.method_call("fromString", vec![Rc::clone(&text), unsigned])),
+                        Rc::new(ast::Expression::from("Number").into_call(vec![text])),
+                    )
+                    .into_return_statement(),
+                );
+                func.returns(Type::Any);
+            }
+        }
+        Helper::Bytes => {
+            // base64url is converted to base64, and padding is optional
+            func.push_statement(if_statement(
+                type_of_is_not(&value, "string"),
+                fail("base64"),
+            ));
+            let text_id: Rc<ast::Identifier> = Rc::new("text".into());
+            let text: Rc<ast::Expression> = Rc::new(Rc::clone(&text_id).into());
+            let replace = |expr: ast::Expression, pattern: &str, replacement: &str| {
+                expr.into_method_call(
+                    "replace",
+                    vec![
+                        Rc::new(ast::Expression::RegularExpressionLiteral(pattern.into())),
+                        Rc::new(ast::StringLiteral::from(replacement).into()),
+                    ],
+                )
+            };
+            let unpadded = replace(
+                replace(
+                    replace(ast::Expression::from("value"), "/-/g", "+"),
+                    "/_/g",
+                    "/",
+                ),
+                "/=+$/",
+                "",
+            );
+            func.push_statement(
+                ast::VariableDeclarationList::declare_const(Rc::clone(&text_id), unpadded).into(),
+            );
+            let padded_id: Rc<ast::Identifier> = Rc::new("padded".into());
+            let padded: Rc<ast::Expression> = Rc::new(Rc::clone(&padded_id).into());
+            let padding = Rc::new(ast::Expression::from(ast::StringLiteral::from("===")))
+                .method_call(
+                    "slice",
+                    vec![Rc::new(
+                        BinaryOperator::Remainder.apply(
+                            Rc::new(
+                                BinaryOperator::Plus
+                                    .apply(Rc::new(text.prop("length")), Rc::new(3f64.into()))
+                                    .into_parentheses(),
+                            ),
+                            Rc::new(4f64.into()),
+                        ),
+                    )],
+                );
+            func.push_statement(
+                ast::VariableDeclarationList::declare_const(
+                    Rc::clone(&padded_id),
+                    BinaryOperator::Plus.apply(text, Rc::new(padding)),
+                )
+                .into(),
+            );
+            let util = import_util(file);
+            let base64: Rc<ast::Expression> = Rc::new(util.prop("base64"));
+            func.push_statement(if_statement(
+                Rc::new(base64.method_call("test", vec![Rc::clone(&padded)]).not()),
+                fail("base64"),
+            ));
+            let bytes_id: Rc<ast::Identifier> = Rc::new("bytes".into());
+            func.push_statement(
+                ast::VariableDeclarationList::declare_const(
+                    Rc::clone(&bytes_id),
+                    util.method_call(
+                        "newBuffer",
+                        vec![Rc::new(
+                            base64.method_call("length", vec![Rc::clone(&padded)]),
+                        )],
+                    ),
+                )
+                .into(),
+            );
+            func.push_statement(
+                base64
+                    .method_call(
+                        "decode",
+                        vec![
+                            padded,
+                            Rc::new(Rc::clone(&bytes_id).into()),
+                            Rc::new(0f64.into()),
+                        ],
+                    )
+                    .into(),
+            );
+            func.push_statement(ast::Statement::ReturnStatement(Some(bytes_id.into())));
+            func.returns(Type::from_id("Uint8Array"));
+        }
+        Helper::Enum => {
+            // Values unknown to the schema are accepted as numbers
+            let values: Rc<ast::Expression> = Rc::new("values".into());
+            let named: Rc<ast::Expression> = Rc::new(values.element(Rc::clone(&value)));
+            let is_name = Rc::new(BinaryOperator::StrictEqual.apply(
+                Rc::new(value.type_of()),
+                Rc::new(ast::StringLiteral::from("string").into()),
+            ))
+            .and(Rc::new(BinaryOperator::StrictEqual.apply(
+                Rc::new(named.type_of()),
+                Rc::new(ast::StringLiteral::from("number").into()),
+            )));
+            func.push_statement(if_statement(
+                Rc::new(is_name),
+                ast::Expression::ElementAccessExpression(ast::ElementAccessExpression {
+                    expression: values,
+                    argument: Rc::clone(&value),
+                })
+                .into_return_statement(),
+            ));
+            let is_number = Rc::new(BinaryOperator::StrictEqual.apply(
+                Rc::new(value.type_of()),
+                Rc::new(ast::StringLiteral::from("number").into()),
+            ))
+            .and(Rc::new(
+                BinaryOperator::StrictEqual.apply(
+                    Rc::new(
+                        BinaryOperator::BinaryOr
+                            .apply(Rc::clone(&value), Rc::new(0f64.into()))
+                            .into_parentheses(),
+                    ),
+                    Rc::clone(&value),
+                ),
+            ));
+            func.push_statement(if_statement(
+                Rc::new(is_number),
+                ast::Statement::ReturnStatement(Some("value".into())),
+            ));
+            func.push_statement(fail("enum value"));
+            func.returns(Type::Number);
+        }
+    }
+    func
+}
+
+/// `throw new Error(message)`
+fn throw_error(message: Rc<ast::Expression>) -> ast::Statement {
+    let mut error = ast::NewExpression::new(Rc::new("Error".into()));
+    error.add_argument(message);
+    ast::Statement::Throw(error.into())
+}
+
+fn not_null_strict(value: &Rc<ast::Expression>) -> Rc<ast::Expression> {
+    Rc::new(BinaryOperator::StrictNotEqual.apply(Rc::clone(value), Rc::new(ast::Expression::Null)))
+}
+
+fn concat(parts: Vec<Rc<ast::Expression>>) -> ast::Expression {
+    let mut parts = parts.into_iter();
+    let first = parts.next().unwrap();
+    let res = parts.fold(first, |left, right| {
+        Rc::new(BinaryOperator::Plus.apply(left, right))
+    });
+    Rc::try_unwrap(res).unwrap_or_else(ast::Expression::ParenthesizedExpression)
+}
+
+#[cfg(test)]
+mod test_from_json {
+    use crate::{GenerationOptions, test_utils::compile_files};
+
+    #[test]
+    fn accepts_both_field_names() {
+        let output = compile_files(
+            &[(
+                "app/user.proto",
+                r#"syntax = "proto3";
+package app;
+message User {
+  string user_name = 1;
+  uint64 id = 2;
+  map<int32, bytes> keys = 3;
+}
+"#,
+            )],
+            &GenerationOptions::default(),
+        );
+        let from_json = &output["app/user/User/fromJSON.ts"];
+        assert!(
+            from_json.contains("export function fromJSON(json: any, path: string = \"$\"): User {")
+        );
+        assert!(from_json.contains("      case \"user_name\":\n      case \"userName\": {\n"));
+        assert!(from_json.contains("message.id = parseLong(value, path + \".id\", true)"));
+        assert!(from_json.contains(
+            "values[parseInt32(ks[i], p + \".\" + ks[i])] = parseBytes(entries[ks[i]], p + \".\" + ks[i])"
+        ));
+        assert!(from_json.contains("function parseBytes(value: any, path: string): Uint8Array {"));
+        assert!(!from_json.contains("function parseNumber("));
+    }
+
+    #[test]
+    fn sets_only_parsed_members_of_oneofs() {
+        let output = compile_files(
+            &[(
+                "app/user.proto",
+                r#"syntax = "proto3";
+package app;
+import "google/protobuf/struct.proto";
+message User {
+  optional bool active = 1;
+  oneof contact {
+    string email = 2;
+    int32 phone = 3;
+  }
+  google.protobuf.Value extra = 4;
+}
+"#,
+            )],
+            &GenerationOptions::default(),
+        );
+        // Messages start like decoded ones, so the parsed member is the only one that is set
+        let from_json = &output["app/user/User/fromJSON.ts"];
+        assert!(
+            from_json
+                .contains("  const message: any = {\n    active: null,\n    extra: null,\n  }\n")
+        );
+        assert!(
+            from_json.contains("          message.email = parseString(value, path + \".email\")\n")
+        );
+
+        let value_from_json = &output["google/protobuf/struct/Value/fromJSON.ts"];
+        assert!(value_from_json.contains("  const message: any = {}\n"));
+        let assignments = value_from_json
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("message."))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            assignments,
+            vec![
+                "null_value = 0",
+                "number_value = json",
+                "string_value = json",
+                "bool_value = json",
+                "list_value = f5(json, path)",
+                "struct_value = f3(json, path)",
+            ]
+        );
+    }
+}
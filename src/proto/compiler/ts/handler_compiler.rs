@@ -0,0 +1,372 @@
+use std::rc::Rc;
+
+use crate::proto::{
+    error::ProtoError,
+    package::Rpc,
+    proto_scope::{ProtoScope, root_scope::RootScope},
+};
+
+use super::{
+    ast::{self, Folder, MethodCall, StatementList, Type},
+    message_name_to_encode_type_name::message_name_to_encode_type_name,
+    rpc_name_to_method_name::rpc_name_to_method_name,
+    service_imports::{
+        import_decode_func, import_encode_func, import_message_type, is_empty_message,
+    },
+    ts_path::TsPath,
+};
+
+const IMPL_PARAMETER_NAME: &str = "impl";
+const DISPATCH_FUNCTION_NAME: &str = "dispatch";
+
+/// Rpcs are dispatched by separate functions, as requests and responses are either
+/// single messages or streams
+#[derive(Debug, Clone, Copy)]
+enum DispatcherKind {
+    Unary,
+    ServerStream,
+    ClientStream,
+    BidiStream,
+}
+
+impl DispatcherKind {
+    const ALL: [DispatcherKind; 4] = [
+        DispatcherKind::Unary,
+        DispatcherKind::ServerStream,
+        DispatcherKind::ClientStream,
+        DispatcherKind::BidiStream,
+    ];
+
+    fn of(rpc: &Rpc) -> Self {
+        match (rpc.client_streaming, rpc.server_streaming) {
+            (false, false) => DispatcherKind::Unary,
+            (false, true) => DispatcherKind::ServerStream,
+            (true, false) => DispatcherKind::ClientStream,
+            (true, true) => DispatcherKind::BidiStream,
+        }
+    }
+
+    fn function_name(self) -> &'static str {
+        match self {
+            DispatcherKind::Unary => "createDispatcher",
+            DispatcherKind::ServerStream => "createStreamDispatcher",
+            DispatcherKind::ClientStream => "createClientStreamDispatcher",
+            DispatcherKind::BidiStream => "createBidiStreamDispatcher",
+        }
+    }
+
+    fn streams_requests(self) -> bool {
+        matches!(
+            self,
+            DispatcherKind::ClientStream | DispatcherKind::BidiStream
+        )
+    }
+
+    fn streams_responses(self) -> bool {
+        matches!(
+            self,
+            DispatcherKind::ServerStream | DispatcherKind::BidiStream
+        )
+    }
+}
+
+pub(super) fn compile_handler(
+    root: &RootScope,
+    service_folder: &mut Folder,
+    service_scope: &ProtoScope,
+) -> Result<(), ProtoError> {
+    let service = match service_scope {
+        ProtoScope::Service(s) => s,
+        _ => unreachable!(),
+    };
+    let mut file = ast::File::new("handler".into());
+    let service_path = root.get_declaration_path(service.id).unwrap();
+    let service_full_name = service_path.full_name();
+    let file_path = {
+        let mut res = TsPath::from(service_path);
+        res.push_file("handler");
+        res
+    };
+
+    let handler_interface_name = format!("{}Handler", service.name);
+    let mut handler_interface =
+        ast::InterfaceDeclaration::new_exported(handler_interface_name.as_str().into());
+    let mut cases_by_kind: [Vec<ast::CaseClause>; 4] = Default::default();
+    let mut decode_stream_funcs = Vec::new();
+    for rpc in service.rpcs.iter() {
+        let (method_signature, case, decode_stream_func) =
+            compile_handler_method(root, &mut file, &file_path, rpc);
+        handler_interface.members.push(method_signature.into());
+        cases_by_kind[DispatcherKind::of(rpc) as usize].push(case);
+        decode_stream_funcs.extend(decode_stream_func);
+    }
+    file.push_statement(handler_interface.into());
+
+    let handler_type = Type::from_id(&handler_interface_name);
+    for (kind, cases) in DispatcherKind::ALL.into_iter().zip(cases_by_kind) {
+        if !cases.is_empty() {
+            file.push_statement(
+                create_dispatcher(kind, &service_full_name, &handler_type, cases).into(),
+            );
+        }
+    }
+    for func in decode_stream_funcs {
+        file.push_statement(func.into());
+    }
+
+    service_folder.push_file(file);
+
+    Ok(())
+}
+
+/// Returns handler interface method, `case` of the dispatcher that calls it
+/// and the generator decoding the stream of requests of client streaming rpcs
+fn compile_handler_method(
+    root: &RootScope,
+    file: &mut ast::File,
+    file_path: &TsPath,
+    rpc: &Rpc,
+) -> (
+    ast::MethodSignature,
+    ast::CaseClause,
+    Option<ast::FunctionDeclaration>,
+) {
+    let method_name = rpc_name_to_method_name(&rpc.name);
+    let data_expr: Rc<ast::Expression> = Rc::new("data".into());
+    let impl_expr: Rc<ast::Expression> = Rc::new(IMPL_PARAMETER_NAME.into());
+
+    let (input_type, decoded_request_expr) = if is_empty_message(root, rpc.input_type) {
+        (Type::Void, None)
+    } else {
+        let input_type_name = root.get_declaration_name(rpc.input_type).unwrap();
+        let input_type =
+            import_message_type(root, file, file_path, rpc.input_type, &input_type_name);
+        let decode_func = import_decode_func(root, file, file_path, rpc.input_type);
+        (input_type, Some(decode_func))
+    };
+
+    let mut parameters = Vec::new();
+    let mut call_arguments = Vec::new();
+    let mut decode_stream_func = None;
+    if rpc.client_streaming {
+        // Streams of requests are decoded by a generator declared next to the dispatchers
+        let request_id: Rc<ast::Identifier> = Rc::new("request".into());
+        let requests_type = Type::generic("AsyncIterable", vec![input_type]);
+        parameters.push(ast::Parameter::new("requests", requests_type.clone()));
+
+        let func_name = format!("decode{}Requests", rpc.name);
+        let mut func = ast::FunctionDeclaration::new_async(&func_name);
+        func.generator = true;
+        func.add_param(ast::Parameter::new(
+            "data",
+            Type::generic("AsyncIterable", vec![Type::from_id("Uint8Array")]),
+        ));
+        func.returns(requests_type);
+        let mut for_await =
+            ast::ForOfStatement::for_await(Rc::clone(&request_id), Rc::clone(&data_expr));
+        let decoded_request = decoded_request_expr
+            .map(|decode_func| Rc::new(decode_func.into_call(vec![Rc::new(request_id.into())])));
+        for_await.push_statement(ast::Expression::Yield(decoded_request).into());
+        func.push_statement(for_await.into());
+
+        call_arguments.push(Rc::new(
+            ast::Expression::from(func_name.as_str()).into_call(vec![Rc::clone(&data_expr)]),
+        ));
+        decode_stream_func = Some(func);
+    } else if let Some(decode_func) = decoded_request_expr {
+        parameters.push(ast::Parameter::new("request", input_type));
+        call_arguments.push(Rc::new(decode_func.into_call(vec![Rc::clone(&data_expr)])));
+    }
+
+    let response_id: Rc<ast::Identifier> = Rc::new("response".into());
+    let (output_type, encoded_response_expr) = if is_empty_message(root, rpc.output_type) {
+        (
+            Type::Void,
+            ast::NewExpression::new(Rc::new("Uint8Array".into())).into(),
+        )
+    } else {
+        let output_type_name = {
+            let message_name = root.get_declaration_name(rpc.output_type).unwrap();
+            message_name_to_encode_type_name(&message_name)
+        };
+        let output_type =
+            import_message_type(root, file, file_path, rpc.output_type, &output_type_name);
+        let encoded_response_expr = import_encode_func(root, file, file_path, rpc.output_type)
+            .into_call(vec![Rc::new(Rc::clone(&response_id).into())])
+            .into_method_call("finish", vec![]);
+        (output_type, encoded_response_expr)
+    };
+
+    let impl_call = impl_expr.method_call(&method_name, call_arguments);
+    let mut case = ast::CaseClause::new(Rc::new(ast::StringLiteral::from(&rpc.name).into()));
+
+    if rpc.server_streaming {
+        let method_signature = ast::MethodSignature::new(
+            &method_name,
+            parameters,
+            Type::generic("AsyncIterable", vec![output_type]),
+        );
+        let mut for_await = ast::ForOfStatement::for_await(response_id, Rc::new(impl_call));
+        for_await
+            .push_statement(ast::Expression::Yield(Some(Rc::new(encoded_response_expr))).into());
+        case.push_statement(for_await.into());
+        case.push_statement(ast::Statement::ReturnStatement(None));
+        return (method_signature, case, decode_stream_func);
+    }
+
+    let returns_void = output_type == Type::Void;
+    let method_signature = ast::MethodSignature::new(
+        &method_name,
+        parameters,
+        Type::generic("Promise", vec![output_type]),
+    );
+    if returns_void {
+        case.push_statement(impl_call.into_await().into());
+    } else {
+        case.push_statement(
+            ast::VariableDeclarationList::declare_const(response_id, impl_call.into_await()).into(),
+        );
+    }
+    case.push_statement(encoded_response_expr.into_return_statement());
+    (method_signature, case, decode_stream_func)
+}
+
+fn create_dispatcher(
+    kind: DispatcherKind,
+    service_full_name: &str,
+    handler_type: &Type,
+    cases: Vec<ast::CaseClause>,
+) -> ast::FunctionDeclaration {
+    let bytes_type = Type::from_id("Uint8Array");
+    let method_id: Rc<ast::Identifier> = Rc::new("method".into());
+    let data_id: Rc<ast::Identifier> = Rc::new("data".into());
+    let data_type = if kind.streams_requests() {
+        Type::generic("AsyncIterable", vec![bytes_type.clone()])
+    } else {
+        bytes_type.clone()
+    };
+    let dispatch_return_type = if kind.streams_responses() {
+        Type::generic("AsyncIterable", vec![bytes_type])
+    } else {
+        Type::generic("Promise", vec![bytes_type])
+    };
+
+    let mut dispatch_func = ast::FunctionDeclaration::new_async(DISPATCH_FUNCTION_NAME);
+    dispatch_func.generator = kind.streams_responses();
+    dispatch_func.add_param(ast::Parameter::new(&method_id, Type::String));
+    dispatch_func.add_param(ast::Parameter::new(&data_id, data_type.clone()));
+    dispatch_func.returns(dispatch_return_type.clone());
+
+    let unknown_method_error = {
+        let mut error = ast::NewExpression::new(Rc::new("Error".into()));
+        error.add_argument(Rc::new(
+            ast::BinaryOperator::Plus.apply(
+                Rc::new(
+                    ast::StringLiteral::from(format!("Unknown method of {}: ", service_full_name))
+                        .into(),
+                ),
+                Rc::new(Rc::clone(&method_id).into()),
+            ),
+        ));
+        ast::Statement::Throw(error.into())
+    };
+    let mut switch = ast::SwitchStatement::new(
+        Rc::new(Rc::clone(&method_id).into()),
+        vec![unknown_method_error].into(),
+    );
+    for case in cases {
+        switch.add_case(case);
+    }
+    dispatch_func.push_statement(switch.into());
+
+    let mut create_func = ast::FunctionDeclaration::new_exported(kind.function_name());
+    create_func.add_param(ast::Parameter::new(
+        IMPL_PARAMETER_NAME,
+        handler_type.clone(),
+    ));
+    create_func.returns(Type::Function(
+        vec![(method_id, Type::String), (data_id, data_type)],
+        Box::new(dispatch_return_type),
+    ));
+    create_func.push_statement(dispatch_func.into());
+    create_func
+        .push_statement(ast::Expression::from(DISPATCH_FUNCTION_NAME).into_return_statement());
+    create_func
+}
+
+#[cfg(test)]
+mod test_handler {
+    use std::path::{Path, PathBuf};
+
+    use crate::{GenerationOptions, MemoryFileSystem, build_root_scope, compile};
+
+    #[test]
+    fn dispatches_every_rpc() {
+        let mut files = MemoryFileSystem::new();
+        files.insert(
+            "app/greeter.proto",
+            r#"syntax = "proto3";
+package app;
+message HelloRequest { string name = 1; }
+message HelloReply { string message = 1; }
+message Empty {}
+service Greeter {
+  rpc SayHello (HelloRequest) returns (HelloReply);
+  rpc Watch (Empty) returns (stream HelloReply);
+  rpc SayHellos (stream HelloRequest) returns (Empty);
+  rpc Chat (stream HelloRequest) returns (stream HelloReply);
+}
+"#,
+        );
+        let root =
+            build_root_scope(&files, &[PathBuf::from("app/greeter.proto")], &[], false).unwrap();
+        let output = compile(&root, &GenerationOptions::default()).unwrap();
+        let handler = &output[Path::new("app/greeter/Greeter/handler.ts")];
+        assert!(handler.contains(
+            "export interface GreeterHandler {
+  sayHello(request: HelloRequest): Promise<HelloReplyEncodeInput>
+  watch(): AsyncIterable<HelloReplyEncodeInput>
+  sayHellos(requests: AsyncIterable<HelloRequest>): Promise<void>
+  chat(requests: AsyncIterable<HelloRequest>): AsyncIterable<HelloReplyEncodeInput>
+}
+"
+        ));
+        assert!(handler.contains(
+            "      case \"SayHello\": {
+        const response = await impl.sayHello(d1(data))
+        return e2(response).finish()
+      }
+"
+        ));
+        assert!(handler.contains(
+            "export function createStreamDispatcher(impl: GreeterHandler): (method: string, data: Uint8Array) => AsyncIterable<Uint8Array> {"
+        ));
+        assert!(handler.contains(
+            "export function createClientStreamDispatcher(impl: GreeterHandler): (method: string, data: AsyncIterable<Uint8Array>) => Promise<Uint8Array> {
+  async function dispatch(method: string, data: AsyncIterable<Uint8Array>): Promise<Uint8Array> {
+    switch (method) {
+      case \"SayHellos\": {
+        await impl.sayHellos(decodeSayHellosRequests(data))
+        return new Uint8Array()
+      }
+"
+        ));
+        assert!(handler.contains(
+            "      case \"Chat\": {
+        for await (const response of impl.chat(decodeChatRequests(data))) {
+          yield e2(response).finish()
+        }
+        return
+      }
+"
+        ));
+        assert!(handler.contains(
+            "async function* decodeChatRequests(data: AsyncIterable<Uint8Array>): AsyncIterable<HelloRequest> {
+  for await (const request of data) {
+    yield d1(request)
+  }
+}
+"
+        ));
+    }
+}
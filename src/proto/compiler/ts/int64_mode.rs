@@ -0,0 +1,364 @@
+use std::{collections::BTreeSet, rc::Rc};
+
+use crate::proto::{
+    error::ProtoError,
+    options::{Constant, find_extension_option},
+    package::{self, Field},
+};
+
+use super::{
+    ast::{
+        self, BinaryOperator, ElementAccess, FunctionDeclaration, MethodCall, Prop, StatementList,
+        Type,
+    },
+    codegen_options::{CodegenOptions, Int64Mode},
+};
+
+/// Field option overriding the mode of the package: `[(ts.int64) = BIGINT]`
+const INT64_OPTION: [&str; 2] = ["ts", "int64"];
+
+/// Mode of 64-bit integers of the field, values of its `(ts.int64)` option are
+/// `LONG`, `BIGINT`, `STRING` and `NUMBER`
+pub(super) fn field_int64_mode(
+    field: &Field,
+    options: &CodegenOptions,
+) -> Result<Int64Mode, ProtoError> {
+    let value = match find_extension_option(&field.options, &INT64_OPTION) {
+        Some(value) => value,
+        None => return Ok(options.int64),
+    };
+    if !has_int64(&field.field_type) {
+        return Err(ProtoError::Default(format!(
+            "Option (ts.int64) cannot be set for field {} of type {}, only for 64-bit integers",
+            field.name,
+            field.field_type.to_string()
+        ))
+        .located(field.span.as_ref()));
+    }
+    let mode = match value {
+        Constant::Identifier(name) => Int64Mode::from_name(&name.to_lowercase()),
+        _ => None,
+    };
+    mode.ok_or_else(|| {
+        ProtoError::Default(format!(
+            "Value of option (ts.int64) must be LONG, BIGINT, STRING or NUMBER, got {}",
+            value
+        ))
+        .located(field.span.as_ref())
+    })
+}
+
+/// Values of the type, its elements or keys and values of the map are 64-bit integers
+pub(super) fn has_int64(field_type: &package::Type) -> bool {
+    match field_type {
+        package::Type::Optional(t) | package::Type::Repeated(t) => has_int64(t),
+        package::Type::Map(key_type, value_type) => has_int64(key_type) || has_int64(value_type),
+        t => t.long_wire_type().is_some(),
+    }
+}
+
+/// Default value of the field as `decode` sets it, optional fields are `null` unless encoded
+pub(super) fn default_value(field_type: &package::Type, mode: Int64Mode) -> ast::Expression {
+    match field_type {
+        package::Type::Optional(_) => ast::Expression::Null,
+        t if t.long_wire_type().is_none() => t.default_expression(),
+        _ => match mode {
+            Int64Mode::BigInt => big_int(0f64),
+            Int64Mode::String => ast::StringLiteral::from("0").into(),
+            Int64Mode::Long | Int64Mode::Number => 0f64.into(),
+        },
+    }
+}
+
+/// Functions converting 64-bit integers from and to the `Long`-like form of protobufjs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(super) enum Int64Helper {
+    ToLongBits,
+    ReadVarint64,
+    ReadSint64,
+    ReadFixed64,
+}
+
+impl Int64Helper {
+    fn name(self) -> &'static str {
+        match self {
+            Int64Helper::ToLongBits => "toLongBits",
+            Int64Helper::ReadVarint64 => "readVarint64",
+            Int64Helper::ReadSint64 => "readSint64",
+            Int64Helper::ReadFixed64 => "readFixed64",
+        }
+    }
+
+    fn call(self, args: Vec<Rc<ast::Expression>>) -> ast::Expression {
+        ast::Expression::from(self.name()).into_call(args)
+    }
+}
+
+/// Value passed to the writer, protobufjs accepts objects with `low` and `high` bits
+/// for 64-bit integers
+pub(super) fn encoded_value(
+    value: Rc<ast::Expression>,
+    field_type: &package::Type,
+    mode: Int64Mode,
+) -> Rc<ast::Expression> {
+    match mode {
+        Int64Mode::Long => value,
+        _ if field_type.long_wire_type().is_none() => value,
+        _ => Rc::new(Int64Helper::ToLongBits.call(vec![value])),
+    }
+}
+
+/// Value of the basic type read with the reader, 64-bit integers are read
+/// with helpers unless they are `Long`
+pub(super) fn read_value(
+    reader: &Rc<ast::Expression>,
+    field_type: &package::Type,
+    mode: Int64Mode,
+    helpers: &mut BTreeSet<Int64Helper>,
+) -> ast::Expression {
+    let mut read = |helper: Int64Helper| {
+        if helper == Int64Helper::ReadSint64 {
+            helpers.insert(Int64Helper::ReadVarint64);
+        }
+        helpers.insert(helper);
+        helper.call(vec![Rc::clone(reader)])
+    };
+    let bits = match (mode, field_type) {
+        (Int64Mode::Long, t) => return reader.method_call(&t.to_string(), vec![]),
+        (_, package::Type::Int64) => as_int_64(read(Int64Helper::ReadVarint64)),
+        (_, package::Type::Uint64) => read(Int64Helper::ReadVarint64),
+        (_, package::Type::Sint64) => read(Int64Helper::ReadSint64),
+        (_, package::Type::Fixed64) => read(Int64Helper::ReadFixed64),
+        (_, package::Type::Sfixed64) => as_int_64(read(Int64Helper::ReadFixed64)),
+        (_, t) => return reader.method_call(&t.to_string(), vec![]),
+    };
+    match mode {
+        Int64Mode::String => ast::Expression::from("String").into_call(vec![Rc::new(bits)]),
+        Int64Mode::Number => ast::Expression::from("Number").into_call(vec![Rc::new(bits)]),
+        _ => bits,
+    }
+}
+
+pub(super) fn helper_declaration(helper: Int64Helper) -> FunctionDeclaration {
+    let mut func = FunctionDeclaration::new(helper.name());
+    let r: Rc<ast::Expression> = Rc::new("r".into());
+    let bits_id: Rc<ast::Identifier> = Rc::new("bits".into());
+    let bits: Rc<ast::Expression> = Rc::new(Rc::clone(&bits_id).into());
+    let two_32 = || Rc::new(big_int(4294967296f64));
+    match helper {
+        Int64Helper::ToLongBits => {
+            func.add_param(ast::Parameter::new(
+                "value",
+                Type::BigInt.or(&Type::String).or(&Type::Number),
+            ));
+            func.push_statement(
+                ast::VariableDeclarationList::declare_const(
+                    bits_id,
+                    as_uint_64(
+                        ast::Expression::from("BigInt").into_call(vec![Rc::new("value".into())]),
+                    ),
+                )
+                .into(),
+            );
+            let to_number = |expr: ast::Expression| {
+                Rc::new(ast::Expression::from("Number").into_call(vec![Rc::new(expr)]))
+            };
+            func.push_statement(
+                ast::Expression::ObjectLiteralExpression(vec![
+                    Rc::new(ast::ObjectLiteralMember::PropertyAssignment(
+                        Rc::new("low".into()),
+                        to_number(BinaryOperator::Remainder.apply(Rc::clone(&bits), two_32())),
+                    )),
+                    Rc::new(ast::ObjectLiteralMember::PropertyAssignment(
+                        Rc::new("high".into()),
+                        to_number(BinaryOperator::Divide.apply(bits, two_32())),
+                    )),
+                ])
+                .into_return_statement(),
+            );
+            func.returns(Type::Any);
+        }
+        Int64Helper::ReadVarint64 => {
+            // `skipType` checks the bounds of the varint, its bytes are read from the last one
+            let start_id: Rc<ast::Identifier> = Rc::new("start".into());
+            let i_id: Rc<ast::Identifier> = Rc::new("i".into());
+            let i: Rc<ast::Expression> = Rc::new(Rc::clone(&i_id).into());
+            let value_id: Rc<ast::Identifier> = Rc::new("value".into());
+            let value: Rc<ast::Expression> = Rc::new(Rc::clone(&value_id).into());
+            func.add_param(ast::Parameter::new("r", Type::from_id("Reader")));
+            func.push_statement(
+                ast::VariableDeclarationList::declare_const(Rc::clone(&start_id), r.prop("pos"))
+                    .into(),
+            );
+            func.push_statement(r.method_call("skipType", vec![Rc::new(0f64.into())]).into());
+            func.push_statement(
+                ast::VariableDeclarationList::declare_let(Rc::clone(&value_id), big_int(0f64))
+                    .into(),
+            );
+            func.push_statement(
+                ast::VariableDeclarationList::declare_let(Rc::clone(&i_id), r.prop("pos")).into(),
+            );
+            let mut while_stmt = ast::WhileStatement::new(Rc::new(
+                BinaryOperator::LessThan.apply(Rc::new(start_id.into()), Rc::clone(&i)),
+            ));
+            while_stmt.push_statement(
+                BinaryOperator::Assign
+                    .apply(
+                        Rc::clone(&i),
+                        Rc::new(BinaryOperator::Minus.apply(Rc::clone(&i), Rc::new(1f64.into()))),
+                    )
+                    .into(),
+            );
+            let byte = BinaryOperator::BinaryAnd.apply(
+                Rc::new(Rc::new(r.prop("buf")).element(i)),
+                Rc::new(127f64.into()),
+            );
+            let shifted =
+                BinaryOperator::Multiply.apply(Rc::clone(&value), Rc::new(big_int(128f64)));
+            while_stmt.push_statement(
+                BinaryOperator::Assign
+                    .apply(
+                        Rc::clone(&value),
+                        Rc::new(BinaryOperator::Plus.apply(
+                            Rc::new(shifted),
+                            Rc::new(ast::Expression::from("BigInt").into_call(vec![Rc::new(byte)])),
+                        )),
+                    )
+                    .into(),
+            );
+            func.push_statement(while_stmt.into());
+            func.push_statement(as_uint_64(value_id.into()).into_return_statement());
+            func.returns(Type::BigInt);
+        }
+        Int64Helper::ReadSint64 => {
+            // Zigzag encoding maps 0, -1, 1, -2 to 0, 1, 2, 3
+            func.add_param(ast::Parameter::new("r", Type::from_id("Reader")));
+            func.push_statement(
+                ast::VariableDeclarationList::declare_const(
+                    bits_id,
+                    Int64Helper::ReadVarint64.call(vec![r]),
+                )
+                .into(),
+            );
+            let two = || Rc::new(big_int(2f64));
+            let is_even = BinaryOperator::StrictEqual.apply(
+                Rc::new(BinaryOperator::Remainder.apply(Rc::clone(&bits), two())),
+                Rc::new(big_int(0f64)),
+            );
+            let negative = BinaryOperator::Plus
+                .apply(Rc::clone(&bits), Rc::new(big_int(1f64)))
+                .into_parentheses()
+                .negate();
+            func.push_statement(
+                ast::Expression::conditional(
+                    Rc::new(is_even),
+                    Rc::new(BinaryOperator::Divide.apply(bits, two())),
+                    Rc::new(BinaryOperator::Divide.apply(Rc::new(negative), two())),
+                )
+                .into_return_statement(),
+            );
+            func.returns(Type::BigInt);
+        }
+        Int64Helper::ReadFixed64 => {
+            let low_id: Rc<ast::Identifier> = Rc::new("low".into());
+            func.add_param(ast::Parameter::new("r", Type::from_id("Reader")));
+            func.push_statement(
+                ast::VariableDeclarationList::declare_const(
+                    Rc::clone(&low_id),
+                    r.method_call("fixed32", vec![]),
+                )
+                .into(),
+            );
+            let high = ast::Expression::from("BigInt")
+                .into_call(vec![Rc::new(r.method_call("fixed32", vec![]))]);
+            func.push_statement(
+                BinaryOperator::Plus
+                    .apply(
+                        Rc::new(BinaryOperator::Multiply.apply(Rc::new(high), two_32())),
+                        Rc::new(
+                            ast::Expression::from("BigInt").into_call(vec![Rc::new(low_id.into())]),
+                        ),
+                    )
+                    .into_return_statement(),
+            );
+            func.returns(Type::BigInt);
+        }
+    }
+    func
+}
+
+/// Literals like `0n` require ES2020 target, `BigInt(0)` only requires the runtime
+fn big_int(value: f64) -> ast::Expression {
+    ast::Expression::from("BigInt").into_call(vec![Rc::new(value.into())])
+}
+
+fn as_int_64(value: ast::Expression) -> ast::Expression {
+    ast::Expression::from("BigInt")
+        .into_method_call("asIntN", vec![Rc::new(64f64.into()), Rc::new(value)])
+}
+
+fn as_uint_64(value: ast::Expression) -> ast::Expression {
+    ast::Expression::from("BigInt")
+        .into_method_call("asUintN", vec![Rc::new(64f64.into()), Rc::new(value)])
+}
+
+#[cfg(test)]
+mod test_int64_mode {
+    use crate::{
+        CodegenOptions, GenerationOptions, Int64Mode, compile,
+        test_utils::{build_files, compile_files},
+    };
+
+    #[test]
+    fn overrides_mode_of_fields() {
+        let options = GenerationOptions {
+            codegen: CodegenOptions {
+                int64: Int64Mode::BigInt,
+                ..CodegenOptions::default()
+            },
+            ..GenerationOptions::default()
+        };
+        let output = compile_files(
+            &[(
+                "app/user.proto",
+                r#"syntax = "proto3";
+package app;
+message User {
+  sint64 id = 1;
+  int64 balance = 2 [(ts.int64) = STRING];
+  fixed64 created = 3 [(ts.int64) = long];
+}
+"#,
+            )],
+            &options,
+        );
+        let types = &output["app/user/User/types.ts"];
+        assert!(types.contains("  id?: bigint | number | null\n"));
+        assert!(types.contains("  balance: string\n"));
+        assert!(types.contains("  created: number\n"));
+        let encode = &output["app/user/User/encode.ts"];
+        assert!(encode.contains("w.uint32(8).sint64(toLongBits(message.id))"));
+        assert!(encode.contains("w.uint32(25).fixed64(message.created)"));
+        let decode = &output["app/user/User/decode.ts"];
+        assert!(decode.contains("message.id = readSint64(r)"));
+        assert!(decode.contains("message.balance = String(BigInt.asIntN(64, readVarint64(r)))"));
+        assert!(decode.contains("function readVarint64(r: Reader): bigint {"));
+        assert!(!decode.contains("function readFixed64("));
+
+        let root = build_files(&[(
+            "app/user.proto",
+            r#"syntax = "proto3";
+package app;
+message User {
+  string name = 1 [(ts.int64) = STRING];
+}
+"#,
+        )]);
+        let err = compile(&root, &options).unwrap_err();
+        assert!(
+            err.message()
+                .starts_with("Option (ts.int64) cannot be set for field name")
+        );
+        assert_eq!(err.span().unwrap().start_line, 4);
+    }
+}
@@ -33,7 +33,7 @@ mod test_import_declaration {
     fn it_works() {
         let decl = Statement::ImportDeclaration(Box::new(ImportDeclaration {
             import_clause: Box::new(ImportClause {
-                name: Some(Identifier::new("google".into())),
+                name: Some(Identifier::new("google")),
                 named_bindings: Some(vec![ImportSpecifier::new_full(
                     Identifier::new("wrong").into(),
                     Some(Identifier::new("right").into()),
@@ -49,6 +49,35 @@ mod test_import_declaration {
     }
 }
 
+fn push_modifiers(res: &mut String, modifiers: &[Modifier]) {
+    for modifier in modifiers {
+        res.push_str(modifier.into());
+        res.push(' ');
+    }
+}
+
+fn push_parameters(res: &mut String, parameters: &[Parameter]) {
+    res.push('(');
+    for (ind, param) in parameters.iter().enumerate() {
+        if ind > 0 {
+            res.push_str(", ");
+        }
+        res.push_str(&param.name.text);
+        if param.optional {
+            res.push('?');
+        }
+        res.push_str(": ");
+        let type_str: String = param.parameter_type.deref().into();
+        res.push_str(type_str.as_str());
+        if let Some(initializer) = &param.initializer {
+            res.push_str(" = ");
+            let initializer_str: String = initializer.deref().into();
+            res.push_str(&initializer_str);
+        }
+    }
+    res.push(')');
+}
+
 impl From<&EnumDeclaration> for String {
     fn from(enum_declaration: &EnumDeclaration) -> Self {
         let mut res = String::new();
@@ -57,11 +86,7 @@ impl From<&EnumDeclaration> for String {
             name,
             members,
         } = enum_declaration;
-        for modifier in modifiers {
-            match modifier {
-                Modifier::Export => res.push_str("export "),
-            }
-        }
+        push_modifiers(&mut res, modifiers);
         res.push_str("enum ");
         res.push_str(&name.text);
         if members.len() <= 0 {
@@ -128,6 +153,7 @@ impl From<&Type> for String {
             Type::Any => "any".into(),
             Type::Boolean => "boolean".into(),
             Type::Number => "number".into(),
+            Type::BigInt => "bigint".into(),
             Type::String => "string".into(),
             Type::Null => "null".into(),
             Type::Never => "never".into(),
@@ -160,6 +186,25 @@ impl From<&Type> for String {
                 .map(|id| id.text.to_string())
                 .collect::<Vec<_>>()
                 .join("."),
+            Type::Generic(name, arguments) => format!(
+                "{}<{}>",
+                name.text,
+                arguments
+                    .iter()
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Type::Function(parameters, return_type) => format!(
+                "({}) => {}",
+                parameters
+                    .iter()
+                    .map(|(name, param_type)| format!("{}: {}", name.text, param_type))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                return_type
+            ),
+            Type::Void => "void".into(),
         }
     }
 }
@@ -209,11 +254,7 @@ impl From<&InterfaceDeclaration> for String {
             name,
             members,
         } = interface_declaration;
-        for modifier in modifiers {
-            match modifier {
-                Modifier::Export => res.push_str("export "),
-            }
-        }
+        push_modifiers(&mut res, modifiers);
         res.push_str("interface ");
         res.push_str(&name.text);
         if members.len() <= 0 {
@@ -234,6 +275,15 @@ impl From<&InterfaceDeclaration> for String {
                     res.push_str(type_str.as_str());
                     res.push_str("\n");
                 }
+                InterfaceMember::MethodSignature(method) => {
+                    res.push_str("  ");
+                    res.push_str(&method.name.text);
+                    push_parameters(&mut res, &method.parameters);
+                    res.push_str(": ");
+                    let type_str: String = (&method.return_type).into();
+                    res.push_str(&type_str);
+                    res.push('\n');
+                }
             }
         }
         res.push_str("}");
@@ -277,27 +327,14 @@ impl From<&FunctionDeclaration> for String {
             ..
         } = f;
 
-        for modifier in modifiers {
-            match modifier {
-                Modifier::Export => res.push_str("export "),
-            }
+        push_modifiers(&mut res, modifiers);
+        res.push_str("function");
+        if f.generator {
+            res.push('*');
         }
-        res.push_str("function ");
+        res.push(' ');
         res.push_str(&name.text);
-        res.push_str("(");
-        for (ind, param) in parameters.iter().enumerate() {
-            if ind > 0 {
-                res.push_str(", ");
-            }
-            res.push_str(&param.name.text);
-            if param.optional {
-                res.push_str("?");
-            }
-            res.push_str(": ");
-            let type_str: String = param.parameter_type.deref().into();
-            res.push_str(type_str.as_str());
-        }
-        res.push_str(")");
+        push_parameters(&mut res, parameters);
         res.push_str(": ");
         let type_str: String = return_type.into();
         res.push_str(type_str.as_str());
@@ -313,6 +350,112 @@ impl From<&FunctionDeclaration> for String {
     }
 }
 
+impl From<&MethodDeclaration> for String {
+    fn from(method: &MethodDeclaration) -> Self {
+        let mut res = String::new();
+        push_modifiers(&mut res, &method.modifiers);
+        if method.generator {
+            res.push('*');
+        }
+        res.push_str(&method.name.text);
+        push_parameters(&mut res, &method.parameters);
+        res.push_str(": ");
+        let type_str: String = (&method.return_type).into();
+        res.push_str(&type_str);
+        if method.body.statements.is_empty() {
+            res.push_str(" {}");
+            return res;
+        }
+        res.push(' ');
+        let block_str: String = (&method.body).into();
+        res.push_str(&block_str);
+        res
+    }
+}
+
+impl From<&ClassDeclaration> for String {
+    fn from(class_declaration: &ClassDeclaration) -> Self {
+        let mut res = String::new();
+        let ClassDeclaration {
+            modifiers,
+            name,
+            members,
+        } = class_declaration;
+        push_modifiers(&mut res, modifiers);
+        res.push_str("class ");
+        res.push_str(&name.text);
+        if members.is_empty() {
+            res.push_str(" {}");
+            return res;
+        }
+        res.push_str(" {\n");
+        for (ind, member) in members.iter().enumerate() {
+            let member_str: String = match member {
+                ClassMember::Property(prop) => {
+                    let mut prop_str = String::new();
+                    push_modifiers(&mut prop_str, &prop.modifiers);
+                    prop_str.push_str(&prop.name.text);
+                    prop_str.push_str(": ");
+                    let type_str: String = (&prop.property_type).into();
+                    prop_str.push_str(&type_str);
+                    prop_str
+                }
+                ClassMember::Constructor(constructor) => {
+                    let mut constructor_str = String::from("constructor");
+                    push_parameters(&mut constructor_str, &constructor.parameters);
+                    if constructor.body.statements.is_empty() {
+                        constructor_str.push_str(" {}");
+                    } else {
+                        constructor_str.push(' ');
+                        let block_str: String = (&constructor.body).into();
+                        constructor_str.push_str(&block_str);
+                    }
+                    constructor_str
+                }
+                ClassMember::Method(method) => method.into(),
+            };
+            // Addition of vertical space between methods
+            if ind > 0 && !matches!(member, ClassMember::Property(_)) {
+                res.push('\n');
+            }
+            tab_lines(&mut res, member_str);
+        }
+        res.push('}');
+        res
+    }
+}
+
+#[cfg(test)]
+mod test_class_declaration {
+    use super::*;
+    #[test]
+    fn it_works() {
+        let mut class_declaration = ClassDeclaration::new_exported("Client");
+        class_declaration.add_member(
+            PropertyDeclaration {
+                modifiers: vec![Modifier::Private, Modifier::Readonly],
+                name: "rpc".into(),
+                property_type: Type::from_id("Rpc"),
+            }
+            .into(),
+        );
+        let mut method = MethodDeclaration::new_async("list");
+        method.generator = true;
+        method.add_param(Parameter::new("request", Type::from_id("Request")));
+        method.returns(Type::generic("AsyncIterable", vec![Type::from_id("Item")]));
+        let mut for_of =
+            ForOfStatement::for_await(Rc::new("data".into()), Rc::new(Expression::from("request")));
+        for_of.push_statement(Expression::Yield(Some(Rc::new("data".into()))).into());
+        method.push_statement(for_of.into());
+        class_declaration.add_member(method.into());
+        let rendered: String = (&class_declaration).into();
+        assert_eq!(
+            rendered,
+            "export class Client {\n  private readonly rpc: Rpc\n\n  async *list(request: Request): AsyncIterable<Item> {\n    for await (const data of request) {\n      yield data\n    }\n  }\n}"
+        );
+    }
+}
+
 impl From<&PropertyAccessExpression> for String {
     fn from(decl: &PropertyAccessExpression) -> Self {
         let mut res = String::new();
@@ -448,7 +591,15 @@ impl From<&Expression> for String {
                 _ => unreachable!(),
             },
             Expression::ObjectLiteralExpression(props) => object_literal_to_string(props),
-            Expression::NewExpression(_) => todo!(),
+            Expression::NewExpression(new_expr) => {
+                let callee_str: String = new_expr.expression.deref().into();
+                let args = new_expr
+                    .arguments
+                    .iter()
+                    .map(|arg| String::from(arg.deref()))
+                    .collect::<Vec<_>>();
+                format!("new {}({})", callee_str, args.join(", "))
+            }
             Expression::NumericLiteral(f64) => f64.to_string(),
             Expression::StringLiteral(str) => to_js_string(str),
             Expression::ElementAccessExpression(element_access_expr) => element_access_expr.into(),
@@ -461,6 +612,16 @@ impl From<&Expression> for String {
                 res.push_str(&inner_str);
                 res
             }
+            Expression::Await(expr) => {
+                let inner_str: String = expr.deref().into();
+                format!("await {}", inner_str)
+            }
+            Expression::Yield(None) => "yield".to_string(),
+            Expression::Yield(Some(expr)) => {
+                let inner_str: String = expr.deref().into();
+                format!("yield {}", inner_str)
+            }
+            Expression::RegularExpressionLiteral(text) => text.to_string(),
         }
     }
 }
@@ -617,12 +778,35 @@ impl From<&ForStatement> for String {
         res
     }
 }
+impl From<&ForOfStatement> for String {
+    fn from(for_of: &ForOfStatement) -> Self {
+        let mut res = String::new();
+        res.push_str("for ");
+        if for_of.is_await {
+            res.push_str("await ");
+        }
+        res.push_str("(const ");
+        res.push_str(&for_of.name.text);
+        res.push_str(" of ");
+        let expr_str: String = for_of.expression.deref().into();
+        res.push_str(&expr_str);
+        res.push_str(") ");
+        let block_str: String = for_of.statement.deref().into();
+        res.push_str(&block_str);
+        res
+    }
+}
 impl From<&CaseClause> for String {
     fn from(c: &CaseClause) -> Self {
         let mut res = String::new();
         res.push_str("case ");
         let expr_str: String = c.expression.deref().into();
         res.push_str(&expr_str);
+        // Clauses without statements fall through to the next one
+        if c.statements.is_empty() {
+            res.push(':');
+            return res;
+        }
         res.push_str(": {\n");
         for s in &c.statements {
             tab_lines(&mut res, s.into());
@@ -676,6 +860,7 @@ impl From<&Statement> for String {
                 (interface_declaration.deref()).into()
             }
             Statement::FunctionDeclaration(func_decl) => func_decl.deref().into(),
+            Statement::ClassDeclaration(class_decl) => class_decl.deref().into(),
             Statement::ReturnStatement(Some(expression)) => {
                 let mut res = String::new();
                 res.push_str("return ");
@@ -690,9 +875,14 @@ impl From<&Statement> for String {
             Statement::Expression(expr) => expr.deref().into(),
             Statement::Empty => ";".into(),
             Statement::For(for_stmt) => for_stmt.deref().into(),
+            Statement::ForOf(for_of) => for_of.deref().into(),
             Statement::While(whl) => whl.deref().into(),
             Statement::Break => "break;".into(),
             Statement::Switch(s) => s.deref().into(),
+            Statement::Throw(expr) => {
+                let expr_str: String = expr.into();
+                format!("throw {}", expr_str)
+            }
         }
     }
 }
@@ -710,6 +900,7 @@ impl From<&File> for String {
                 (Statement::ImportDeclaration(_), Some(Statement::ImportDeclaration(_))) => {}
                 (Statement::ImportDeclaration(_), _) => res.push_str("\n"),
                 (Statement::FunctionDeclaration(_), _) => res.push_str("\n"),
+                (Statement::ClassDeclaration(_), _) => res.push('\n'),
                 (_, Some(Statement::ReturnStatement(_))) => res.push_str("\n"),
                 (&Statement::ReturnStatement(_), _) => {}
                 _ => {}
@@ -0,0 +1,7 @@
+pub(crate) fn rpc_name_to_method_name(rpc_name: &str) -> String {
+    let mut chars = rpc_name.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
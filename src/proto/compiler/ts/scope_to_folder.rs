@@ -1,20 +1,39 @@
 use std::ops::Deref;
 
-use super::{ast::*, file_to_folder::file_to_folder};
+use super::{ast::*, codegen_options::GenerationOptions, file_to_folder::file_to_folder};
 use crate::proto::{
     error::ProtoError,
     proto_scope::{root_scope::RootScope, traits::ChildrenScopes, ProtoScope},
 };
 
-fn scope_to_folder(root: &RootScope, scope: &ProtoScope) -> Result<Folder, ProtoError> {
+fn scope_to_folder(
+    root: &RootScope,
+    scope: &ProtoScope,
+    parent_package: &str,
+    options: &GenerationOptions,
+) -> Result<Folder, ProtoError> {
+    let package = match parent_package {
+        "" => scope.name().to_string(),
+        parent => format!("{}.{}", parent, scope.name()),
+    };
+    let codegen_options = options.for_package(&package);
     let mut folder = Folder::new(scope.name());
     for child in scope.children().iter() {
         let child_folder: Folder = match child.deref() {
             ProtoScope::Root(_) => unreachable!(),
-            p @ ProtoScope::Package(_) => scope_to_folder(root, p)?,
-            f @ ProtoScope::File(_) => file_to_folder(root, f)?,
+            p @ ProtoScope::Package(_) => {
+                let package_folder = scope_to_folder(root, p, &package, options)?;
+                // Package with only imported files has nothing to generate
+                if package_folder.entries.is_empty() {
+                    continue;
+                }
+                package_folder
+            }
+            ProtoScope::File(f) if !f.generated || !codegen_options.generate => continue,
+            f @ ProtoScope::File(_) => file_to_folder(root, f, &codegen_options)?,
             ProtoScope::Enum(_) => unreachable!(),
             ProtoScope::Message(_) => unreachable!(),
+            ProtoScope::Service(_) => unreachable!(),
         };
         folder.push_folder(child_folder);
     }
@@ -24,15 +43,26 @@ fn scope_to_folder(root: &RootScope, scope: &ProtoScope) -> Result<Folder, Proto
 pub(crate) fn root_scope_to_folder(
     root: &RootScope,
     folder_name: String,
+    options: &GenerationOptions,
 ) -> Result<Folder, ProtoError> {
+    let codegen_options = options.for_package("");
     let mut folder = Folder::new(folder_name.into());
     for child in root.children.iter() {
         let child_folder = match child.deref() {
             ProtoScope::Root(_) => unreachable!(),
-            package_child @ ProtoScope::Package(_) => scope_to_folder(root, package_child)?,
-            file_scope @ ProtoScope::File(_) => file_to_folder(root, file_scope)?,
-            ProtoScope::Enum(_) => todo!(),
-            ProtoScope::Message(_) => todo!(),
+            package_child @ ProtoScope::Package(_) => {
+                let package_folder = scope_to_folder(root, package_child, "", options)?;
+                if package_folder.entries.is_empty() {
+                    continue;
+                }
+                package_folder
+            }
+            ProtoScope::File(f) if !f.generated || !codegen_options.generate => continue,
+            file_scope @ ProtoScope::File(_) => file_to_folder(root, file_scope, &codegen_options)?,
+            // Declarations belong to files, the root only holds packages and files
+            ProtoScope::Enum(_) | ProtoScope::Message(_) | ProtoScope::Service(_) => {
+                unreachable!("Declaration outside of a file")
+            }
         };
         folder.push_folder(child_folder);
     }
@@ -0,0 +1,100 @@
+use std::rc::Rc;
+
+use crate::proto::proto_scope::root_scope::RootScope;
+
+use super::{
+    ast::{self, File, ImportSpecifier},
+    constants::{DECODE_FUNCTION_NAME, ENCODE_FUNCTION_NAME},
+    ensure_import::ensure_import,
+    get_relative_import::{get_relative_import, get_relative_import_string},
+    ts_path::{TsPath, TsPathComponent},
+};
+
+/// Returns true if no types, encode and decode files are generated for the message
+pub(super) fn is_empty_message(root: &RootScope, message_id: usize) -> bool {
+    root.get_declaration(message_id)
+        .and_then(|scope| {
+            scope
+                .get_message_declaration()
+                .map(|m| m.entries.is_empty())
+        })
+        .unwrap_or(false)
+}
+
+pub(super) fn import_message_type(
+    root: &RootScope,
+    file: &mut File,
+    file_path: &TsPath,
+    message_id: usize,
+    type_name: &str,
+) -> ast::Type {
+    let type_path = {
+        let mut res = TsPath::from(root.get_declaration_path(message_id).unwrap());
+        res.push_file("types");
+        res.push(TsPathComponent::Interface(type_name.into()));
+        res
+    };
+    if let Some(import_declaration) = get_relative_import(file_path, &type_path) {
+        ensure_import(file, import_declaration);
+    }
+    ast::Type::from_id(type_name)
+}
+
+pub(super) fn import_encode_func(
+    root: &RootScope,
+    file: &mut File,
+    file_path: &TsPath,
+    message_id: usize,
+) -> ast::Expression {
+    import_message_func(
+        root,
+        file,
+        file_path,
+        message_id,
+        ENCODE_FUNCTION_NAME,
+        format!("e{}", message_id),
+    )
+}
+
+pub(super) fn import_decode_func(
+    root: &RootScope,
+    file: &mut File,
+    file_path: &TsPath,
+    message_id: usize,
+) -> ast::Expression {
+    import_message_func(
+        root,
+        file,
+        file_path,
+        message_id,
+        DECODE_FUNCTION_NAME,
+        format!("d{}", message_id),
+    )
+}
+
+fn import_message_func(
+    root: &RootScope,
+    file: &mut File,
+    file_path: &TsPath,
+    message_id: usize,
+    func_name: &str,
+    imported_name: String,
+) -> ast::Expression {
+    let func_path = {
+        let mut res = TsPath::from(root.get_declaration_path(message_id).unwrap());
+        res.push_file(func_name);
+        res.push_function(func_name);
+        res
+    };
+    let import_string = get_relative_import_string(file_path, &func_path).unwrap();
+    let imported_name = Rc::new(ast::Identifier::from(imported_name));
+    let import_stmt = ast::ImportDeclaration::import(
+        vec![ImportSpecifier {
+            name: Rc::clone(&imported_name),
+            property_name: Some(Rc::new(func_name.into())),
+        }],
+        import_string.into(),
+    );
+    ensure_import(file, import_stmt);
+    ast::Expression::from(imported_name)
+}
@@ -0,0 +1,865 @@
+use std::{ops::Deref, rc::Rc};
+
+use crate::proto::{
+    error::ProtoError,
+    package::{self, Field, MessageEntry},
+    proto_scope::{ProtoScope, message::MessageScope, root_scope::RootScope},
+};
+
+use super::{
+    ast::{
+        self, BinaryOperator, ElementAccess, ExpressionChain, Folder, FunctionDeclaration,
+        MethodCall, Prop, StatementList, Type,
+    },
+    constants::{PROTOBUF_MODULE, TO_JSON_FUNCTION_NAME},
+    ensure_import::ensure_import,
+    get_relative_import::{get_relative_import, get_relative_import_string},
+    is_safe_id::is_safe_id,
+    service_imports::is_empty_message,
+    ts_path::{TsPath, TsPathComponent},
+};
+
+/// Generates `toJSON.ts` converting the decode result of the message into the proto3 JSON form.
+/// Well-known types are converted into their special JSON representations
+pub(super) fn compile_to_json(
+    root: &RootScope,
+    message_folder: &mut Folder,
+    message_scope: &ProtoScope,
+) -> Result<(), ProtoError> {
+    let message_declaration = match message_scope {
+        ProtoScope::Message(m) => m,
+        _ => unreachable!(),
+    };
+    let mut file = ast::File::new(TO_JSON_FUNCTION_NAME.into());
+
+    let message_type_id: Rc<ast::Identifier> = Rc::new(message_scope.name().deref().into());
+    ensure_import(
+        &mut file,
+        ast::ImportDeclaration::import(
+            vec![ast::ImportSpecifier::new(Rc::clone(&message_type_id))],
+            "./types".into(),
+        ),
+    );
+
+    let mut to_json_func = FunctionDeclaration::new_exported(TO_JSON_FUNCTION_NAME);
+    to_json_func.add_param(ast::Parameter::new(
+        "message",
+        Type::reference(message_type_id),
+    ));
+    let message_expr: Rc<ast::Expression> = Rc::new("message".into());
+
+    let full_name = root
+        .get_declaration_path(message_declaration.id)
+        .unwrap()
+        .full_name();
+    let return_type = match full_name.as_str() {
+        "google.protobuf.Timestamp" => {
+            timestamp_to_json(&mut to_json_func, &message_expr);
+            Type::String
+        }
+        "google.protobuf.Duration" => {
+            duration_to_json(&mut to_json_func, &message_expr);
+            Type::String
+        }
+        "google.protobuf.FieldMask" => {
+            field_mask_to_json(&mut to_json_func, &message_expr);
+            Type::String
+        }
+        "google.protobuf.Struct" => {
+            let fields = find_field(message_declaration, "fields");
+            let value_type = match &fields.field_type {
+                package::Type::Map(_, value_type) => value_type,
+                _ => unreachable!(),
+            };
+            let json_id: Rc<ast::Identifier> = Rc::new("json".into());
+            let json_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&json_id).into());
+            to_json_func.push_statement(declare_any(&json_id, false));
+            let fields_value: Rc<ast::Expression> = Rc::new(message_expr.prop("fields"));
+            let mut if_block = ast::Block::new();
+            for statement in map_entries_to_json(
+                root,
+                message_scope,
+                &mut file,
+                value_type,
+                &fields_value,
+                &json_expr,
+            ) {
+                if_block.push_statement(statement);
+            }
+            to_json_func.push_statement(if_statement(not_null(&fields_value), if_block.into()));
+            to_json_func.push_statement(ast::Statement::ReturnStatement(Some(json_id.into())));
+            Type::Any
+        }
+        "google.protobuf.ListValue" => {
+            let values = find_field(message_declaration, "values");
+            let element_type = match &values.field_type {
+                package::Type::Repeated(element_type) => element_type,
+                _ => unreachable!(),
+            };
+            let json_id: Rc<ast::Identifier> = Rc::new("json".into());
+            let json_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&json_id).into());
+            to_json_func.push_statement(declare_any(&json_id, true));
+            let values_value: Rc<ast::Expression> = Rc::new(message_expr.prop("values"));
+            let loop_statement = array_to_json(
+                root,
+                message_scope,
+                &mut file,
+                element_type,
+                &values_value,
+                &json_expr,
+            );
+            to_json_func.push_statement(if_statement(not_null(&values_value), loop_statement));
+            to_json_func.push_statement(ast::Statement::ReturnStatement(Some(json_id.into())));
+            Type::Any
+        }
+        "google.protobuf.Value" => {
+            value_to_json(
+                root,
+                message_scope,
+                &mut file,
+                &mut to_json_func,
+                message_declaration,
+                &message_expr,
+            );
+            Type::Any
+        }
+        "google.protobuf.DoubleValue"
+        | "google.protobuf.FloatValue"
+        | "google.protobuf.Int64Value"
+        | "google.protobuf.UInt64Value"
+        | "google.protobuf.Int32Value"
+        | "google.protobuf.UInt32Value"
+        | "google.protobuf.BoolValue"
+        | "google.protobuf.StringValue"
+        | "google.protobuf.BytesValue" => {
+            // Wrappers are written as their unwrapped values
+            let value_field = find_field(message_declaration, "value");
+            let json_value = json_value(
+                root,
+                message_scope,
+                &mut file,
+                &value_field.field_type,
+                Rc::new(message_expr.prop("value")),
+            );
+            to_json_func.push_statement(if_statement(
+                has_json_value(
+                    &value_field.field_type,
+                    &Rc::new(message_expr.prop("value")),
+                ),
+                return_value(json_value),
+            ));
+            to_json_func.push_statement(
+                default_json_value(&value_field.field_type).into_return_statement(),
+            );
+            Type::Any
+        }
+        _ => {
+            let json_id: Rc<ast::Identifier> = Rc::new("json".into());
+            let json_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&json_id).into());
+            to_json_func.push_statement(declare_any(&json_id, false));
+            for entry in &message_declaration.entries {
+                match entry {
+                    MessageEntry::Field(field) => compile_field_to_json(
+                        root,
+                        message_scope,
+                        &mut file,
+                        &mut to_json_func,
+                        &message_expr,
+                        &json_expr,
+                        field,
+                    ),
+                    MessageEntry::OneOf(one_of) => {
+                        for field in &one_of.fields {
+                            compile_field_to_json(
+                                root,
+                                message_scope,
+                                &mut file,
+                                &mut to_json_func,
+                                &message_expr,
+                                &json_expr,
+                                field,
+                            );
+                        }
+                    }
+                    // Extensions are not written, their JSON keys need the full names
+                    MessageEntry::Extension(_) => {}
+                }
+            }
+            to_json_func.push_statement(ast::Statement::ReturnStatement(Some(json_id.into())));
+            Type::Any
+        }
+    };
+    to_json_func.returns(return_type);
+
+    file.push_statement(to_json_func.into());
+    message_folder.push_file(file);
+    Ok(())
+}
+
+fn compile_field_to_json(
+    root: &RootScope,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    to_json_func: &mut FunctionDeclaration,
+    message_expr: &Rc<ast::Expression>,
+    json_expr: &Rc<ast::Expression>,
+    field: &Field,
+) {
+    let value: Rc<ast::Expression> = Rc::new(message_expr.prop(&field.json_name()));
+    let json_key = field.json_key();
+    let target: Rc<ast::Expression> = Rc::new(if is_safe_id(&json_key) {
+        json_expr.prop(&json_key)
+    } else {
+        json_expr.element(Rc::new(ast::StringLiteral::new(json_key).into()))
+    });
+
+    let mut block = ast::Block::new();
+    let condition = match &field.field_type {
+        package::Type::Repeated(element_type) => {
+            if !requires_conversion(element_type) {
+                block.push_statement(assign(&target, Rc::clone(&value)));
+            } else {
+                let values_id: Rc<ast::Identifier> = Rc::new("values".into());
+                let values_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&values_id).into());
+                block.push_statement(declare_any(&values_id, true));
+                block.push_statement(array_to_json(
+                    root,
+                    message_scope,
+                    file,
+                    element_type,
+                    &value,
+                    &values_expr,
+                ));
+                block.push_statement(assign(&target, values_expr));
+            }
+            not_null(&value).and(Rc::new(value.prop("length"))).into()
+        }
+        package::Type::Map(_, value_type) => {
+            if !requires_conversion(value_type) {
+                block.push_statement(assign(&target, Rc::clone(&value)));
+            } else {
+                let values_id: Rc<ast::Identifier> = Rc::new("values".into());
+                let values_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&values_id).into());
+                block.push_statement(declare_any(&values_id, false));
+                for statement in
+                    map_entries_to_json(root, message_scope, file, value_type, &value, &values_expr)
+                {
+                    block.push_statement(statement);
+                }
+                block.push_statement(assign(&target, values_expr));
+            }
+            let keys =
+                ast::Expression::from("Object").into_method_call("keys", vec![Rc::clone(&value)]);
+            not_null(&value)
+                .and(Rc::new(keys.into_prop("length")))
+                .into()
+        }
+        field_type => {
+            let json_value = json_value(root, message_scope, file, field_type, Rc::clone(&value));
+            block.push_statement(assign(&target, json_value));
+            // Members of oneofs are written whenever they are set, even with default values
+            let message_declaration = message_scope.get_message_declaration().unwrap();
+            match message_declaration.is_one_of_member(field) {
+                true => not_null(&value),
+                false => has_json_value(field_type, &value),
+            }
+        }
+    };
+    to_json_func.push_statement(if_statement(condition, block.into()));
+}
+
+/// `for (let i = 0; i < array.length; ++i) target.push(...)`
+fn array_to_json(
+    root: &RootScope,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    element_type: &package::Type,
+    array_expr: &Rc<ast::Expression>,
+    target: &Rc<ast::Expression>,
+) -> ast::Statement {
+    let i_id: Rc<ast::Identifier> = Rc::new("i".into());
+    let element = Rc::new(array_expr.element(Rc::new(Rc::clone(&i_id).into())));
+    let json_value = json_value(root, message_scope, file, element_type, element);
+    let mut for_stmt = ast::ForStatement::for_each(i_id, Rc::clone(array_expr));
+    for_stmt.push_statement(target.method_call("push", vec![json_value]).into());
+    for_stmt.into()
+}
+
+/// Copies converted values of a map, keys of maps are always strings in JSON
+fn map_entries_to_json(
+    root: &RootScope,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    value_type: &package::Type,
+    map_expr: &Rc<ast::Expression>,
+    target: &Rc<ast::Expression>,
+) -> Vec<ast::Statement> {
+    let keys_id: Rc<ast::Identifier> = Rc::new("ks".into());
+    let keys_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&keys_id).into());
+    let i_id: Rc<ast::Identifier> = Rc::new("i".into());
+    let key = Rc::new(keys_expr.element(Rc::new(Rc::clone(&i_id).into())));
+    let value = Rc::new(map_expr.element(Rc::clone(&key)));
+    let json_value = json_value(root, message_scope, file, value_type, value);
+    let mut for_stmt = ast::ForStatement::for_each(i_id, Rc::clone(&keys_expr));
+    for_stmt.push_statement(assign(&Rc::new(target.element(key)), json_value));
+    vec![
+        ast::VariableDeclarationList::declare_const(
+            keys_id,
+            ast::Expression::from("Object").into_method_call("keys", vec![Rc::clone(map_expr)]),
+        )
+        .into(),
+        for_stmt.into(),
+    ]
+}
+
+/// JSON form of a value of a singular field or of an element of a repeated field
+fn json_value(
+    root: &RootScope,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    field_type: &package::Type,
+    value: Rc<ast::Expression>,
+) -> Rc<ast::Expression> {
+    match field_type {
+        // Files are not generated for messages without fields
+        package::Type::Message(m_id) if is_empty_message(root, *m_id) => {
+            Rc::new(ast::Expression::ObjectLiteralExpression(Vec::new()))
+        }
+        package::Type::Message(m_id) => {
+            let to_json_func = import_to_json_func(root, message_scope, file, *m_id);
+            Rc::new(to_json_func.into_call(vec![value]))
+        }
+        // Values unknown to the schema are written as numbers
+        package::Type::Enum(e_id) => {
+            let enum_expr = Rc::new(import_enum(root, message_scope, file, *e_id));
+            Rc::new(
+                BinaryOperator::LogicalOr.apply(enum_expr.element(Rc::clone(&value)).into(), value),
+            )
+        }
+        // NaN and infinities are written as strings
+        package::Type::Double | package::Type::Float => Rc::new(ast::Expression::conditional(
+            Rc::new(ast::Expression::from("isFinite").into_call(vec![Rc::clone(&value)])),
+            Rc::clone(&value),
+            Rc::new(to_string(value)),
+        )),
+        t if t.long_wire_type().is_some() => Rc::new(to_string(value)),
+        package::Type::Bytes => {
+            let util_expr = import_util(file);
+            Rc::new(Rc::new(util_expr.prop("base64")).method_call(
+                "encode",
+                vec![
+                    Rc::clone(&value),
+                    Rc::new(0f64.into()),
+                    Rc::new(value.prop("length")),
+                ],
+            ))
+        }
+        package::Type::Optional(t) => json_value(root, message_scope, file, t, value),
+        package::Type::Repeated(_) | package::Type::Map(_, _) => unreachable!(),
+        _ => value,
+    }
+}
+
+/// Fields with default values are omitted, as proto3 JSON printers do by default,
+/// optional fields are written whenever they are set
+fn has_json_value(field_type: &package::Type, value: &Rc<ast::Expression>) -> Rc<ast::Expression> {
+    match field_type {
+        package::Type::Optional(_) => not_null(value),
+        package::Type::Message(_) => not_null(value),
+        package::Type::Double | package::Type::Float => Rc::new(not_null(value).and(Rc::new(
+            BinaryOperator::StrictNotEqual.apply(Rc::clone(value), Rc::new(0f64.into())),
+        ))),
+        t if t.long_wire_type().is_some() => Rc::new(not_null(value).and(Rc::new(
+            BinaryOperator::StrictNotEqual.apply(
+                Rc::new(to_string(Rc::clone(value))),
+                Rc::new(ast::StringLiteral::from("0").into()),
+            ),
+        ))),
+        package::Type::Bytes => Rc::new(not_null(value).and(Rc::new(value.prop("length")))),
+        package::Type::Repeated(_) | package::Type::Map(_, _) => unreachable!(),
+        _ => Rc::clone(value),
+    }
+}
+
+/// JSON form of the default value, written by wrappers of well-known types
+fn default_json_value(field_type: &package::Type) -> ast::Expression {
+    match field_type {
+        package::Type::Bool => ast::Expression::False,
+        package::Type::String | package::Type::Bytes => ast::StringLiteral::from("").into(),
+        t if t.long_wire_type().is_some() => ast::StringLiteral::from("0").into(),
+        _ => 0f64.into(),
+    }
+}
+
+/// Booleans, strings and 32-bit integers are the same in JSON
+fn requires_conversion(field_type: &package::Type) -> bool {
+    !matches!(
+        field_type,
+        package::Type::Bool
+            | package::Type::String
+            | package::Type::Int32
+            | package::Type::Uint32
+            | package::Type::Sint32
+            | package::Type::Fixed32
+            | package::Type::Sfixed32
+    )
+}
+
+/// `Timestamp` is written in RFC 3339 with 0, 3, 6 or 9 fractional digits, e.g. `1972-01-01T10:00:20.021Z`
+fn timestamp_to_json(to_json_func: &mut FunctionDeclaration, message_expr: &Rc<ast::Expression>) {
+    let seconds_id: Rc<ast::Identifier> = Rc::new("seconds".into());
+    to_json_func.push_statement(declare_seconds(&seconds_id, message_expr));
+    let date_id: Rc<ast::Identifier> = Rc::new("date".into());
+    let date = ast::Expression::NewExpression(ast::NewExpression {
+        expression: Rc::new("Date".into()),
+        arguments: vec![Rc::new(BinaryOperator::Multiply.apply(
+            Rc::new(Rc::clone(&seconds_id).into()),
+            Rc::new(1000f64.into()),
+        ))],
+    });
+    // `toISOString` always writes milliseconds, they are replaced with nanoseconds
+    to_json_func.push_statement(
+        ast::VariableDeclarationList::declare_const(
+            Rc::clone(&date_id),
+            Rc::new(Rc::new(date).method_call("toISOString", vec![]))
+                .method_call("slice", vec![Rc::new(0f64.into()), Rc::new(19f64.into())]),
+        )
+        .into(),
+    );
+    push_fraction_returns(
+        to_json_func,
+        Rc::new(date_id.into()),
+        Rc::new(message_expr.prop("nanos")),
+        "Z",
+    );
+}
+
+/// `Duration` is written in seconds with 0, 3, 6 or 9 fractional digits, e.g. `-1.5s`
+fn duration_to_json(to_json_func: &mut FunctionDeclaration, message_expr: &Rc<ast::Expression>) {
+    let seconds_id: Rc<ast::Identifier> = Rc::new("seconds".into());
+    let seconds_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&seconds_id).into());
+    to_json_func.push_statement(declare_seconds(&seconds_id, message_expr));
+    let nanos_id: Rc<ast::Identifier> = Rc::new("nanos".into());
+    to_json_func.push_statement(
+        ast::VariableDeclarationList::declare_const(
+            Rc::clone(&nanos_id),
+            math_abs(Rc::new(message_expr.prop("nanos"))),
+        )
+        .into(),
+    );
+    // Seconds are 0 in durations shorter than a second, the sign is then set on nanos
+    let is_negative = BinaryOperator::LogicalOr.apply(
+        Rc::new(BinaryOperator::LessThan.apply(Rc::clone(&seconds_expr), Rc::new(0f64.into()))),
+        Rc::new(
+            BinaryOperator::LessThan
+                .apply(Rc::new(message_expr.prop("nanos")), Rc::new(0f64.into())),
+        ),
+    );
+    let sign = ast::Expression::conditional(
+        Rc::new(is_negative),
+        Rc::new(ast::StringLiteral::from("-").into()),
+        Rc::new(ast::StringLiteral::from("").into()),
+    );
+    let prefix_id: Rc<ast::Identifier> = Rc::new("prefix".into());
+    to_json_func.push_statement(
+        ast::VariableDeclarationList::declare_const(
+            Rc::clone(&prefix_id),
+            BinaryOperator::Plus.apply(
+                Rc::new(sign.into_parentheses()),
+                Rc::new(to_string(Rc::new(math_abs(seconds_expr)))),
+            ),
+        )
+        .into(),
+    );
+    push_fraction_returns(
+        to_json_func,
+        Rc::new(prefix_id.into()),
+        Rc::new(nanos_id.into()),
+        "s",
+    );
+}
+
+/// `FieldMask` is written as comma separated paths in lowerCamelCase
+fn field_mask_to_json(to_json_func: &mut FunctionDeclaration, message_expr: &Rc<ast::Expression>) {
+    let paths_id: Rc<ast::Identifier> = Rc::new("paths".into());
+    let paths_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&paths_id).into());
+    to_json_func.push_statement(
+        ast::VariableDeclarationList::declare_typed_const(
+            Rc::clone(&paths_id),
+            Rc::new(Type::String.array()),
+            Vec::new().into(),
+        )
+        .into(),
+    );
+    let message_paths: Rc<ast::Expression> = Rc::new(message_expr.prop("paths"));
+    let i_id: Rc<ast::Identifier> = Rc::new("i".into());
+    let mut for_stmt = ast::ForStatement::for_each(Rc::clone(&i_id), Rc::clone(&message_paths));
+
+    let parts_id: Rc<ast::Identifier> = Rc::new("parts".into());
+    let parts_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&parts_id).into());
+    for_stmt.push_statement(
+        ast::VariableDeclarationList::declare_const(
+            Rc::clone(&parts_id),
+            Rc::new(message_paths.element(Rc::new(i_id.into())))
+                .method_call("split", vec![Rc::new(ast::StringLiteral::from("_").into())]),
+        )
+        .into(),
+    );
+    let path_id: Rc<ast::Identifier> = Rc::new("path".into());
+    let path_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&path_id).into());
+    for_stmt.push_statement(
+        ast::VariableDeclarationList::declare_let(
+            Rc::clone(&path_id),
+            parts_expr.element(Rc::new(0f64.into())),
+        )
+        .into(),
+    );
+    let j_id: Rc<ast::Identifier> = Rc::new("j".into());
+    let part: Rc<ast::Expression> = Rc::new(parts_expr.element(Rc::new(Rc::clone(&j_id).into())));
+    let mut parts_for = ast::ForStatement::for_each(Rc::clone(&j_id), Rc::clone(&parts_expr));
+    parts_for.initializer = ast::VariableDeclarationList::declare_let(j_id, 1f64.into()).into();
+    let capitalized = BinaryOperator::Plus.apply(
+        Rc::new(
+            Rc::new(part.method_call("charAt", vec![Rc::new(0f64.into())]))
+                .method_call("toUpperCase", vec![]),
+        ),
+        Rc::new(part.method_call("slice", vec![Rc::new(1f64.into())])),
+    );
+    parts_for.push_statement(assign(
+        &path_expr,
+        Rc::new(BinaryOperator::Plus.apply(Rc::clone(&path_expr), Rc::new(capitalized))),
+    ));
+    for_stmt.push_statement(parts_for.into());
+    for_stmt.push_statement(paths_expr.method_call("push", vec![path_expr]).into());
+    to_json_func.push_statement(for_stmt.into());
+    to_json_func.push_statement(
+        paths_expr
+            .method_call("join", vec![Rc::new(ast::StringLiteral::from(",").into())])
+            .into_return_statement(),
+    );
+}
+
+/// `Value` is written as the JSON value it holds
+fn value_to_json(
+    root: &RootScope,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    to_json_func: &mut FunctionDeclaration,
+    message_declaration: &MessageScope,
+    message_expr: &Rc<ast::Expression>,
+) {
+    // The member of the `kind` oneof that is set is written, `null_value` and no member are `null`
+    for name in [
+        "struct_value",
+        "list_value",
+        "number_value",
+        "string_value",
+        "bool_value",
+    ] {
+        let field = find_field(message_declaration, name);
+        let value: Rc<ast::Expression> = Rc::new(message_expr.prop(name));
+        let json_value = json_value(
+            root,
+            message_scope,
+            file,
+            &field.field_type,
+            Rc::clone(&value),
+        );
+        to_json_func.push_statement(if_statement(not_null(&value), return_value(json_value)));
+    }
+    to_json_func.push_statement(ast::Expression::Null.into_return_statement());
+}
+
+/// Returns `prefix` followed by the fraction of a second and `suffix`
+fn push_fraction_returns(
+    to_json_func: &mut FunctionDeclaration,
+    prefix: Rc<ast::Expression>,
+    nanos: Rc<ast::Expression>,
+    suffix: &str,
+) {
+    let suffix: Rc<ast::Expression> = Rc::new(ast::StringLiteral::from(suffix).into());
+    let concat = |parts: Vec<Rc<ast::Expression>>| {
+        parts
+            .into_iter()
+            .reduce(|left, right| Rc::new(BinaryOperator::Plus.apply(left, right)))
+            .unwrap()
+    };
+    let dot: Rc<ast::Expression> = Rc::new(ast::StringLiteral::from(".").into());
+
+    to_json_func.push_statement(if_statement(
+        Rc::new(nanos.not()),
+        return_value(concat(vec![Rc::clone(&prefix), Rc::clone(&suffix)])),
+    ));
+    let fraction_id: Rc<ast::Identifier> = Rc::new("fraction".into());
+    let fraction_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&fraction_id).into());
+    to_json_func.push_statement(
+        ast::VariableDeclarationList::declare_const(
+            fraction_id,
+            Rc::new(to_string(Rc::new(
+                BinaryOperator::Plus.apply(Rc::new(1_000_000_000f64.into()), Rc::clone(&nanos)),
+            )))
+            .method_call("slice", vec![Rc::new(1f64.into())]),
+        )
+        .into(),
+    );
+    for (divisor, digits) in [(1_000_000f64, 3f64), (1_000f64, 6f64)] {
+        let is_divisible = BinaryOperator::StrictEqual.apply(
+            Rc::new(BinaryOperator::Remainder.apply(Rc::clone(&nanos), Rc::new(divisor.into()))),
+            Rc::new(0f64.into()),
+        );
+        let digits = Rc::new(
+            fraction_expr.method_call("slice", vec![Rc::new(0f64.into()), Rc::new(digits.into())]),
+        );
+        to_json_func.push_statement(if_statement(
+            Rc::new(is_divisible),
+            return_value(concat(vec![
+                Rc::clone(&prefix),
+                Rc::clone(&dot),
+                digits,
+                Rc::clone(&suffix),
+            ])),
+        ));
+    }
+    to_json_func.push_statement(return_value(concat(vec![
+        prefix,
+        dot,
+        fraction_expr,
+        suffix,
+    ])));
+}
+
+/// 64-bit integers are `Long` objects in the default mode, `bigint`, strings or numbers otherwise
+fn declare_seconds(
+    seconds_id: &Rc<ast::Identifier>,
+    message_expr: &Rc<ast::Expression>,
+) -> ast::Statement {
+    let seconds: Rc<ast::Expression> = Rc::new(message_expr.prop("seconds"));
+    ast::VariableDeclarationList::declare_const(
+        Rc::clone(seconds_id),
+        ast::Expression::conditional(
+            Rc::new(BinaryOperator::StrictEqual.apply(
+                Rc::new(seconds.type_of()),
+                Rc::new(ast::StringLiteral::from("object").into()),
+            )),
+            Rc::new(seconds.method_call("toNumber", vec![])),
+            Rc::new(ast::Expression::from("Number").into_call(vec![Rc::clone(&seconds)])),
+        ),
+    )
+    .into()
+}
+
+pub(super) fn find_field<'a>(message_declaration: &'a MessageScope, name: &str) -> &'a Field {
+    message_declaration
+        .get_fields()
+        .into_iter()
+        .find(|f| f.name.deref() == name)
+        .unwrap()
+}
+
+pub(super) fn declare_any(id: &Rc<ast::Identifier>, is_array: bool) -> ast::Statement {
+    let (var_type, initializer) = match is_array {
+        true => (Type::Any.array(), Vec::new().into()),
+        false => (
+            Type::Any,
+            ast::Expression::ObjectLiteralExpression(Vec::new()),
+        ),
+    };
+    ast::VariableDeclarationList::declare_typed_const(Rc::clone(id), Rc::new(var_type), initializer)
+        .into()
+}
+
+/// Returned expressions are built for the statement, so they are normally not shared
+fn return_value(value: Rc<ast::Expression>) -> ast::Statement {
+    match Rc::try_unwrap(value) {
+        Ok(value) => value.into_return_statement(),
+        Err(value) => ast::Expression::ParenthesizedExpression(value).into_return_statement(),
+    }
+}
+
+pub(super) fn if_statement(
+    condition: Rc<ast::Expression>,
+    then_statement: ast::Statement,
+) -> ast::Statement {
+    ast::Statement::IfStatement(ast::IfStatement {
+        expression: condition,
+        then_statement: then_statement.into(),
+        else_statement: None,
+    })
+}
+
+pub(super) fn assign(target: &Rc<ast::Expression>, value: Rc<ast::Expression>) -> ast::Statement {
+    BinaryOperator::Assign
+        .apply(Rc::clone(target), value)
+        .into()
+}
+
+pub(super) fn not_null(value: &Rc<ast::Expression>) -> Rc<ast::Expression> {
+    Rc::new(BinaryOperator::WeakNotEqual.apply(Rc::clone(value), Rc::new(ast::Expression::Null)))
+}
+
+pub(super) fn to_string(value: Rc<ast::Expression>) -> ast::Expression {
+    ast::Expression::from("String").into_call(vec![value])
+}
+
+fn math_abs(value: Rc<ast::Expression>) -> ast::Expression {
+    ast::Expression::from("Math").into_method_call("abs", vec![value])
+}
+
+pub(super) fn import_util(file: &mut ast::File) -> Rc<ast::Expression> {
+    let util_id: Rc<ast::Identifier> = Rc::new("util".into());
+    ensure_import(
+        file,
+        ast::ImportDeclaration::import(
+            vec![ast::ImportSpecifier::new(Rc::clone(&util_id))],
+            PROTOBUF_MODULE.into(),
+        ),
+    );
+    Rc::new(util_id.into())
+}
+
+pub(super) fn import_enum(
+    root: &RootScope,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    enum_id: usize,
+) -> ast::Expression {
+    let enum_name = root.get_declaration_name(enum_id).unwrap();
+    let enum_ts_path = {
+        let mut res = TsPath::from(root.get_declaration_path(enum_id).unwrap());
+        res.push(TsPathComponent::Enum(Rc::clone(&enum_name)));
+        res
+    };
+    let current_file_path = {
+        let mut res = TsPath::from(
+            root.get_declaration_path(message_scope.id().unwrap())
+                .unwrap(),
+        );
+        res.push_file(&file.name);
+        res
+    };
+    if let Some(import_declaration) = get_relative_import(&current_file_path, &enum_ts_path) {
+        ensure_import(file, import_declaration);
+    }
+    ast::Expression::from(enum_name.deref())
+}
+
+fn import_to_json_func(
+    root: &RootScope,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    m_id: usize,
+) -> ast::Expression {
+    let to_json_path = {
+        let mut res = TsPath::from(root.get_declaration_path(m_id).unwrap());
+        res.push_file(TO_JSON_FUNCTION_NAME);
+        res.push_function(TO_JSON_FUNCTION_NAME);
+        res
+    };
+    let current_file_path = {
+        let mut res = TsPath::from(
+            root.get_declaration_path(message_scope.id().unwrap())
+                .unwrap(),
+        );
+        res.push_file(TO_JSON_FUNCTION_NAME);
+        res
+    };
+    match get_relative_import_string(&current_file_path, &to_json_path) {
+        Some(import_string) => {
+            let imported_name = Rc::new(ast::Identifier::from(format!("j{}", m_id)));
+            ensure_import(
+                file,
+                ast::ImportDeclaration::import(
+                    vec![ast::ImportSpecifier {
+                        name: Rc::clone(&imported_name),
+                        property_name: Some(Rc::new(TO_JSON_FUNCTION_NAME.into())),
+                    }],
+                    import_string.into(),
+                ),
+            );
+            ast::Expression::from(imported_name)
+        }
+        None => TO_JSON_FUNCTION_NAME.into(),
+    }
+}
+
+#[cfg(test)]
+mod test_to_json {
+    use crate::{GenerationOptions, test_utils::compile_files};
+
+    #[test]
+    fn omits_default_values() {
+        let output = compile_files(
+            &[(
+                "app/user.proto",
+                r#"syntax = "proto3";
+package app;
+message User {
+  enum Role { GUEST = 0; ADMIN = 1; }
+  string user_name = 1 [json_name = "login"];
+  int64 created = 2;
+  Role role = 3;
+  repeated double scores = 4;
+}
+"#,
+            )],
+            &GenerationOptions::default(),
+        );
+        let to_json = &output["app/user/User/toJSON.ts"];
+        assert!(to_json.contains("if (message.login) {\n    json.login = message.login\n"));
+        assert!(to_json.contains("json.created = String(message.created)"));
+        assert!(to_json.contains("json.role = Role[message.role] || message.role"));
+        assert!(to_json.contains("values.push(isFinite(message.scores[i])"));
+    }
+
+    #[test]
+    fn writes_fields_with_presence_when_set() {
+        let output = compile_files(
+            &[(
+                "app/user.proto",
+                r#"syntax = "proto3";
+package app;
+import "google/protobuf/struct.proto";
+message User {
+  optional bool active = 1;
+  oneof contact {
+    string email = 2;
+    int32 phone = 3;
+  }
+  google.protobuf.Value extra = 4;
+}
+"#,
+            )],
+            &GenerationOptions::default(),
+        );
+        // Members that were not decoded stay unset, so the set one is found by `!= null`
+        let decode = &output["app/user/User/decode.ts"];
+        assert!(
+            decode.contains("  const message: any = {\n    active: null,\n    extra: null,\n  }\n")
+        );
+        let to_json = &output["app/user/User/toJSON.ts"];
+        for name in ["active", "email", "phone"] {
+            assert!(to_json.contains(&format!(
+                "  if (message.{name} != null) {{\n    json.{name} = message.{name}\n"
+            )));
+        }
+
+        let value_decode = &output["google/protobuf/struct/Value/decode.ts"];
+        assert!(value_decode.contains("  const message: any = {}\n"));
+        let value_to_json = &output["google/protobuf/struct/Value/toJSON.ts"];
+        let checks = value_to_json
+            .lines()
+            .filter_map(|line| line.strip_prefix("  if (message."))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            checks,
+            vec![
+                "struct_value != null)",
+                "list_value != null)",
+                "number_value != null)",
+                "string_value != null)",
+                "bool_value != null)",
+            ]
+        );
+        assert!(value_to_json.ends_with("  return null\n}\n"));
+    }
+}
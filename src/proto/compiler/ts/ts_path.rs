@@ -111,6 +111,9 @@ impl From<ProtoPath> for TsPath {
                 PathComponent::Enum(s) => {
                     res.path.push(TsPathComponent::File(Rc::clone(&s)));
                 }
+                PathComponent::Service(s) => {
+                    res.path.push(TsPathComponent::Folder(Rc::clone(s)));
+                }
             }
         }
         res
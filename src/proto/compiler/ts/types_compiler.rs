@@ -9,9 +9,11 @@ use crate::proto::{
 
 use super::{
     ast::Folder,
+    codegen_options::{CodegenOptions, Int64Mode},
     constants::PROTOBUF_MODULE,
     ensure_import::ensure_import,
     get_relative_import::get_relative_import,
+    int64_mode::field_int64_mode,
     message_name_to_encode_type_name::message_name_to_encode_type_name,
     ts_path::{TsPath, TsPathComponent},
 };
@@ -20,11 +22,12 @@ pub(super) fn insert_message_types(
     root: &RootScope,
     message_folder: &mut Folder,
     message_scope: &ProtoScope,
+    options: &CodegenOptions,
 ) -> Result<(), ProtoError> {
     let mut file = super::ast::File::new("types".into());
 
-    insert_encoded_input_interface(&root, &mut file, &message_scope)?;
-    insert_decode_result_interface(&root, &mut file, &message_scope)?;
+    insert_encoded_input_interface(root, &mut file, message_scope, options)?;
+    insert_decode_result_interface(root, &mut file, message_scope, options)?;
 
     message_folder.push_file(file);
 
@@ -35,6 +38,7 @@ fn insert_encoded_input_interface(
     root: &RootScope,
     types_file: &mut ast::File,
     message_scope: &ProtoScope,
+    options: &CodegenOptions,
 ) -> Result<(), ProtoError> {
     let message_name = message_scope.name();
     let mut interface = ast::InterfaceDeclaration::new_exported(
@@ -47,20 +51,28 @@ fn insert_encoded_input_interface(
     for entry in &message_declaration.entries {
         match entry {
             MessageEntry::Field(f) => {
-                let property_type =
-                    import_encoding_input_type(&root, &message_scope, types_file, &f.field_type)?
-                        .or(&Type::Null);
+                let int64 = field_int64_mode(f, options)?;
+                let property_type = import_encoding_input_type(
+                    root,
+                    message_scope,
+                    types_file,
+                    &f.field_type,
+                    int64,
+                )?
+                .or(&Type::Null);
                 interface.members.push(
                     ast::PropertySignature::new_optional(f.json_name(), property_type).into(),
                 );
             }
             MessageEntry::OneOf(one_of) => {
-                for option in &one_of.options {
+                for option in &one_of.fields {
+                    let int64 = field_int64_mode(option, options)?;
                     let property_type = import_encoding_input_type(
                         &root,
                         &message_scope,
                         types_file,
                         &option.field_type,
+                        int64,
                     )?
                     .or(&Type::Null);
                     interface.members.push(
@@ -69,6 +81,20 @@ fn insert_encoded_input_interface(
                     );
                 }
             }
+            MessageEntry::Extension(f) => {
+                let int64 = field_int64_mode(f, options)?;
+                let property_type = import_encoding_input_type(
+                    root,
+                    message_scope,
+                    types_file,
+                    &f.field_type,
+                    int64,
+                )?
+                .or(&Type::Null);
+                interface.members.push(
+                    ast::PropertySignature::new_optional(f.json_name(), property_type).into(),
+                );
+            }
         }
     }
 
@@ -80,6 +106,7 @@ fn insert_decode_result_interface(
     root: &RootScope,
     types_file: &mut ast::File,
     message_scope: &ProtoScope,
+    options: &CodegenOptions,
 ) -> Result<(), ProtoError> {
     let mut interface = ast::InterfaceDeclaration::new_exported(message_scope.name().into());
     let message_declaration = match message_scope {
@@ -90,19 +117,27 @@ fn insert_decode_result_interface(
         use crate::proto::package::MessageEntry::*;
         match entry {
             Field(f) => {
-                let property_type =
-                    import_decode_result_type(&root, &message_scope, types_file, &f.field_type)?;
+                let int64 = field_int64_mode(f, options)?;
+                let property_type = import_decode_result_type(
+                    root,
+                    message_scope,
+                    types_file,
+                    &f.field_type,
+                    int64,
+                )?;
                 interface
                     .members
                     .push(ast::PropertySignature::new(f.json_name(), property_type).into())
             }
             OneOf(one_of) => {
-                for option in &one_of.options {
+                for option in &one_of.fields {
+                    let int64 = field_int64_mode(option, options)?;
                     let property_type = import_decode_result_type(
                         &root,
                         &message_scope,
                         types_file,
                         &option.field_type,
+                        int64,
                     )?
                     .or(&Type::Null);
                     interface.members.push(
@@ -111,6 +146,19 @@ fn insert_decode_result_interface(
                     );
                 }
             }
+            Extension(f) => {
+                let int64 = field_int64_mode(f, options)?;
+                let property_type = import_decode_result_type(
+                    root,
+                    message_scope,
+                    types_file,
+                    &f.field_type,
+                    int64,
+                )?;
+                interface
+                    .members
+                    .push(ast::PropertySignature::new_optional(f.json_name(), property_type).into())
+            }
         }
     }
 
@@ -123,6 +171,7 @@ fn import_encoding_input_type(
     message_scope: &ProtoScope,
     types_file: &mut ast::File,
     field_type: &package::Type,
+    int64: Int64Mode,
 ) -> Result<Type, ProtoError> {
     match field_type {
         package::Type::Enum(e_id) => import_enum_type(root, message_scope, types_file, *e_id),
@@ -141,30 +190,33 @@ fn import_encoding_input_type(
         }
         package::Type::Repeated(field_type) => {
             let element_type =
-                import_encoding_input_type(root, message_scope, types_file, field_type)?;
+                import_encoding_input_type(root, message_scope, types_file, field_type, int64)?;
             return Ok(Type::array(element_type));
         }
         package::Type::Optional(field_type) => {
             let element_type =
-                import_encoding_input_type(root, message_scope, types_file, field_type)?;
+                import_encoding_input_type(root, message_scope, types_file, field_type, int64)?;
             return Ok(element_type.nullable());
         }
         package::Type::Map(key, value) => {
             let key_type = resolve_key_type(key);
-            let value_type = import_encoding_input_type(root, message_scope, types_file, value)?;
+            let value_type =
+                import_encoding_input_type(root, message_scope, types_file, value, int64)?;
             return Ok(Type::Record(Box::new(key_type), Box::new(value_type)));
         }
         package::Type::Bool => Ok(Type::Boolean),
         package::Type::Bytes => Ok(Type::reference(ast::Identifier::new("Uint8Array").into())),
         package::Type::Double => Ok(Type::Number),
         package::Type::Fixed32 => Ok(Type::Number),
-        package::Type::Fixed64 => Ok(Type::Number),
+        package::Type::Fixed64 if int64 == Int64Mode::Long => Ok(Type::Number),
         package::Type::Float => Ok(Type::Number),
         package::Type::Int32 => Ok(Type::Number),
         package::Type::Int64
         | package::Type::Sfixed64
         | package::Type::Sint64
-        | package::Type::Uint64 => {
+        | package::Type::Uint64
+            if int64 == Int64Mode::Long =>
+        {
             let util_id: Rc<ast::Identifier> = Rc::new("util".into());
             let util_import = ast::ImportDeclaration::import(
                 vec![ast::ImportSpecifier::new(Rc::clone(&util_id))],
@@ -177,6 +229,15 @@ fn import_encoding_input_type(
             ])
             .or(&Type::Number))
         }
+        // `number` is accepted as `bigint` like it is accepted as `Long`
+        package::Type::Fixed64
+        | package::Type::Int64
+        | package::Type::Sfixed64
+        | package::Type::Sint64
+        | package::Type::Uint64 => Ok(match int64_type(int64) {
+            Type::BigInt => Type::BigInt.or(&Type::Number),
+            t => t,
+        }),
         package::Type::Sfixed32 => Ok(Type::Number),
         package::Type::Sint32 => Ok(Type::Number),
         package::Type::String => Ok(Type::String),
@@ -184,6 +245,15 @@ fn import_encoding_input_type(
     }
 }
 
+/// Type of 64-bit integers which are not `Long`
+fn int64_type(int64: Int64Mode) -> Type {
+    match int64 {
+        Int64Mode::BigInt => Type::BigInt,
+        Int64Mode::String => Type::String,
+        Int64Mode::Long | Int64Mode::Number => Type::Number,
+    }
+}
+
 fn resolve_key_type(key: &Rc<package::Type>) -> Type {
     match key.deref() {
         package::Type::Message(_) => unreachable!(),
@@ -199,6 +269,7 @@ fn import_decode_result_type(
     message_scope: &ProtoScope,
     types_file: &mut ast::File,
     field_type: &package::Type,
+    int64: Int64Mode,
 ) -> Result<Type, ProtoError> {
     match field_type {
         package::Type::Enum(e_id) => import_enum_type(root, message_scope, types_file, *e_id),
@@ -211,13 +282,15 @@ fn import_decode_result_type(
         package::Type::Bytes => Ok(Type::reference(ast::Identifier::new("Uint8Array").into())),
         package::Type::Double => Ok(Type::Number),
         package::Type::Fixed32 => Ok(Type::Number),
-        package::Type::Fixed64 => Ok(Type::Number),
+        package::Type::Fixed64 if int64 == Int64Mode::Long => Ok(Type::Number),
         package::Type::Float => Ok(Type::Number),
         package::Type::Int32 => Ok(Type::Number),
         package::Type::Int64
         | package::Type::Sfixed64
         | package::Type::Sint64
-        | package::Type::Uint64 => {
+        | package::Type::Uint64
+            if int64 == Int64Mode::Long =>
+        {
             let util_id: Rc<ast::Identifier> = Rc::new("util".into());
             let util_import = ast::ImportDeclaration::import(
                 vec![ast::ImportSpecifier::new(Rc::clone(&util_id))],
@@ -229,6 +302,11 @@ fn import_decode_result_type(
                 Rc::new(ast::Identifier::new("Long")),
             ]))
         }
+        package::Type::Fixed64
+        | package::Type::Int64
+        | package::Type::Sfixed64
+        | package::Type::Sint64
+        | package::Type::Uint64 => Ok(int64_type(int64)),
         package::Type::Sfixed32 => Ok(Type::Number),
         package::Type::Sint32 => Ok(Type::Number),
         package::Type::String => Ok(Type::String),
@@ -236,17 +314,18 @@ fn import_decode_result_type(
 
         package::Type::Repeated(field_type) => {
             let element_type =
-                import_decode_result_type(root, message_scope, types_file, field_type)?;
+                import_decode_result_type(root, message_scope, types_file, field_type, int64)?;
             return Ok(element_type.array());
         }
         package::Type::Optional(field_type) => {
             let element_type =
-                import_decode_result_type(root, message_scope, types_file, field_type)?;
+                import_decode_result_type(root, message_scope, types_file, field_type, int64)?;
             return Ok(element_type.nullable());
         }
         package::Type::Map(key, value) => {
             let key_type = resolve_key_type(key);
-            let value_type = import_decode_result_type(root, message_scope, types_file, value)?;
+            let value_type =
+                import_decode_result_type(root, message_scope, types_file, value, int64)?;
             return Ok(Type::Record(Box::new(key_type), Box::new(value_type)));
         }
     }
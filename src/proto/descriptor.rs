@@ -0,0 +1,1074 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use path_clean::clean;
+use prost::Message;
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+    FileDescriptorSet, ServiceDescriptorProto, UninterpretedOption,
+    field_descriptor_proto::{Label, Type},
+    uninterpreted_option::NamePart,
+};
+
+use super::{
+    error::ProtoError,
+    id_generator::IdGenerator,
+    options::{Constant, OptionName, OptionNamePart, ProtoOption},
+    package::{
+        Declaration, EnumDeclaration, EnumEntry, ExtendDeclaration, ExtensionsDeclaration,
+        FieldDeclaration, FieldRange, FieldTypeReference, ImportPath, MessageDeclaration,
+        MessageDeclarationEntry, OneOfDeclaration, ProtoFile, ProtoVersion, ReservedDeclaration,
+        RpcDeclaration, ServiceDeclaration, load_parsed_file,
+    },
+    proto_scope::{
+        builder::{
+            ScopeBuilder, ScopeBuilderTrait,
+            well_known::{is_descriptor_import, is_well_known_import},
+        },
+        root_scope::RootScope,
+        validation::validate,
+    },
+    syntax::parse_aggregate_text,
+};
+
+/// Builds the root scope of files described by protoc, dependencies come before the files
+/// importing them. Code is generated only for files for which `generated` returns true.
+/// Well known files are replaced by the built-in ones
+pub(crate) fn read_descriptor_root_scope(
+    files: &[FileDescriptorProto],
+    generated: impl Fn(&FileDescriptorProto) -> bool,
+) -> Result<RootScope, ProtoError> {
+    let builder = ScopeBuilder::new_ref();
+    let mut id_generator = IdGenerator::new();
+    let mut errors = Vec::new();
+    let mut loaded: HashSet<PathBuf> = HashSet::new();
+    for file in files {
+        let import_path = to_import_path(file.name());
+        if is_well_known_import(&import_path) || is_descriptor_import(&import_path) {
+            continue;
+        }
+        if !loaded.insert(import_path.to_path_buf()) {
+            continue;
+        }
+        let loaded_file =
+            descriptor_to_proto_file(&mut id_generator, file).and_then(|proto_file| {
+                load_parsed_file(&builder, &mut id_generator, proto_file, generated(file))
+            });
+        if let Err(err) = loaded_file {
+            errors.push(err);
+        }
+    }
+    ProtoError::from_errors(errors)?;
+    let root = builder.finish()?;
+    validate(&root)?;
+    Ok(root)
+}
+
+/// Builds the root scope of a serialized `FileDescriptorSet`, as written by
+/// `protoc --descriptor_set_out` or `buf build -o`.
+/// Only `files` are generated, or all files of the set if `files` is empty.
+/// Files they import are generated only if `include_imports` is set
+pub(crate) fn read_descriptor_set(
+    descriptor_set: &[u8],
+    files: &[PathBuf],
+    include_imports: bool,
+) -> Result<RootScope, ProtoError> {
+    let encoded = descriptor_set;
+    let mut descriptor_set = FileDescriptorSet::decode(encoded)
+        .map_err(|e| ProtoError::Default(format!("Cannot decode FileDescriptorSet: {}", e)))?;
+    restore_field_options(&mut descriptor_set.file, encoded, DESCRIPTOR_SET_FILES);
+    let by_path = descriptor_set
+        .file
+        .iter()
+        .map(|file| (to_import_path(file.name()).to_path_buf(), file))
+        .collect::<HashMap<_, _>>();
+    let mut generated: HashSet<PathBuf> = HashSet::new();
+    let mut errors = Vec::new();
+    let mut pending = files.iter().map(clean).collect::<Vec<_>>();
+    while let Some(path) = pending.pop() {
+        let Some(file) = by_path.get(&path) else {
+            errors.push(ProtoError::Default(format!(
+                "{} is not in the descriptor set",
+                path.display()
+            )));
+            continue;
+        };
+        if generated.insert(path) && include_imports {
+            let imports = file.dependency.iter().map(|d| to_import_path(d));
+            pending.extend(
+                imports
+                    .filter(|i| !is_well_known_import(i) && !is_descriptor_import(i))
+                    .map(|i| i.to_path_buf()),
+            );
+        }
+    }
+    ProtoError::from_errors(errors)?;
+    read_descriptor_root_scope(&descriptor_set.file, |file| {
+        files.is_empty() || generated.contains(&to_import_path(file.name()).to_path_buf())
+    })
+}
+
+/// Number of the `file` field of `FileDescriptorSet`
+pub(crate) const DESCRIPTOR_SET_FILES: u32 = 1;
+/// Number of the `proto_file` field of `CodeGeneratorRequest`
+pub(crate) const REQUEST_FILES: u32 = 15;
+
+/// protoc writes custom options as extension fields of the options messages,
+/// which are dropped when descriptors are decoded. Values of extensions of
+/// `google.protobuf.FieldOptions` declared in `files` are read again from `encoded`,
+/// the message with the files in its `files_number` field, and kept as uninterpreted options.
+/// Options of message types are not restored
+pub(crate) fn restore_field_options(
+    files: &mut [FileDescriptorProto],
+    encoded: &[u8],
+    files_number: u32,
+) {
+    let extensions = field_option_extensions(files);
+    if extensions.is_empty() {
+        return;
+    }
+    let encoded_files = wire_fields(encoded).filter_map(|(number, value)| match value {
+        WireValue::Bytes(bytes) if number == files_number => Some(bytes),
+        _ => None,
+    });
+    for (file, encoded_file) in files.iter_mut().zip(encoded_files) {
+        let mut messages = file.message_type.iter_mut();
+        let mut fields = file.extension.iter_mut();
+        for (number, value) in wire_fields(encoded_file) {
+            let WireValue::Bytes(bytes) = value else {
+                continue;
+            };
+            match number {
+                4 => {
+                    if let Some(message) = messages.next() {
+                        restore_message_options(message, bytes, &extensions);
+                    }
+                }
+                7 => {
+                    if let Some(field) = fields.next() {
+                        restore_field_option_values(field, bytes, &extensions);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Fields, extensions and nested messages are matched to their encodings by their order
+fn restore_message_options(
+    message: &mut DescriptorProto,
+    encoded: &[u8],
+    extensions: &HashMap<u32, OptionExtension>,
+) {
+    let mut fields = message.field.iter_mut();
+    let mut nested_messages = message.nested_type.iter_mut();
+    let mut message_extensions = message.extension.iter_mut();
+    for (number, value) in wire_fields(encoded) {
+        let WireValue::Bytes(bytes) = value else {
+            continue;
+        };
+        let field = match number {
+            2 => fields.next(),
+            6 => message_extensions.next(),
+            3 => {
+                if let Some(nested) = nested_messages.next() {
+                    restore_message_options(nested, bytes, extensions);
+                }
+                None
+            }
+            _ => None,
+        };
+        if let Some(field) = field {
+            restore_field_option_values(field, bytes, extensions);
+        }
+    }
+}
+
+fn restore_field_option_values(
+    field: &mut FieldDescriptorProto,
+    encoded: &[u8],
+    extensions: &HashMap<u32, OptionExtension>,
+) {
+    for (number, value) in wire_fields(encoded) {
+        let (8, WireValue::Bytes(options)) = (number, value) else {
+            continue;
+        };
+        for (number, value) in wire_fields(options) {
+            if let Some(option) = extensions
+                .get(&number)
+                .and_then(|extension| extension.to_uninterpreted(value))
+            {
+                let field_options = field.options.get_or_insert_with(Default::default);
+                field_options.uninterpreted_option.push(option);
+            }
+        }
+    }
+}
+
+/// Extension of `google.protobuf.FieldOptions` declared in the descriptors
+struct OptionExtension {
+    /// Full name without the leading dot: `ts.int64`
+    name: String,
+    field_type: Type,
+    /// Names of the values of an enum extension by their numbers
+    enum_values: HashMap<i32, String>,
+}
+
+impl OptionExtension {
+    /// Message values and packed lists are not restored
+    fn to_uninterpreted(&self, value: WireValue) -> Option<UninterpretedOption> {
+        let mut res = UninterpretedOption {
+            name: vec![NamePart {
+                name_part: self.name.clone(),
+                is_extension: true,
+            }],
+            ..Default::default()
+        };
+        let mut set_int = |value: i64| match value {
+            v if v < 0 => res.negative_int_value = Some(v),
+            v => res.positive_int_value = Some(v as u64),
+        };
+        match (self.field_type, value) {
+            (Type::Enum, WireValue::Varint(v)) => match self.enum_values.get(&(v as i32)) {
+                Some(name) => res.identifier_value = Some(name.clone()),
+                None => set_int(v as i32 as i64),
+            },
+            (Type::Bool, WireValue::Varint(v)) => res.identifier_value = Some((v != 0).to_string()),
+            (Type::Int32 | Type::Int64, WireValue::Varint(v)) => set_int(v as i64),
+            (Type::Uint32 | Type::Uint64, WireValue::Varint(v)) => set_int(v as i64),
+            (Type::Sint32 | Type::Sint64, WireValue::Varint(v)) => {
+                set_int((v >> 1) as i64 ^ -((v & 1) as i64))
+            }
+            (Type::Fixed64, WireValue::Fixed64(v)) => res.positive_int_value = Some(v),
+            (Type::Sfixed64, WireValue::Fixed64(v)) => set_int(v as i64),
+            (Type::Fixed32, WireValue::Fixed32(v)) => set_int(v as i64),
+            (Type::Sfixed32, WireValue::Fixed32(v)) => set_int(v as i32 as i64),
+            (Type::Double, WireValue::Fixed64(v)) => res.double_value = Some(f64::from_bits(v)),
+            (Type::Float, WireValue::Fixed32(v)) => {
+                res.double_value = Some(f32::from_bits(v) as f64)
+            }
+            (Type::String | Type::Bytes, WireValue::Bytes(bytes)) => {
+                res.string_value = Some(bytes.to_vec())
+            }
+            _ => return None,
+        }
+        Some(res)
+    }
+}
+
+/// Extensions of `google.protobuf.FieldOptions` declared in the files or in their messages
+fn field_option_extensions(files: &[FileDescriptorProto]) -> HashMap<u32, OptionExtension> {
+    let mut enums: HashMap<String, &EnumDescriptorProto> = HashMap::new();
+    let mut extensions: Vec<(String, &FieldDescriptorProto)> = Vec::new();
+    fn collect<'a>(
+        scope: &str,
+        messages: &'a [DescriptorProto],
+        enum_types: &'a [EnumDescriptorProto],
+        fields: &'a [FieldDescriptorProto],
+        enums: &mut HashMap<String, &'a EnumDescriptorProto>,
+        extensions: &mut Vec<(String, &'a FieldDescriptorProto)>,
+    ) {
+        let full_name = |name: &str| match scope {
+            "" => name.to_string(),
+            _ => format!("{}.{}", scope, name),
+        };
+        for enum_type in enum_types {
+            enums.insert(format!(".{}", full_name(enum_type.name())), enum_type);
+        }
+        for field in fields {
+            if field.extendee() == ".google.protobuf.FieldOptions" {
+                extensions.push((full_name(field.name()), field));
+            }
+        }
+        for message in messages {
+            let scope = full_name(message.name());
+            collect(
+                &scope,
+                &message.nested_type,
+                &message.enum_type,
+                &message.extension,
+                enums,
+                extensions,
+            );
+        }
+    }
+    for file in files {
+        collect(
+            file.package(),
+            &file.message_type,
+            &file.enum_type,
+            &file.extension,
+            &mut enums,
+            &mut extensions,
+        );
+    }
+    extensions
+        .into_iter()
+        .map(|(name, field)| {
+            let enum_values = enums
+                .get(field.type_name())
+                .map(|enum_type| {
+                    enum_type
+                        .value
+                        .iter()
+                        .map(|value| (value.number(), value.name().to_string()))
+                        .collect()
+                })
+                .unwrap_or_default();
+            let extension = OptionExtension {
+                name,
+                field_type: field.r#type(),
+                enum_values,
+            };
+            (field.number() as u32, extension)
+        })
+        .collect()
+}
+
+/// Field of an encoded message, length delimited values are not decoded
+#[derive(Clone, Copy)]
+enum WireValue<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+/// Numbers and values of the fields of an encoded message, until the end or a malformed field
+fn wire_fields(mut encoded: &[u8]) -> impl Iterator<Item = (u32, WireValue<'_>)> {
+    fn read_varint(encoded: &mut &[u8]) -> Option<u64> {
+        let mut res = 0u64;
+        for shift in (0..64).step_by(7) {
+            let (&byte, rest) = encoded.split_first()?;
+            *encoded = rest;
+            res |= ((byte & 0x7f) as u64) << shift;
+            if byte < 0x80 {
+                return Some(res);
+            }
+        }
+        None
+    }
+    fn read_bytes<'a>(encoded: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+        let (bytes, rest) = encoded.split_at_checked(len)?;
+        *encoded = rest;
+        Some(bytes)
+    }
+    std::iter::from_fn(move || {
+        let key = read_varint(&mut encoded)?;
+        let value = match key & 7 {
+            0 => WireValue::Varint(read_varint(&mut encoded)?),
+            1 => WireValue::Fixed64(u64::from_le_bytes(
+                read_bytes(&mut encoded, 8)?.try_into().ok()?,
+            )),
+            2 => {
+                let len = read_varint(&mut encoded)? as usize;
+                WireValue::Bytes(read_bytes(&mut encoded, len)?)
+            }
+            5 => WireValue::Fixed32(u32::from_le_bytes(
+                read_bytes(&mut encoded, 4)?.try_into().ok()?,
+            )),
+            // Groups are not used in descriptors
+            _ => return None,
+        };
+        Some(((key >> 3) as u32, value))
+    })
+}
+
+/// Converts the file described by protoc into the declarations the parser would produce for it.
+/// Type references are qualified with their packages. Custom options are read from
+/// uninterpreted options, other options that do not affect generated code are dropped
+pub(crate) fn descriptor_to_proto_file(
+    id_generator: &mut IdGenerator,
+    file: &FileDescriptorProto,
+) -> Result<ProtoFile, ProtoError> {
+    let version = match file.syntax() {
+        "" | "proto2" => ProtoVersion::Proto2,
+        "proto3" => ProtoVersion::Proto3,
+        syntax => {
+            return Err(ProtoError::Default(format!(
+                "Syntax \"{}\" of {} is not supported",
+                syntax,
+                file.name()
+            )));
+        }
+    };
+    let import_path = to_import_path(file.name());
+    let mut declarations: Vec<Declaration> = Vec::new();
+    for message in &file.message_type {
+        declarations.push(to_message(id_generator, version, message).into());
+    }
+    for enum_type in &file.enum_type {
+        declarations.push(to_enum(id_generator, enum_type).into());
+    }
+    for service in &file.service {
+        declarations.push(to_service(id_generator, service).into());
+    }
+    for extend in to_extends(version, &file.extension) {
+        declarations.push(extend.into());
+    }
+    Ok(ProtoFile {
+        version,
+        declarations,
+        imports: file.dependency.iter().map(|d| to_import_path(d)).collect(),
+        path: to_path(file.package()),
+        name: import_path.file_name,
+        options: file.options.as_ref().map_or_else(Vec::new, |o| {
+            let mut options = deprecated_option(o.deprecated);
+            options.extend(custom_options(&o.uninterpreted_option));
+            options
+        }),
+    })
+}
+
+/// `google/protobuf/timestamp.proto` is split into its directories and the file name
+fn to_import_path(name: &str) -> ImportPath {
+    let mut packages = name.split('/').map(Rc::from).collect::<Vec<Rc<str>>>();
+    let file_name = packages.pop().unwrap_or_else(|| Rc::from(name));
+    ImportPath {
+        file_name,
+        packages,
+        span: None,
+    }
+}
+
+/// `.my.package.Message` or `my.package` is split into its components
+fn to_path(name: &str) -> Vec<Rc<str>> {
+    name.split('.')
+        .filter(|part| !part.is_empty())
+        .map(Rc::from)
+        .collect()
+}
+
+fn to_message(
+    id_generator: &mut IdGenerator,
+    version: ProtoVersion,
+    message: &DescriptorProto,
+) -> MessageDeclaration {
+    let mut entries: Vec<MessageDeclarationEntry> = Vec::new();
+    let mut oneofs: Vec<Option<OneOfDeclaration>> = message
+        .oneof_decl
+        .iter()
+        .map(|oneof| {
+            Some(OneOfDeclaration {
+                name: Rc::from(oneof.name()),
+                fields: Vec::new(),
+                options: oneof
+                    .options
+                    .as_ref()
+                    .map(|o| custom_options(&o.uninterpreted_option))
+                    .unwrap_or_default(),
+                span: None,
+            })
+        })
+        .collect();
+    // Fields of a oneof are consecutive, the oneof is placed at its first field
+    let mut oneof_positions: Vec<(usize, usize)> = Vec::new();
+    for field in &message.field {
+        let declaration = to_field(version, message, field);
+        match field.oneof_index {
+            Some(index) if !field.proto3_optional() => {
+                let index = index as usize;
+                if !oneof_positions.iter().any(|(i, _)| *i == index) {
+                    oneof_positions.push((index, entries.len()));
+                }
+                if let Some(Some(oneof)) = oneofs.get_mut(index) {
+                    oneof.fields.push(declaration);
+                }
+            }
+            _ => entries.push(MessageDeclarationEntry::Field(declaration)),
+        }
+    }
+    for (index, position) in oneof_positions.into_iter().rev() {
+        if let Some(oneof) = oneofs.get_mut(index).and_then(Option::take) {
+            entries.insert(position, MessageDeclarationEntry::OneOf(oneof));
+        }
+    }
+    for nested in &message.nested_type {
+        if is_map_entry(nested) {
+            continue;
+        }
+        let nested = to_message(id_generator, version, nested);
+        entries.push(Declaration::from(nested).into());
+    }
+    for enum_type in &message.enum_type {
+        entries.push(Declaration::from(to_enum(id_generator, enum_type)).into());
+    }
+    for extend in to_extends(version, &message.extension) {
+        entries.push(Declaration::from(extend).into());
+    }
+    if !message.extension_range.is_empty() {
+        entries.push(MessageDeclarationEntry::Extensions(ExtensionsDeclaration {
+            ranges: message
+                .extension_range
+                .iter()
+                .map(|range| exclusive_range(range.start(), range.end()))
+                .collect(),
+            options: Vec::new(),
+        }));
+    }
+    if !message.reserved_range.is_empty() || !message.reserved_name.is_empty() {
+        entries.push(MessageDeclarationEntry::Reserved(ReservedDeclaration {
+            ranges: message
+                .reserved_range
+                .iter()
+                .map(|range| exclusive_range(range.start(), range.end()))
+                .collect(),
+            names: message
+                .reserved_name
+                .iter()
+                .map(|n| Rc::from(n.as_str()))
+                .collect(),
+            span: None,
+        }));
+    }
+    let options = message.options.as_ref().map_or_else(Vec::new, |o| {
+        let mut options = deprecated_option(o.deprecated);
+        options.extend(custom_options(&o.uninterpreted_option));
+        options
+    });
+    id_generator.create((Rc::from(message.name()), entries, options))
+}
+
+/// Ranges of messages exclude their end, ranges of declarations include it
+fn exclusive_range(start: i32, end: i32) -> FieldRange {
+    FieldRange {
+        start: start as i64,
+        end: end as i64 - 1,
+    }
+}
+
+fn is_map_entry(message: &DescriptorProto) -> bool {
+    message.options.as_ref().is_some_and(|o| o.map_entry())
+}
+
+fn to_field(
+    version: ProtoVersion,
+    message: &DescriptorProto,
+    field: &FieldDescriptorProto,
+) -> FieldDeclaration {
+    let map_entry = message.nested_type.iter().find(|nested| {
+        is_map_entry(nested) && field.type_name().rsplit('.').next() == Some(nested.name())
+    });
+    let field_type_ref = match (field.label(), map_entry) {
+        (Label::Repeated, Some(entry)) => {
+            let entry_field = |number: i32| {
+                entry
+                    .field
+                    .iter()
+                    .find(|f| f.number() == number)
+                    .map(to_type_reference)
+                    .unwrap_or(FieldTypeReference::Bytes)
+            };
+            FieldTypeReference::Map(Box::new(entry_field(1)), Box::new(entry_field(2)))
+        }
+        (Label::Repeated, None) => FieldTypeReference::repeated(to_type_reference(field)),
+        (Label::Optional, _) if field.proto3_optional() || version == ProtoVersion::Proto2 => {
+            FieldTypeReference::optional(to_type_reference(field))
+        }
+        _ => to_type_reference(field),
+    };
+    let mut options = Vec::new();
+    if let Some(field_options) = &field.options {
+        if let Some(packed) = field_options.packed {
+            options.push(simple_option("packed", Constant::Bool(packed)));
+        }
+        options.extend(deprecated_option(field_options.deprecated));
+        options.extend(custom_options(&field_options.uninterpreted_option));
+    }
+    // protoc sets the default json name of every field, only explicit ones are kept
+    if let Some(json_name) = &field.json_name
+        && *json_name != default_json_name(field.name())
+    {
+        options.push(simple_option(
+            "json_name",
+            Constant::String(Rc::from(json_name.as_str())),
+        ));
+    }
+    FieldDeclaration {
+        name: Rc::from(field.name()),
+        field_type_ref,
+        tag: field.number() as i64,
+        options,
+        span: None,
+    }
+}
+
+fn to_type_reference(field: &FieldDescriptorProto) -> FieldTypeReference {
+    match field.r#type() {
+        Type::Double => FieldTypeReference::Double,
+        Type::Float => FieldTypeReference::Float,
+        Type::Int64 => FieldTypeReference::Int64,
+        Type::Uint64 => FieldTypeReference::Uint64,
+        Type::Int32 => FieldTypeReference::Int32,
+        Type::Fixed64 => FieldTypeReference::Fixed64,
+        Type::Fixed32 => FieldTypeReference::Fixed32,
+        Type::Bool => FieldTypeReference::Bool,
+        Type::String => FieldTypeReference::String,
+        Type::Bytes => FieldTypeReference::Bytes,
+        Type::Uint32 => FieldTypeReference::Uint32,
+        Type::Sfixed32 => FieldTypeReference::Sfixed32,
+        Type::Sfixed64 => FieldTypeReference::Sfixed64,
+        Type::Sint32 => FieldTypeReference::Sint32,
+        Type::Sint64 => FieldTypeReference::Sint64,
+        Type::Group | Type::Message | Type::Enum => {
+            FieldTypeReference::IdPath(to_path(field.type_name()))
+        }
+    }
+}
+
+/// Name protoc gives to a field without `json_name` option: `foo_bar` becomes `fooBar`
+pub(super) fn default_json_name(name: &str) -> String {
+    let mut res = String::with_capacity(name.len());
+    let mut capitalize_next = false;
+    for c in name.chars() {
+        if c == '_' {
+            capitalize_next = true;
+        } else if capitalize_next {
+            res.push(c.to_ascii_uppercase());
+            capitalize_next = false;
+        } else {
+            res.push(c);
+        }
+    }
+    res
+}
+
+/// Extensions are grouped into `extend` blocks by the extended message
+fn to_extends(version: ProtoVersion, fields: &[FieldDescriptorProto]) -> Vec<ExtendDeclaration> {
+    let mut res: Vec<ExtendDeclaration> = Vec::new();
+    for field in fields {
+        let extendee = FieldTypeReference::IdPath(to_path(field.extendee()));
+        let declaration = to_field(version, &DescriptorProto::default(), field);
+        match res.iter_mut().find(|extend| extend.extendee == extendee) {
+            Some(extend) => extend.fields.push(declaration),
+            None => res.push(ExtendDeclaration {
+                extendee,
+                fields: vec![declaration],
+                extendee_span: None,
+            }),
+        }
+    }
+    res
+}
+
+fn to_enum(id_generator: &mut IdGenerator, enum_type: &EnumDescriptorProto) -> EnumDeclaration {
+    let entries = enum_type
+        .value
+        .iter()
+        .map(|value| EnumEntry {
+            name: Rc::from(value.name()),
+            value: value.number() as i64,
+            options: value.options.as_ref().map_or_else(Vec::new, |o| {
+                let mut options = deprecated_option(o.deprecated);
+                options.extend(custom_options(&o.uninterpreted_option));
+                options
+            }),
+            span: None,
+        })
+        .collect::<Vec<_>>();
+    let mut options = Vec::new();
+    if let Some(enum_options) = &enum_type.options {
+        if let Some(allow_alias) = enum_options.allow_alias {
+            options.push(simple_option("allow_alias", Constant::Bool(allow_alias)));
+        }
+        options.extend(deprecated_option(enum_options.deprecated));
+        options.extend(custom_options(&enum_options.uninterpreted_option));
+    }
+    let mut res: EnumDeclaration =
+        id_generator.create((Rc::from(enum_type.name()), entries, options));
+    if !enum_type.reserved_range.is_empty() || !enum_type.reserved_name.is_empty() {
+        res.reserved.push(ReservedDeclaration {
+            ranges: enum_type
+                .reserved_range
+                .iter()
+                .map(|range| FieldRange {
+                    start: range.start() as i64,
+                    end: range.end() as i64,
+                })
+                .collect(),
+            names: enum_type
+                .reserved_name
+                .iter()
+                .map(|n| Rc::from(n.as_str()))
+                .collect(),
+            span: None,
+        });
+    }
+    res
+}
+
+fn to_service(
+    id_generator: &mut IdGenerator,
+    service: &ServiceDescriptorProto,
+) -> ServiceDeclaration {
+    let rpcs = service
+        .method
+        .iter()
+        .map(|method| RpcDeclaration {
+            name: Rc::from(method.name()),
+            input_type: FieldTypeReference::IdPath(to_path(method.input_type())),
+            client_streaming: method.client_streaming(),
+            output_type: FieldTypeReference::IdPath(to_path(method.output_type())),
+            server_streaming: method.server_streaming(),
+            options: method.options.as_ref().map_or_else(Vec::new, |o| {
+                let mut options = deprecated_option(o.deprecated);
+                options.extend(custom_options(&o.uninterpreted_option));
+                options
+            }),
+            span: None,
+            input_span: None,
+            output_span: None,
+        })
+        .collect::<Vec<_>>();
+    let options = service.options.as_ref().map_or_else(Vec::new, |o| {
+        let mut options = deprecated_option(o.deprecated);
+        options.extend(custom_options(&o.uninterpreted_option));
+        options
+    });
+    id_generator.create((Rc::from(service.name()), rpcs, options))
+}
+
+fn simple_option(name: &str, value: Constant) -> ProtoOption {
+    ProtoOption {
+        name: OptionName {
+            parts: vec![OptionNamePart::Name(Rc::from(name))],
+        },
+        value,
+    }
+}
+
+fn deprecated_option(deprecated: Option<bool>) -> Vec<ProtoOption> {
+    deprecated
+        .map(|value| simple_option("deprecated", Constant::Bool(value)))
+        .into_iter()
+        .collect()
+}
+
+/// Custom options kept uninterpreted, as in descriptor sets written by this compiler,
+/// or restored by `restore_field_options`. Malformed message values are dropped
+fn custom_options(options: &[UninterpretedOption]) -> Vec<ProtoOption> {
+    options
+        .iter()
+        .filter_map(|option| {
+            let parts = option
+                .name
+                .iter()
+                .map(|part| {
+                    if part.is_extension {
+                        OptionNamePart::Extension(to_path(&part.name_part))
+                    } else {
+                        OptionNamePart::Name(Rc::from(part.name_part.as_str()))
+                    }
+                })
+                .collect::<Vec<_>>();
+            let value = if let Some(identifier) = &option.identifier_value {
+                match identifier.as_str() {
+                    "true" => Constant::Bool(true),
+                    "false" => Constant::Bool(false),
+                    _ => Constant::Identifier(Rc::from(identifier.as_str())),
+                }
+            } else if let Some(value) = option.positive_int_value {
+                i64::try_from(value).map_or(Constant::Uint(value), Constant::Int)
+            } else if let Some(value) = option.negative_int_value {
+                Constant::Int(value)
+            } else if let Some(value) = option.double_value {
+                Constant::Float(value)
+            } else if let Some(value) = &option.string_value {
+                Constant::String(Rc::from(String::from_utf8_lossy(value).as_ref()))
+            } else {
+                parse_aggregate_text(Path::new(""), option.aggregate_value()).ok()?
+            };
+            Some(ProtoOption {
+                name: OptionName { parts },
+                value,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test_descriptor {
+    use std::path::{Path, PathBuf};
+
+    use prost::Message;
+    use prost_types::{
+        DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
+        FieldOptions, FileDescriptorProto, FileDescriptorSet, MessageOptions, OneofDescriptorProto,
+        field_descriptor_proto::{Label, Type},
+    };
+
+    use super::{IdGenerator, descriptor_to_proto_file, read_descriptor_set};
+    use crate::{
+        GenerationOptions, compile, encode_descriptor_set, proto::package::parse_proto_file,
+        test_utils::build_files,
+    };
+
+    fn field(name: &str, number: i32, label: Label, field_type: Type) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.into()),
+            number: Some(number),
+            label: Some(label as i32),
+            r#type: Some(field_type as i32),
+            json_name: Some(super::default_json_name(name)),
+            ..Default::default()
+        }
+    }
+
+    fn message_field(
+        name: &str,
+        number: i32,
+        label: Label,
+        type_name: &str,
+    ) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            type_name: Some(type_name.into()),
+            ..field(name, number, label, Type::Message)
+        }
+    }
+
+    #[test]
+    fn converts_to_parsed_declarations() {
+        let source = r#"
+            syntax = "proto3";
+            package app;
+            import "common/id.proto";
+            message User {
+                string user_name = 1 [json_name = "name"];
+                optional int32 age = 2;
+                map<string, common.Id> tags = 3;
+                oneof contact {
+                    string email = 4;
+                    string phone = 5;
+                }
+                repeated int32 scores = 6 [packed = false];
+                enum Role {
+                    ROLE_UNKNOWN = 0;
+                    ROLE_ADMIN = 1;
+                }
+            }
+        "#;
+        let tags_entry = DescriptorProto {
+            name: Some("TagsEntry".into()),
+            field: vec![
+                field("key", 1, Label::Optional, Type::String),
+                message_field("value", 2, Label::Optional, ".common.Id"),
+            ],
+            options: Some(MessageOptions {
+                map_entry: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let user = DescriptorProto {
+            name: Some("User".into()),
+            field: vec![
+                FieldDescriptorProto {
+                    json_name: Some("name".into()),
+                    ..field("user_name", 1, Label::Optional, Type::String)
+                },
+                FieldDescriptorProto {
+                    oneof_index: Some(1),
+                    proto3_optional: Some(true),
+                    ..field("age", 2, Label::Optional, Type::Int32)
+                },
+                message_field("tags", 3, Label::Repeated, ".app.User.TagsEntry"),
+                FieldDescriptorProto {
+                    oneof_index: Some(0),
+                    ..field("email", 4, Label::Optional, Type::String)
+                },
+                FieldDescriptorProto {
+                    oneof_index: Some(0),
+                    ..field("phone", 5, Label::Optional, Type::String)
+                },
+                FieldDescriptorProto {
+                    options: Some(FieldOptions {
+                        packed: Some(false),
+                        ..Default::default()
+                    }),
+                    ..field("scores", 6, Label::Repeated, Type::Int32)
+                },
+            ],
+            nested_type: vec![tags_entry],
+            enum_type: vec![EnumDescriptorProto {
+                name: Some("Role".into()),
+                value: ["ROLE_UNKNOWN", "ROLE_ADMIN"]
+                    .iter()
+                    .enumerate()
+                    .map(|(number, name)| EnumValueDescriptorProto {
+                        name: Some(name.to_string()),
+                        number: Some(number as i32),
+                        options: None,
+                    })
+                    .collect(),
+                ..Default::default()
+            }],
+            oneof_decl: ["contact", "_age"]
+                .iter()
+                .map(|name| OneofDescriptorProto {
+                    name: Some(name.to_string()),
+                    options: None,
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let file = FileDescriptorProto {
+            name: Some("app/user.proto".into()),
+            package: Some("app".into()),
+            dependency: vec!["common/id.proto".into()],
+            message_type: vec![user],
+            syntax: Some("proto3".into()),
+            ..Default::default()
+        };
+
+        let mut id_generator = IdGenerator::new();
+        let parsed = parse_proto_file(&mut id_generator, Path::new("user.proto"), source).unwrap();
+        let converted = descriptor_to_proto_file(&mut id_generator, &file).unwrap();
+        assert_eq!(converted.to_string(), parsed.to_string());
+    }
+
+    #[test]
+    fn generates_selected_files_of_descriptor_set() {
+        let id = FileDescriptorProto {
+            name: Some("common/id.proto".into()),
+            package: Some("common".into()),
+            message_type: vec![DescriptorProto {
+                name: Some("Id".into()),
+                field: vec![field("value", 1, Label::Optional, Type::String)],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let user = FileDescriptorProto {
+            name: Some("app/user.proto".into()),
+            package: Some("app".into()),
+            dependency: vec!["common/id.proto".into()],
+            message_type: vec![DescriptorProto {
+                name: Some("User".into()),
+                field: vec![message_field("id", 1, Label::Optional, ".common.Id")],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let descriptor_set = FileDescriptorSet {
+            file: vec![id, user],
+        }
+        .encode_to_vec();
+        let generated = |files: &[&str], include_imports: bool| {
+            let files = files.iter().map(PathBuf::from).collect::<Vec<_>>();
+            let root = read_descriptor_set(&descriptor_set, &files, include_imports).unwrap();
+            let output = compile(&root, &GenerationOptions::default()).unwrap();
+            let mut folders = output
+                .keys()
+                .map(|path| path.iter().next().unwrap().to_string_lossy().into_owned())
+                .collect::<Vec<_>>();
+            folders.dedup();
+            folders
+        };
+
+        assert_eq!(generated(&[], false), vec!["app", "common"]);
+        assert_eq!(generated(&["app/user.proto"], false), vec!["app"]);
+        assert_eq!(generated(&["app/user.proto"], true), vec!["app", "common"]);
+        let err = read_descriptor_set(&descriptor_set, &[PathBuf::from("user.proto")], false);
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "user.proto is not in the descriptor set"
+        );
+    }
+
+    fn visits_declaration(descriptor_set: &[u8]) -> String {
+        let root =
+            read_descriptor_set(descriptor_set, &[PathBuf::from("app/user.proto")], false).unwrap();
+        let output = compile(&root, &GenerationOptions::default()).unwrap();
+        let types = &output[Path::new("app/user/User/types.ts")];
+        let line = types.lines().find(|line| line.contains("visits")).unwrap();
+        line.trim().to_string()
+    }
+
+    #[test]
+    fn reads_custom_options_of_written_descriptor_sets() {
+        let source = r#"syntax = "proto3";
+package app;
+option (app.owner) = "team";
+message User {
+  int64 visits = 1 [(ts.int64) = STRING, (app.meta).limit = -1, (app.meta) = { label: "v" }];
+}
+"#;
+        let root = build_files(&[("app/user.proto", source)]);
+        let descriptor_set = encode_descriptor_set(&root);
+        let mut id_generator = IdGenerator::new();
+        let parsed = parse_proto_file(&mut id_generator, Path::new("user.proto"), source).unwrap();
+        let file = &FileDescriptorSet::decode(descriptor_set.as_slice())
+            .unwrap()
+            .file[0];
+        let converted = descriptor_to_proto_file(&mut id_generator, file).unwrap();
+        assert_eq!(converted.to_string(), parsed.to_string());
+        assert_eq!(
+            visits_declaration(&descriptor_set),
+            "visits?: string | null"
+        );
+    }
+
+    #[test]
+    fn restores_field_options_interpreted_by_protoc() {
+        let int64_enum = EnumDescriptorProto {
+            name: Some("Int64".into()),
+            value: ["LONG", "BIGINT", "STRING", "NUMBER"]
+                .iter()
+                .enumerate()
+                .map(|(number, name)| EnumValueDescriptorProto {
+                    name: Some(name.to_string()),
+                    number: Some(number as i32),
+                    options: None,
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let options_file = FileDescriptorProto {
+            name: Some("ts/options.proto".into()),
+            package: Some("ts".into()),
+            dependency: vec!["google/protobuf/descriptor.proto".into()],
+            enum_type: vec![int64_enum],
+            extension: vec![FieldDescriptorProto {
+                type_name: Some(".ts.Int64".into()),
+                extendee: Some(".google.protobuf.FieldOptions".into()),
+                ..field("int64", 50000, Label::Optional, Type::Enum)
+            }],
+            syntax: Some("proto3".into()),
+            ..Default::default()
+        };
+        // prost does not encode extensions, so `[(ts.int64) = STRING]` is appended to the field
+        let mut field_options = Vec::new();
+        prost::encoding::int32::encode(50000, &2, &mut field_options);
+        let mut visits = field("visits", 1, Label::Optional, Type::Int64).encode_to_vec();
+        prost::encoding::bytes::encode(8, &field_options, &mut visits);
+        let mut user = DescriptorProto {
+            name: Some("User".into()),
+            ..Default::default()
+        }
+        .encode_to_vec();
+        prost::encoding::bytes::encode(2, &visits, &mut user);
+        let mut user_file = FileDescriptorProto {
+            name: Some("app/user.proto".into()),
+            package: Some("app".into()),
+            dependency: vec!["ts/options.proto".into()],
+            syntax: Some("proto3".into()),
+            ..Default::default()
+        }
+        .encode_to_vec();
+        prost::encoding::bytes::encode(4, &user, &mut user_file);
+        let mut descriptor_set = FileDescriptorSet {
+            file: vec![options_file],
+        }
+        .encode_to_vec();
+        prost::encoding::bytes::encode(1, &user_file, &mut descriptor_set);
+
+        assert_eq!(
+            visits_declaration(&descriptor_set),
+            "visits?: string | null"
+        );
+    }
+}
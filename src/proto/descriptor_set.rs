@@ -0,0 +1,1195 @@
+use std::{collections::HashMap, fmt::Write, rc::Rc};
+
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, EnumOptions, EnumValueDescriptorProto, EnumValueOptions,
+    FieldDescriptorProto, FieldOptions, FileDescriptorProto, FileDescriptorSet, FileOptions,
+    MessageOptions, MethodDescriptorProto, MethodOptions, OneofDescriptorProto, OneofOptions,
+    ServiceDescriptorProto, ServiceOptions, SourceCodeInfo, UninterpretedOption,
+    descriptor_proto::{ExtensionRange, ReservedRange},
+    enum_descriptor_proto::EnumReservedRange,
+    field_descriptor_proto::{Label, Type as FieldType},
+    file_options::OptimizeMode,
+    source_code_info::Location,
+    uninterpreted_option::NamePart,
+};
+
+use super::{
+    descriptor::default_json_name,
+    diagnostic::json_string,
+    options::{Constant, OptionNamePart, ProtoOption, find_option},
+    package::{Extension, Field, MessageEntry, ProtoVersion, Type},
+    proto_scope::{
+        ProtoScope, enum_scope::EnumScope, file::FileScope, message::MessageScope,
+        root_scope::RootScope, service::ServiceScope, traits::ChildrenScopes,
+    },
+    span::Span,
+};
+
+// Numbers of the descriptor fields used in paths of source locations
+const FILE_MESSAGE_TYPE: i32 = 4;
+const FILE_ENUM_TYPE: i32 = 5;
+const FILE_SERVICE: i32 = 6;
+const FILE_EXTENSION: i32 = 7;
+const MESSAGE_FIELD: i32 = 2;
+const MESSAGE_NESTED_TYPE: i32 = 3;
+const MESSAGE_ENUM_TYPE: i32 = 4;
+const MESSAGE_EXTENSION: i32 = 6;
+const MESSAGE_ONEOF_DECL: i32 = 8;
+const ENUM_VALUE: i32 = 2;
+const SERVICE_METHOD: i32 = 2;
+
+/// Serializes the resolved schema into descriptors of all its files, including imported ones.
+/// Files come after the files they import. Type names are fully qualified,
+/// source locations are known for declarations parsed from proto files
+pub(crate) fn root_scope_to_descriptor_set(root: &RootScope) -> FileDescriptorSet {
+    let mut files = Vec::new();
+    collect_files(root.children(), &mut Vec::new(), &mut files);
+    let descriptors = files
+        .into_iter()
+        .map(|(package, file)| file_to_descriptor(root, &package, file))
+        .collect::<Vec<_>>();
+
+    let by_name = descriptors
+        .iter()
+        .enumerate()
+        .map(|(i, file)| (file.name().to_string(), i))
+        .collect::<HashMap<_, _>>();
+    let mut order = Vec::new();
+    let mut visited = vec![false; descriptors.len()];
+    for i in 0..descriptors.len() {
+        visit_dependencies(i, &descriptors, &by_name, &mut visited, &mut order);
+    }
+    let mut descriptors = descriptors.into_iter().map(Some).collect::<Vec<_>>();
+    FileDescriptorSet {
+        file: order
+            .into_iter()
+            .filter_map(|i| descriptors[i].take())
+            .collect(),
+    }
+}
+
+fn collect_files<'a>(
+    scopes: &'a [Rc<ProtoScope>],
+    package: &mut Vec<Rc<str>>,
+    res: &mut Vec<(Vec<Rc<str>>, &'a FileScope)>,
+) {
+    for scope in scopes {
+        match scope.as_ref() {
+            ProtoScope::Package(p) => {
+                package.push(Rc::clone(&p.name));
+                collect_files(&p.children, package, res);
+                package.pop();
+            }
+            ProtoScope::File(f) => res.push((package.clone(), f)),
+            _ => {}
+        }
+    }
+}
+
+/// Pushes indexes of the dependencies of the file and then of the file itself
+fn visit_dependencies(
+    index: usize,
+    files: &[FileDescriptorProto],
+    by_name: &HashMap<String, usize>,
+    visited: &mut [bool],
+    order: &mut Vec<usize>,
+) {
+    if visited[index] {
+        return;
+    }
+    visited[index] = true;
+    for dependency in &files[index].dependency {
+        if let Some(&dependency_index) = by_name.get(dependency) {
+            visit_dependencies(dependency_index, files, by_name, visited, order);
+        }
+    }
+    order.push(index);
+}
+
+/// Collects source locations while the file is converted
+struct FileContext<'a> {
+    root: &'a RootScope,
+    version: ProtoVersion,
+    locations: Vec<Location>,
+}
+
+impl FileContext<'_> {
+    fn type_name(&self, id: usize) -> String {
+        match self.root.get_declaration_path(id) {
+            Some(path) => format!(".{}", path.full_name()),
+            None => String::new(),
+        }
+    }
+
+    fn add_location(&mut self, path: Vec<i32>, span: &Option<Span>) {
+        let Some(span) = span else {
+            return;
+        };
+        // Descriptor spans are 0-based and the end column is exclusive
+        let mut location_span = vec![span.start_line as i32 - 1, span.start_column as i32 - 1];
+        if span.end_line != span.start_line {
+            location_span.push(span.end_line as i32 - 1);
+        }
+        location_span.push(span.end_column as i32);
+        self.locations.push(Location {
+            path,
+            span: location_span,
+            ..Default::default()
+        });
+    }
+}
+
+fn file_to_descriptor(
+    root: &RootScope,
+    package: &[Rc<str>],
+    file: &FileScope,
+) -> FileDescriptorProto {
+    let mut context = FileContext {
+        root,
+        version: file.version,
+        locations: Vec::new(),
+    };
+    let mut res = FileDescriptorProto {
+        name: Some(
+            package
+                .iter()
+                .map(|p| p.as_ref())
+                .chain([file.name.as_ref()])
+                .collect::<Vec<_>>()
+                .join("/"),
+        ),
+        package: (!package.is_empty()).then(|| package.join(".")),
+        // Custom options are kept uninterpreted, so descriptor.proto is not needed
+        dependency: file
+            .imports
+            .iter()
+            .filter(|i| {
+                !(i.packages.join("/") == "google/protobuf" && &*i.file_name == "descriptor.proto")
+            })
+            .map(|i| {
+                i.packages
+                    .iter()
+                    .map(|p| p.as_ref())
+                    .chain([i.file_name.as_ref()])
+                    .collect::<Vec<_>>()
+                    .join("/")
+            })
+            .collect(),
+        options: file_options(&file.options),
+        syntax: Some(file.version.to_string()),
+        ..Default::default()
+    };
+    for child in &file.children {
+        match child.as_ref() {
+            ProtoScope::Message(m) => {
+                let path = vec![FILE_MESSAGE_TYPE, res.message_type.len() as i32];
+                res.message_type
+                    .push(message_to_descriptor(&mut context, m, path));
+            }
+            ProtoScope::Enum(e) => {
+                let path = vec![FILE_ENUM_TYPE, res.enum_type.len() as i32];
+                res.enum_type
+                    .push(enum_to_descriptor(&mut context, e, path));
+            }
+            ProtoScope::Service(s) => {
+                let path = vec![FILE_SERVICE, res.service.len() as i32];
+                res.service
+                    .push(service_to_descriptor(&mut context, s, path));
+            }
+            _ => {}
+        }
+    }
+    res.extension = extensions_to_descriptors(&mut context, &file.extensions, &[FILE_EXTENSION]);
+    if !context.locations.is_empty() {
+        res.source_code_info = Some(SourceCodeInfo {
+            location: context.locations,
+        });
+    }
+    res
+}
+
+fn message_to_descriptor(
+    context: &mut FileContext,
+    message: &MessageScope,
+    path: Vec<i32>,
+) -> DescriptorProto {
+    context.add_location(path.clone(), &message.span);
+    let mut res = DescriptorProto {
+        name: Some(message.name.to_string()),
+        options: message_options(&message.options, false),
+        ..Default::default()
+    };
+    for child in &message.children {
+        match child.as_ref() {
+            ProtoScope::Message(m) => {
+                let nested_path = [
+                    &path[..],
+                    &[MESSAGE_NESTED_TYPE, res.nested_type.len() as i32],
+                ];
+                let nested = message_to_descriptor(context, m, nested_path.concat());
+                res.nested_type.push(nested);
+            }
+            ProtoScope::Enum(e) => {
+                let enum_path = [&path[..], &[MESSAGE_ENUM_TYPE, res.enum_type.len() as i32]];
+                res.enum_type
+                    .push(enum_to_descriptor(context, e, enum_path.concat()));
+            }
+            _ => {}
+        }
+    }
+
+    let oneof_count = message
+        .entries
+        .iter()
+        .filter(|entry| matches!(entry, MessageEntry::OneOf(_)))
+        .count();
+    let mut synthetic_oneofs = Vec::new();
+    let message_type_name = context.type_name(message.id);
+    let mut add_field = |context: &mut FileContext,
+                         res: &mut DescriptorProto,
+                         field: &Field,
+                         oneof_index: Option<i32>| {
+        let field_path = [&path[..], &[MESSAGE_FIELD, res.field.len() as i32]].concat();
+        context.add_location(field_path, &field.span);
+        let mut descriptor = field_to_descriptor(context, field);
+        if let Type::Map(key_type, value_type) = &field.field_type {
+            let entry = map_entry(context, &field.name, key_type, value_type);
+            descriptor.type_name = Some(format!("{}.{}", message_type_name, entry.name()));
+            res.nested_type.push(entry);
+        }
+        if descriptor.proto3_optional() {
+            descriptor.oneof_index = Some((oneof_count + synthetic_oneofs.len()) as i32);
+            synthetic_oneofs.push(OneofDescriptorProto {
+                name: Some(format!("_{}", field.name)),
+                options: None,
+            });
+        } else {
+            descriptor.oneof_index = oneof_index;
+        }
+        res.field.push(descriptor);
+    };
+    for entry in &message.entries {
+        match entry {
+            MessageEntry::Field(field) => add_field(context, &mut res, field, None),
+            MessageEntry::OneOf(oneof) => {
+                let index = res.oneof_decl.len() as i32;
+                let oneof_path = [&path[..], &[MESSAGE_ONEOF_DECL, index]].concat();
+                context.add_location(oneof_path, &oneof.span);
+                res.oneof_decl.push(OneofDescriptorProto {
+                    name: Some(oneof.name.to_string()),
+                    options: Some(OneofOptions {
+                        uninterpreted_option: uninterpreted_options(&oneof.options),
+                    })
+                    .filter(|o| *o != OneofOptions::default()),
+                });
+                for field in &oneof.fields {
+                    add_field(context, &mut res, field, Some(index));
+                }
+            }
+            // Extensions are described by the scope declaring them
+            MessageEntry::Extension(_) => {}
+        }
+    }
+    res.oneof_decl.extend(synthetic_oneofs);
+
+    let extension_path = [&path[..], &[MESSAGE_EXTENSION]].concat();
+    res.extension = extensions_to_descriptors(context, &message.extensions, &extension_path);
+    res.extension_range = message
+        .extension_ranges
+        .iter()
+        .map(|range| ExtensionRange {
+            start: Some(range.start as i32),
+            end: Some(range.end as i32 + 1),
+            options: None,
+        })
+        .collect();
+    for reserved in &message.reserved {
+        res.reserved_range
+            .extend(reserved.ranges.iter().map(|range| ReservedRange {
+                start: Some(range.start as i32),
+                end: Some(range.end as i32 + 1),
+            }));
+        res.reserved_name
+            .extend(reserved.names.iter().map(|n| n.to_string()));
+    }
+    res
+}
+
+/// Map fields are repeated fields of a generated nested `FooEntry` message
+fn map_entry(
+    context: &FileContext,
+    field_name: &str,
+    key_type: &Type,
+    value_type: &Type,
+) -> DescriptorProto {
+    let entry_field = |name: &str, tag: i64, field_type: &Type| {
+        let field = Field {
+            name: Rc::from(name),
+            field_type: field_type.clone(),
+            tag,
+            options: Vec::new(),
+            packed: false,
+            span: None,
+        };
+        field_to_descriptor(context, &field)
+    };
+    let mut name = String::new();
+    for part in field_name.split('_').filter(|p| !p.is_empty()) {
+        let mut chars = part.chars();
+        name.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+        name.push_str(chars.as_str());
+    }
+    name.push_str("Entry");
+    DescriptorProto {
+        name: Some(name),
+        field: vec![
+            entry_field("key", 1, key_type),
+            entry_field("value", 2, value_type),
+        ],
+        options: message_options(&[], true),
+        ..Default::default()
+    }
+}
+
+fn field_to_descriptor(context: &FileContext, field: &Field) -> FieldDescriptorProto {
+    let (label, field_type, proto3_optional) = match &field.field_type {
+        Type::Repeated(t) => (Label::Repeated, t.as_ref(), false),
+        Type::Map(_, _) => (Label::Repeated, &field.field_type, false),
+        Type::Optional(t) => (
+            Label::Optional,
+            t.as_ref(),
+            context.version == ProtoVersion::Proto3,
+        ),
+        t => (Label::Optional, t, false),
+    };
+    let (field_type, type_name) = match field_type {
+        Type::Enum(id) => (FieldType::Enum, Some(context.type_name(*id))),
+        // Name of the map entry is set by the message
+        Type::Message(_) | Type::Map(_, _) => (
+            FieldType::Message,
+            match field_type {
+                Type::Message(id) => Some(context.type_name(*id)),
+                _ => None,
+            },
+        ),
+        t => (scalar_type(t), None),
+    };
+    let json_name = find_option(&field.options, "json_name")
+        .and_then(Constant::as_str)
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| default_json_name(&field.name));
+    let options = FieldOptions {
+        packed: bool_option(&field.options, "packed"),
+        deprecated: bool_option(&field.options, "deprecated"),
+        uninterpreted_option: uninterpreted_options(&field.options),
+        ..Default::default()
+    };
+    FieldDescriptorProto {
+        name: Some(field.name.to_string()),
+        number: Some(field.tag as i32),
+        label: Some(label as i32),
+        r#type: Some(field_type as i32),
+        type_name,
+        default_value: find_option(&field.options, "default").map(|value| match value {
+            Constant::String(s) | Constant::Identifier(s) => s.to_string(),
+            value => value.to_string(),
+        }),
+        json_name: Some(json_name),
+        options: (options != FieldOptions::default()).then_some(options),
+        proto3_optional: proto3_optional.then_some(true),
+        ..Default::default()
+    }
+}
+
+fn scalar_type(field_type: &Type) -> FieldType {
+    match field_type {
+        Type::Bool => FieldType::Bool,
+        Type::Bytes => FieldType::Bytes,
+        Type::Double => FieldType::Double,
+        Type::Fixed32 => FieldType::Fixed32,
+        Type::Fixed64 => FieldType::Fixed64,
+        Type::Float => FieldType::Float,
+        Type::Int32 => FieldType::Int32,
+        Type::Int64 => FieldType::Int64,
+        Type::Sfixed32 => FieldType::Sfixed32,
+        Type::Sfixed64 => FieldType::Sfixed64,
+        Type::Sint32 => FieldType::Sint32,
+        Type::Sint64 => FieldType::Sint64,
+        Type::String => FieldType::String,
+        Type::Uint32 => FieldType::Uint32,
+        Type::Uint64 => FieldType::Uint64,
+        Type::Enum(_) => FieldType::Enum,
+        Type::Message(_) | Type::Repeated(_) | Type::Optional(_) | Type::Map(_, _) => {
+            FieldType::Message
+        }
+    }
+}
+
+fn extensions_to_descriptors(
+    context: &mut FileContext,
+    extensions: &[Extension],
+    path: &[i32],
+) -> Vec<FieldDescriptorProto> {
+    let mut res = Vec::new();
+    for (i, extension) in extensions.iter().enumerate() {
+        context.add_location([path, &[i as i32]].concat(), &extension.field.span);
+        let mut descriptor = field_to_descriptor(context, &extension.field);
+        descriptor.extendee = Some(context.type_name(extension.extendee));
+        res.push(descriptor);
+    }
+    res
+}
+
+fn enum_to_descriptor(
+    context: &mut FileContext,
+    enum_scope: &EnumScope,
+    path: Vec<i32>,
+) -> EnumDescriptorProto {
+    context.add_location(path.clone(), &enum_scope.span);
+    let mut res = EnumDescriptorProto {
+        name: Some(enum_scope.name.to_string()),
+        options: Some(EnumOptions {
+            allow_alias: bool_option(&enum_scope.options, "allow_alias"),
+            deprecated: bool_option(&enum_scope.options, "deprecated"),
+            uninterpreted_option: uninterpreted_options(&enum_scope.options),
+        })
+        .filter(|o| *o != EnumOptions::default()),
+        ..Default::default()
+    };
+    for (i, entry) in enum_scope.entries.iter().enumerate() {
+        context.add_location([&path[..], &[ENUM_VALUE, i as i32]].concat(), &entry.span);
+        res.value.push(EnumValueDescriptorProto {
+            name: Some(entry.name.to_string()),
+            number: Some(entry.value as i32),
+            options: Some(EnumValueOptions {
+                deprecated: bool_option(&entry.options, "deprecated"),
+                uninterpreted_option: uninterpreted_options(&entry.options),
+            })
+            .filter(|o| *o != EnumValueOptions::default()),
+        });
+    }
+    for reserved in &enum_scope.reserved {
+        res.reserved_range
+            .extend(reserved.ranges.iter().map(|range| EnumReservedRange {
+                start: Some(range.start as i32),
+                end: Some(range.end as i32),
+            }));
+        res.reserved_name
+            .extend(reserved.names.iter().map(|n| n.to_string()));
+    }
+    res
+}
+
+fn service_to_descriptor(
+    context: &mut FileContext,
+    service: &ServiceScope,
+    path: Vec<i32>,
+) -> ServiceDescriptorProto {
+    context.add_location(path.clone(), &service.span);
+    for (i, rpc) in service.rpcs.iter().enumerate() {
+        context.add_location([&path[..], &[SERVICE_METHOD, i as i32]].concat(), &rpc.span);
+    }
+    ServiceDescriptorProto {
+        name: Some(service.name.to_string()),
+        method: service
+            .rpcs
+            .iter()
+            .map(|rpc| MethodDescriptorProto {
+                name: Some(rpc.name.to_string()),
+                input_type: Some(context.type_name(rpc.input_type)),
+                output_type: Some(context.type_name(rpc.output_type)),
+                options: Some(MethodOptions {
+                    deprecated: bool_option(&rpc.options, "deprecated"),
+                    uninterpreted_option: uninterpreted_options(&rpc.options),
+                    ..Default::default()
+                })
+                .filter(|o| *o != MethodOptions::default()),
+                client_streaming: rpc.client_streaming.then_some(true),
+                server_streaming: rpc.server_streaming.then_some(true),
+            })
+            .collect(),
+        options: Some(ServiceOptions {
+            deprecated: bool_option(&service.options, "deprecated"),
+            uninterpreted_option: uninterpreted_options(&service.options),
+        })
+        .filter(|o| *o != ServiceOptions::default()),
+    }
+}
+
+fn bool_option(options: &[ProtoOption], name: &str) -> Option<bool> {
+    find_option(options, name).and_then(Constant::as_bool)
+}
+
+fn string_option(options: &[ProtoOption], name: &str) -> Option<String> {
+    find_option(options, name)
+        .and_then(Constant::as_str)
+        .map(|value| value.to_string())
+}
+
+fn message_options(options: &[ProtoOption], map_entry: bool) -> Option<MessageOptions> {
+    Some(MessageOptions {
+        deprecated: bool_option(options, "deprecated"),
+        map_entry: map_entry.then_some(true),
+        uninterpreted_option: uninterpreted_options(options),
+        ..Default::default()
+    })
+    .filter(|o| *o != MessageOptions::default())
+}
+
+/// Built-in file options, options of other languages' code generators are kept
+/// so that the descriptors can be passed to them
+fn file_options(options: &[ProtoOption]) -> Option<FileOptions> {
+    let optimize_for = match find_option(options, "optimize_for") {
+        Some(Constant::Identifier(mode)) => OptimizeMode::from_str_name(mode),
+        _ => None,
+    };
+    Some(FileOptions {
+        java_package: string_option(options, "java_package"),
+        java_outer_classname: string_option(options, "java_outer_classname"),
+        java_multiple_files: bool_option(options, "java_multiple_files"),
+        optimize_for: optimize_for.map(|mode| mode as i32),
+        go_package: string_option(options, "go_package"),
+        deprecated: bool_option(options, "deprecated"),
+        cc_enable_arenas: bool_option(options, "cc_enable_arenas"),
+        objc_class_prefix: string_option(options, "objc_class_prefix"),
+        csharp_namespace: string_option(options, "csharp_namespace"),
+        swift_prefix: string_option(options, "swift_prefix"),
+        php_namespace: string_option(options, "php_namespace"),
+        php_metadata_namespace: string_option(options, "php_metadata_namespace"),
+        ruby_package: string_option(options, "ruby_package"),
+        uninterpreted_option: uninterpreted_options(options),
+        ..Default::default()
+    })
+    .filter(|o| *o != FileOptions::default())
+}
+
+/// Custom options like `(my.ext) = 1`, their extensions are not resolved,
+/// so they are kept in the form protoc uses before it interprets them
+fn uninterpreted_options(options: &[ProtoOption]) -> Vec<UninterpretedOption> {
+    options
+        .iter()
+        .filter(|option| matches!(option.name.parts[0], OptionNamePart::Extension(_)))
+        .map(|option| {
+            let name = option
+                .name
+                .parts
+                .iter()
+                .map(|part| match part {
+                    OptionNamePart::Name(name) => NamePart {
+                        name_part: name.to_string(),
+                        is_extension: false,
+                    },
+                    OptionNamePart::Extension(path) => NamePart {
+                        name_part: path.join("."),
+                        is_extension: true,
+                    },
+                })
+                .collect();
+            let mut res = UninterpretedOption {
+                name,
+                ..Default::default()
+            };
+            match &option.value {
+                Constant::Identifier(id) => res.identifier_value = Some(id.to_string()),
+                Constant::Bool(b) => res.identifier_value = Some(b.to_string()),
+                Constant::Int(i) if *i < 0 => res.negative_int_value = Some(*i),
+                Constant::Int(i) => res.positive_int_value = Some(*i as u64),
+                Constant::Uint(i) => res.positive_int_value = Some(*i),
+                Constant::Float(f) => res.double_value = Some(*f),
+                Constant::String(s) => res.string_value = Some(s.as_bytes().to_vec()),
+                // Text format of the message without the enclosing braces
+                value @ (Constant::Aggregate(_) | Constant::List(_)) => {
+                    let text = value.to_string();
+                    res.aggregate_value = Some(text[1..text.len() - 1].trim().to_string());
+                }
+            }
+            res
+        })
+        .collect()
+}
+
+/// Value of the proto3 JSON mapping of descriptors
+enum Json {
+    String(String),
+    Number(i64),
+    Double(f64),
+    Bool(bool),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    fn write(&self, res: &mut String, indent: usize) {
+        match self {
+            Json::String(s) => res.push_str(&json_string(s)),
+            Json::Number(n) => write!(res, "{}", n).unwrap(),
+            Json::Double(n) if n.is_nan() => res.push_str("\"NaN\""),
+            Json::Double(n) if n.is_infinite() && *n > 0f64 => res.push_str("\"Infinity\""),
+            Json::Double(n) if n.is_infinite() => res.push_str("\"-Infinity\""),
+            Json::Double(n) => write!(res, "{}", n).unwrap(),
+            Json::Bool(b) => write!(res, "{}", b).unwrap(),
+            // Paths and spans of source locations are kept on one line
+            Json::Array(items) if items.iter().all(|i| matches!(i, Json::Number(_))) => {
+                res.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        res.push_str(", ");
+                    }
+                    item.write(res, indent);
+                }
+                res.push(']');
+            }
+            Json::Array(items) => {
+                res.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    res.push_str(if i > 0 { ",\n" } else { "\n" });
+                    res.push_str(&"  ".repeat(indent + 1));
+                    item.write(res, indent + 1);
+                }
+                write!(res, "\n{}]", "  ".repeat(indent)).unwrap();
+            }
+            Json::Object(entries) if entries.is_empty() => res.push_str("{}"),
+            Json::Object(entries) => {
+                res.push('{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    res.push_str(if i > 0 { ",\n" } else { "\n" });
+                    write!(res, "{}\"{}\": ", "  ".repeat(indent + 1), key).unwrap();
+                    value.write(res, indent + 1);
+                }
+                write!(res, "\n{}}}", "  ".repeat(indent)).unwrap();
+            }
+        }
+    }
+}
+
+/// Builds a JSON object, fields that are not set are omitted like in the proto3 JSON mapping
+struct JsonObject(Vec<(&'static str, Json)>);
+
+impl JsonObject {
+    fn new() -> Self {
+        JsonObject(Vec::new())
+    }
+    fn string(mut self, key: &'static str, value: &Option<String>) -> Self {
+        if let Some(value) = value {
+            self.0.push((key, Json::String(value.clone())));
+        }
+        self
+    }
+    fn number(mut self, key: &'static str, value: Option<i32>) -> Self {
+        if let Some(value) = value {
+            self.0.push((key, Json::Number(value as i64)));
+        }
+        self
+    }
+    fn double(mut self, key: &'static str, value: Option<f64>) -> Self {
+        if let Some(value) = value {
+            self.0.push((key, Json::Double(value)));
+        }
+        self
+    }
+    fn bool(mut self, key: &'static str, value: Option<bool>) -> Self {
+        if let Some(value) = value {
+            self.0.push((key, Json::Bool(value)));
+        }
+        self
+    }
+    fn enum_name(mut self, key: &'static str, value: Option<&str>) -> Self {
+        if let Some(value) = value {
+            self.0.push((key, Json::String(value.to_string())));
+        }
+        self
+    }
+    fn object(mut self, key: &'static str, value: Option<Json>) -> Self {
+        if let Some(value) = value {
+            self.0.push((key, value));
+        }
+        self
+    }
+    fn array(mut self, key: &'static str, items: Vec<Json>) -> Self {
+        if !items.is_empty() {
+            self.0.push((key, Json::Array(items)));
+        }
+        self
+    }
+    fn build(self) -> Json {
+        Json::Object(self.0)
+    }
+}
+
+/// Renders the descriptor set in the proto3 JSON mapping, e.g. to diff schemas
+pub(crate) fn descriptor_set_to_json(descriptor_set: &FileDescriptorSet) -> String {
+    let json = JsonObject::new()
+        .array("file", descriptor_set.file.iter().map(file_json).collect())
+        .build();
+    let mut res = String::new();
+    json.write(&mut res, 0);
+    res.push('\n');
+    res
+}
+
+fn strings_json(values: &[String]) -> Vec<Json> {
+    values.iter().map(|v| Json::String(v.clone())).collect()
+}
+
+fn numbers_json(values: &[i32]) -> Vec<Json> {
+    values.iter().map(|v| Json::Number(*v as i64)).collect()
+}
+
+/// 64-bit integers are strings and bytes are base64 in the proto3 JSON mapping
+fn uninterpreted_json(options: &[UninterpretedOption]) -> Vec<Json> {
+    options
+        .iter()
+        .map(|o| {
+            let name = o
+                .name
+                .iter()
+                .map(|part| {
+                    JsonObject::new()
+                        .string("namePart", &Some(part.name_part.clone()))
+                        .bool("isExtension", Some(part.is_extension))
+                        .build()
+                })
+                .collect();
+            JsonObject::new()
+                .array("name", name)
+                .string("identifierValue", &o.identifier_value)
+                .string(
+                    "positiveIntValue",
+                    &o.positive_int_value.map(|v| v.to_string()),
+                )
+                .string(
+                    "negativeIntValue",
+                    &o.negative_int_value.map(|v| v.to_string()),
+                )
+                .double("doubleValue", o.double_value)
+                .string("stringValue", &o.string_value.as_deref().map(base64))
+                .string("aggregateValue", &o.aggregate_value)
+                .build()
+        })
+        .collect()
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut res = String::new();
+    for chunk in bytes.chunks(3) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |bits, (i, b)| bits | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            res.push(match i <= chunk.len() {
+                true => ALPHABET[(bits >> (18 - 6 * i) & 63) as usize] as char,
+                false => '=',
+            });
+        }
+    }
+    res
+}
+
+fn file_json(file: &FileDescriptorProto) -> Json {
+    let options = file.options.as_ref().map(|o| {
+        JsonObject::new()
+            .string("javaPackage", &o.java_package)
+            .string("javaOuterClassname", &o.java_outer_classname)
+            .bool("javaMultipleFiles", o.java_multiple_files)
+            .enum_name(
+                "optimizeFor",
+                o.optimize_for.map(|_| o.optimize_for().as_str_name()),
+            )
+            .string("goPackage", &o.go_package)
+            .bool("deprecated", o.deprecated)
+            .bool("ccEnableArenas", o.cc_enable_arenas)
+            .string("objcClassPrefix", &o.objc_class_prefix)
+            .string("csharpNamespace", &o.csharp_namespace)
+            .string("swiftPrefix", &o.swift_prefix)
+            .string("phpNamespace", &o.php_namespace)
+            .string("phpMetadataNamespace", &o.php_metadata_namespace)
+            .string("rubyPackage", &o.ruby_package)
+            .array(
+                "uninterpretedOption",
+                uninterpreted_json(&o.uninterpreted_option),
+            )
+            .build()
+    });
+    let source_code_info = file.source_code_info.as_ref().map(|info| {
+        let locations = info
+            .location
+            .iter()
+            .map(|location| {
+                JsonObject::new()
+                    .array("path", numbers_json(&location.path))
+                    .array("span", numbers_json(&location.span))
+                    .build()
+            })
+            .collect();
+        JsonObject::new().array("location", locations).build()
+    });
+    JsonObject::new()
+        .string("name", &file.name)
+        .string("package", &file.package)
+        .array("dependency", strings_json(&file.dependency))
+        .array(
+            "messageType",
+            file.message_type.iter().map(message_json).collect(),
+        )
+        .array("enumType", file.enum_type.iter().map(enum_json).collect())
+        .array("service", file.service.iter().map(service_json).collect())
+        .array("extension", file.extension.iter().map(field_json).collect())
+        .object("options", options)
+        .object("sourceCodeInfo", source_code_info)
+        .string("syntax", &file.syntax)
+        .build()
+}
+
+fn message_json(message: &DescriptorProto) -> Json {
+    let range_json = |start: Option<i32>, end: Option<i32>| {
+        JsonObject::new()
+            .number("start", start)
+            .number("end", end)
+            .build()
+    };
+    let options = message.options.as_ref().map(|o| {
+        JsonObject::new()
+            .bool("mapEntry", o.map_entry)
+            .bool("deprecated", o.deprecated)
+            .array(
+                "uninterpretedOption",
+                uninterpreted_json(&o.uninterpreted_option),
+            )
+            .build()
+    });
+    JsonObject::new()
+        .string("name", &message.name)
+        .array("field", message.field.iter().map(field_json).collect())
+        .array(
+            "extension",
+            message.extension.iter().map(field_json).collect(),
+        )
+        .array(
+            "nestedType",
+            message.nested_type.iter().map(message_json).collect(),
+        )
+        .array(
+            "enumType",
+            message.enum_type.iter().map(enum_json).collect(),
+        )
+        .array(
+            "extensionRange",
+            message
+                .extension_range
+                .iter()
+                .map(|r| range_json(r.start, r.end))
+                .collect(),
+        )
+        .array(
+            "oneofDecl",
+            message
+                .oneof_decl
+                .iter()
+                .map(|o| {
+                    let options = o.options.as_ref().map(|o| {
+                        JsonObject::new()
+                            .array(
+                                "uninterpretedOption",
+                                uninterpreted_json(&o.uninterpreted_option),
+                            )
+                            .build()
+                    });
+                    JsonObject::new()
+                        .string("name", &o.name)
+                        .object("options", options)
+                        .build()
+                })
+                .collect(),
+        )
+        .object("options", options)
+        .array(
+            "reservedRange",
+            message
+                .reserved_range
+                .iter()
+                .map(|r| range_json(r.start, r.end))
+                .collect(),
+        )
+        .array("reservedName", strings_json(&message.reserved_name))
+        .build()
+}
+
+fn field_json(field: &FieldDescriptorProto) -> Json {
+    let options = field.options.as_ref().map(|o| {
+        JsonObject::new()
+            .bool("packed", o.packed)
+            .bool("deprecated", o.deprecated)
+            .array(
+                "uninterpretedOption",
+                uninterpreted_json(&o.uninterpreted_option),
+            )
+            .build()
+    });
+    JsonObject::new()
+        .string("name", &field.name)
+        .string("extendee", &field.extendee)
+        .number("number", field.number)
+        .enum_name("label", field.label.map(|_| field.label().as_str_name()))
+        .enum_name("type", field.r#type.map(|_| field.r#type().as_str_name()))
+        .string("typeName", &field.type_name)
+        .string("defaultValue", &field.default_value)
+        .object("options", options)
+        .number("oneofIndex", field.oneof_index)
+        .string("jsonName", &field.json_name)
+        .bool("proto3Optional", field.proto3_optional)
+        .build()
+}
+
+fn enum_json(enum_type: &EnumDescriptorProto) -> Json {
+    let options_json = |deprecated: Option<bool>, uninterpreted: &[UninterpretedOption]| {
+        JsonObject::new()
+            .bool("deprecated", deprecated)
+            .array("uninterpretedOption", uninterpreted_json(uninterpreted))
+            .build()
+    };
+    let values = enum_type
+        .value
+        .iter()
+        .map(|value| {
+            JsonObject::new()
+                .string("name", &value.name)
+                .number("number", value.number)
+                .object(
+                    "options",
+                    value
+                        .options
+                        .as_ref()
+                        .map(|o| options_json(o.deprecated, &o.uninterpreted_option)),
+                )
+                .build()
+        })
+        .collect();
+    let options = enum_type.options.as_ref().map(|o| {
+        JsonObject::new()
+            .bool("allowAlias", o.allow_alias)
+            .bool("deprecated", o.deprecated)
+            .array(
+                "uninterpretedOption",
+                uninterpreted_json(&o.uninterpreted_option),
+            )
+            .build()
+    });
+    JsonObject::new()
+        .string("name", &enum_type.name)
+        .array("value", values)
+        .object("options", options)
+        .array(
+            "reservedRange",
+            enum_type
+                .reserved_range
+                .iter()
+                .map(|r| {
+                    JsonObject::new()
+                        .number("start", r.start)
+                        .number("end", r.end)
+                        .build()
+                })
+                .collect(),
+        )
+        .array("reservedName", strings_json(&enum_type.reserved_name))
+        .build()
+}
+
+fn service_json(service: &ServiceDescriptorProto) -> Json {
+    let options_json = |deprecated: Option<bool>, uninterpreted: &[UninterpretedOption]| {
+        JsonObject::new()
+            .bool("deprecated", deprecated)
+            .array("uninterpretedOption", uninterpreted_json(uninterpreted))
+            .build()
+    };
+    let methods = service
+        .method
+        .iter()
+        .map(|method| {
+            JsonObject::new()
+                .string("name", &method.name)
+                .string("inputType", &method.input_type)
+                .string("outputType", &method.output_type)
+                .object(
+                    "options",
+                    method
+                        .options
+                        .as_ref()
+                        .map(|o| options_json(o.deprecated, &o.uninterpreted_option)),
+                )
+                .bool("clientStreaming", method.client_streaming)
+                .bool("serverStreaming", method.server_streaming)
+                .build()
+        })
+        .collect();
+    JsonObject::new()
+        .string("name", &service.name)
+        .array("method", methods)
+        .object(
+            "options",
+            service
+                .options
+                .as_ref()
+                .map(|o| options_json(o.deprecated, &o.uninterpreted_option)),
+        )
+        .build()
+}
+
+#[cfg(test)]
+mod test_descriptor_set {
+    use prost_types::{
+        UninterpretedOption,
+        field_descriptor_proto::{Label, Type},
+        uninterpreted_option::NamePart,
+    };
+
+    use super::{descriptor_set_to_json, root_scope_to_descriptor_set};
+    use crate::{proto::descriptor::read_descriptor_root_scope, test_utils::build_files};
+
+    #[test]
+    fn serializes_resolved_files() {
+        let root = build_files(&[
+            (
+                "common/id.proto",
+                "syntax = \"proto3\";\npackage common;\nmessage Id { string value = 1; }\n",
+            ),
+            (
+                "app/user.proto",
+                r#"syntax = "proto3";
+package app;
+import "common/id.proto";
+message User {
+  common.Id id = 1;
+  optional string nick_name = 2;
+  map<string, common.Id> friends = 3;
+  reserved 10 to 12;
+}
+service Users {
+  rpc Get(User) returns (User);
+}
+"#,
+            ),
+        ]);
+        let descriptor_set = root_scope_to_descriptor_set(&root);
+        let names = descriptor_set
+            .file
+            .iter()
+            .map(|f| f.name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["common/id.proto", "app/user.proto"]);
+
+        let file = &descriptor_set.file[1];
+        assert_eq!(file.dependency, vec!["common/id.proto"]);
+        let user = &file.message_type[0];
+        assert_eq!(user.field[0].type_name(), ".common.Id");
+        assert_eq!(user.field[1].label(), Label::Optional);
+        assert_eq!(user.field[1].r#type(), Type::String);
+        assert!(user.field[1].proto3_optional());
+        assert_eq!(user.field[1].oneof_index, Some(0));
+        assert_eq!(user.oneof_decl[0].name(), "_nick_name");
+        assert_eq!(user.field[2].label(), Label::Repeated);
+        assert_eq!(user.field[2].type_name(), ".app.User.FriendsEntry");
+        let entry = &user.nested_type[0];
+        assert!(entry.options.as_ref().unwrap().map_entry());
+        assert_eq!(entry.field[1].type_name(), ".common.Id");
+        assert_eq!(user.reserved_range[0].end, Some(13));
+        let locations = &file.source_code_info.as_ref().unwrap().location;
+        let span = |path: &[i32]| {
+            let location = locations.iter().find(|l| l.path == path).unwrap();
+            location.span.clone()
+        };
+        assert_eq!(span(&[4, 0]), vec![3, 0, 8, 1]);
+        assert_eq!(span(&[4, 0, 2, 1]), vec![5, 2, 31]);
+        assert_eq!(span(&[6, 0]), vec![9, 0, 11, 1]);
+        assert_eq!(span(&[6, 0, 2, 0]), vec![10, 2, 31]);
+
+        // Descriptors are read back into the same schema
+        let root = read_descriptor_root_scope(&descriptor_set.file, |_| true).unwrap();
+        assert_eq!(
+            root_scope_to_descriptor_set(&root).file[1].message_type,
+            file.message_type
+        );
+        assert!(descriptor_set_to_json(&descriptor_set).contains("\"proto3Optional\": true"));
+    }
+
+    #[test]
+    fn keeps_custom_options_uninterpreted() {
+        let root = build_files(&[(
+            "app/user.proto",
+            r#"syntax = "proto3";
+package app;
+option (app.owner) = "team";
+message User {
+  int64 visits = 1 [(ts.int64) = STRING, (app.meta).limit = -1, (app.meta) = { label: "v" }];
+  oneof contact {
+    option (app.required) = true;
+    string email = 2;
+  }
+}
+"#,
+        )]);
+        let descriptor_set = root_scope_to_descriptor_set(&root);
+        let extension = |name: &str| NamePart {
+            name_part: name.to_string(),
+            is_extension: true,
+        };
+        let file = &descriptor_set.file[0];
+        assert_eq!(
+            file.options.as_ref().unwrap().uninterpreted_option,
+            vec![UninterpretedOption {
+                name: vec![extension("app.owner")],
+                string_value: Some(b"team".to_vec()),
+                ..Default::default()
+            }]
+        );
+        let field = &file.message_type[0].field[0];
+        assert_eq!(
+            field.options.as_ref().unwrap().uninterpreted_option,
+            vec![
+                UninterpretedOption {
+                    name: vec![extension("ts.int64")],
+                    identifier_value: Some("STRING".to_string()),
+                    ..Default::default()
+                },
+                UninterpretedOption {
+                    name: vec![
+                        extension("app.meta"),
+                        NamePart {
+                            name_part: "limit".to_string(),
+                            is_extension: false,
+                        },
+                    ],
+                    negative_int_value: Some(-1),
+                    ..Default::default()
+                },
+                UninterpretedOption {
+                    name: vec![extension("app.meta")],
+                    aggregate_value: Some("label: \"v\"".to_string()),
+                    ..Default::default()
+                },
+            ]
+        );
+        let oneof = &file.message_type[0].oneof_decl[0];
+        assert_eq!(
+            oneof.options.as_ref().unwrap().uninterpreted_option,
+            vec![UninterpretedOption {
+                name: vec![extension("app.required")],
+                identifier_value: Some("true".to_string()),
+                ..Default::default()
+            }]
+        );
+        let mut locations = file.source_code_info.as_ref().unwrap().location.iter();
+        let oneof_location = locations.find(|l| l.path == [4, 0, 8, 0]).unwrap();
+        assert_eq!(oneof_location.span, vec![5, 2, 8, 3]);
+        assert!(descriptor_set_to_json(&descriptor_set).contains("\"stringValue\": \"dGVhbQ==\""));
+
+        // Options of oneofs are read back from descriptors
+        let root = read_descriptor_root_scope(&descriptor_set.file, |_| true).unwrap();
+        assert_eq!(
+            root_scope_to_descriptor_set(&root).file[0].message_type[0].oneof_decl,
+            file.message_type[0].oneof_decl
+        );
+    }
+}
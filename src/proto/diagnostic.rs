@@ -0,0 +1,257 @@
+use std::{collections::HashMap, fmt::Write, path::Path, rc::Rc};
+
+use super::{error::ProtoError, span::Span};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const GREEN: &str = "\x1b[1;32m";
+const BLUE: &str = "\x1b[1;34m";
+
+/// Renders errors in rustc style: message, location and the source line
+/// with the erroneous part underlined
+pub(crate) struct DiagnosticRenderer {
+    color: bool,
+    sources: HashMap<Rc<Path>, Option<Vec<String>>>,
+}
+
+impl DiagnosticRenderer {
+    pub fn new(color: bool) -> Self {
+        Self {
+            color,
+            sources: HashMap::new(),
+        }
+    }
+
+    pub fn render(&mut self, error: &ProtoError) -> String {
+        let mut res = String::new();
+        let span = match error.span() {
+            Some(span) => span,
+            None => {
+                self.write_title(&mut res, "error", RED, &error.message());
+                return res;
+            }
+        };
+        let gutter_width = std::iter::once(span)
+            .chain(error.notes().iter().map(|note| &note.span))
+            .map(|span| span.start_line.to_string().len())
+            .max()
+            .unwrap_or(1);
+
+        self.write_title(&mut res, "error", RED, &error.message());
+        self.write_snippet(&mut res, span, gutter_width, '^', RED);
+        for note in error.notes() {
+            self.write_title(&mut res, "note", GREEN, &note.message);
+            self.write_snippet(&mut res, &note.span, gutter_width, '-', BLUE);
+        }
+        res
+    }
+
+    fn paint(&self, text: &str, color: &str) -> String {
+        if self.color {
+            format!("{}{}{}", color, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn write_title(&self, res: &mut String, level: &str, color: &str, message: &str) {
+        writeln!(
+            res,
+            "{}{}",
+            self.paint(level, color),
+            self.paint(&format!(": {}", message), BOLD)
+        )
+        .unwrap();
+    }
+
+    fn write_snippet(
+        &mut self,
+        res: &mut String,
+        span: &Span,
+        gutter_width: usize,
+        marker: char,
+        color: &str,
+    ) {
+        let padding = " ".repeat(gutter_width);
+        writeln!(res, "{}{} {}", padding, self.paint("-->", BLUE), span).unwrap();
+        let line = match self.source_line(&span.file_path, span.start_line) {
+            Some(line) => line,
+            None => return,
+        };
+        let bar = self.paint("|", BLUE);
+        writeln!(res, "{} {}", padding, bar).unwrap();
+        writeln!(
+            res,
+            "{} {} {}",
+            self.paint(&format!("{:>gutter_width$}", span.start_line), BLUE),
+            bar,
+            line
+        )
+        .unwrap();
+
+        // Tabs are kept, so that the underline is aligned with the source line
+        let indent = line
+            .chars()
+            .take(span.start_column.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        let line_length = line.chars().count();
+        let end_column = if span.end_line == span.start_line {
+            span.end_column.min(line_length)
+        } else {
+            line_length
+        };
+        let underline = marker
+            .to_string()
+            .repeat((end_column + 1).saturating_sub(span.start_column).max(1));
+        writeln!(
+            res,
+            "{} {} {}{}",
+            padding,
+            bar,
+            indent,
+            self.paint(&underline, color)
+        )
+        .unwrap();
+    }
+
+    fn source_line(&mut self, file_path: &Rc<Path>, line: usize) -> Option<String> {
+        let lines = self.sources.entry(Rc::clone(file_path)).or_insert_with(|| {
+            std::fs::read_to_string(file_path)
+                .ok()
+                .map(|content| content.lines().map(|l| l.to_string()).collect())
+        });
+        lines.as_ref()?.get(line.checked_sub(1)?).cloned()
+    }
+}
+
+/// Renders the error as a single line JSON object, so that tools do not depend on the wording
+pub(crate) fn render_json(error: &ProtoError) -> String {
+    let mut res = String::from("{\"severity\":\"error\"");
+    write!(res, ",\"code\":{}", json_string(error.code())).unwrap();
+    write!(res, ",\"message\":{}", json_string(&error.message())).unwrap();
+    write_json_span(&mut res, error.span());
+    res.push_str(",\"related\":[");
+    for (i, note) in error.notes().iter().enumerate() {
+        if i > 0 {
+            res.push(',');
+        }
+        write!(res, "{{\"message\":{}", json_string(&note.message)).unwrap();
+        write_json_span(&mut res, Some(&note.span));
+        res.push('}');
+    }
+    res.push_str("]}");
+    res
+}
+
+/// Writes file and 1-based inclusive line and column range, `null` if the location is unknown
+fn write_json_span(res: &mut String, span: Option<&Span>) {
+    let Some(span) = span else {
+        res.push_str(
+            ",\"file\":null,\"start_line\":null,\"start_column\":null,\"end_line\":null,\"end_column\":null",
+        );
+        return;
+    };
+    write!(
+        res,
+        ",\"file\":{},\"start_line\":{},\"start_column\":{},\"end_line\":{},\"end_column\":{}",
+        json_string(&span.file_path.to_string_lossy()),
+        span.start_line,
+        span.start_column,
+        span.end_line,
+        span.end_column
+    )
+    .unwrap();
+}
+
+pub(super) fn json_string(value: &str) -> String {
+    let mut res = String::with_capacity(value.len() + 2);
+    res.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(res, "\\u{:04x}", c as u32).unwrap(),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+#[cfg(test)]
+mod test_render {
+    use std::{path::Path, rc::Rc};
+
+    use crate::{
+        proto::{error::semantic_error, span::Span},
+        test_utils::TempDir,
+    };
+
+    use super::{DiagnosticRenderer, render_json};
+
+    #[test]
+    fn renders_source_line_and_notes() {
+        let temp_dir = TempDir::new("diagnostic_renders_source_line");
+        let file_path = temp_dir.path().join("test.proto");
+        std::fs::write(
+            &file_path,
+            "message Foo {\n  int32 a = 1;\n  string b = 1;\n}\n",
+        )
+        .unwrap();
+        let file_path: Rc<Path> = Rc::from(file_path.as_path());
+        let span = |line: usize, start_column: usize, end_column: usize| Span {
+            file_path: Rc::clone(&file_path),
+            start_line: line,
+            start_column,
+            end_line: line,
+            end_column,
+        };
+        let error = semantic_error("Duplicate number 1", &span(3, 3, 15))
+            .with_note("first defined here", Some(&span(2, 3, 14)));
+
+        let rendered = DiagnosticRenderer::new(false).render(&error);
+
+        let path = file_path.to_string_lossy();
+        assert_eq!(
+            rendered,
+            format!(
+                "error: Duplicate number 1\n \
+                 --> {path}:3:3\n  \
+                 |\n\
+                 3 |   string b = 1;\n  \
+                 |   ^^^^^^^^^^^^^\n\
+                 note: first defined here\n \
+                 --> {path}:2:3\n  \
+                 |\n\
+                 2 |   int32 a = 1;\n  \
+                 |   ------------\n"
+            )
+        );
+    }
+
+    #[test]
+    fn renders_json() {
+        let span = |line: usize| Span {
+            file_path: Rc::from(Path::new("a.proto")),
+            start_line: line,
+            start_column: 3,
+            end_line: line,
+            end_column: 14,
+        };
+        let error = semantic_error("Field \"b\" is duplicated", &span(3))
+            .with_note("first defined here", Some(&span(2)));
+
+        assert_eq!(
+            render_json(&error),
+            "{\"severity\":\"error\",\"code\":\"semantic_error\",\"message\":\"Field \\\"b\\\" is duplicated\",\
+             \"file\":\"a.proto\",\"start_line\":3,\"start_column\":3,\"end_line\":3,\"end_column\":14,\
+             \"related\":[{\"message\":\"first defined here\",\"file\":\"a.proto\",\
+             \"start_line\":2,\"start_column\":3,\"end_line\":2,\"end_column\":14}]}"
+        );
+    }
+}
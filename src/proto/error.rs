@@ -1,41 +1,140 @@
 use std::{
     fmt::{Display, Formatter},
     io,
-    path::PathBuf,
 };
 
-use super::lexems::{self};
+use super::{
+    lexems::{self},
+    span::Span,
+};
+
+/// Related location mentioned by the error, e.g. the first declaration of a duplicate
+#[derive(Debug)]
+pub struct Note {
+    pub span: Span,
+    pub message: String,
+}
 
 #[derive(Debug)]
-pub(crate) enum ProtoError {
+pub enum ProtoError {
     Default(String),
     CannotOpenFile(io::Error),
     IOError(io::Error),
     UnknownCharacter {
-        file_path: PathBuf,
-        line: usize,
-        column: usize,
+        span: Span,
         char: char,
     },
     InvalidIntLiteral {
+        span: Span,
         literal: String,
-        file_path: PathBuf,
-        line: usize,
-        start_column: usize,
-        end_column: usize,
     },
     SyntaxError {
-        file_path: PathBuf,
-        line: usize,
-        column: usize,
+        span: Span,
+        message: String,
+    },
+    /// Schema is syntactically correct, but declaration at span is invalid
+    SemanticError {
+        span: Span,
         message: String,
+        notes: Vec<Note>,
     },
+    /// Several independent errors reported at once
+    Multiple(Vec<ProtoError>),
 }
 
 impl ProtoError {
     pub fn new(value: &str) -> Self {
         ProtoError::Default(value.to_string())
     }
+
+    /// Fails with all collected errors if there are any
+    pub fn from_errors(errors: Vec<ProtoError>) -> Result<(), ProtoError> {
+        let mut errors = errors
+            .into_iter()
+            .flat_map(ProtoError::into_errors)
+            .collect::<Vec<_>>();
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ => Err(ProtoError::Multiple(errors)),
+        }
+    }
+
+    /// Location of the error, if it is known
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            ProtoError::UnknownCharacter { span, .. }
+            | ProtoError::InvalidIntLiteral { span, .. }
+            | ProtoError::SyntaxError { span, .. }
+            | ProtoError::SemanticError { span, .. } => Some(span),
+            _ => None,
+        }
+    }
+
+    /// Description of the error without its location
+    pub fn message(&self) -> String {
+        match self {
+            ProtoError::UnknownCharacter { char, .. } => format!("Unknown character: {}", char),
+            ProtoError::InvalidIntLiteral { literal, .. } => {
+                format!("Invalid integer literal: \"{}\"", literal)
+            }
+            ProtoError::SyntaxError { message, .. } | ProtoError::SemanticError { message, .. } => {
+                message.clone()
+            }
+            err => err.to_string(),
+        }
+    }
+
+    /// Stable identifier of the kind of the error
+    pub fn code(&self) -> &'static str {
+        match self {
+            ProtoError::Default(_) => "error",
+            ProtoError::CannotOpenFile(_) => "cannot_open_file",
+            ProtoError::IOError(_) => "io_error",
+            ProtoError::UnknownCharacter { .. } => "unknown_character",
+            ProtoError::InvalidIntLiteral { .. } => "invalid_int_literal",
+            ProtoError::SyntaxError { .. } => "syntax_error",
+            ProtoError::SemanticError { .. } => "semantic_error",
+            ProtoError::Multiple(_) => "multiple",
+        }
+    }
+
+    pub fn notes(&self) -> &[Note] {
+        match self {
+            ProtoError::SemanticError { notes, .. } => notes,
+            _ => &[],
+        }
+    }
+
+    /// Attaches location to the error that has none
+    pub fn located(self, span: Option<&Span>) -> Self {
+        match (self, span) {
+            (ProtoError::Default(message), Some(span)) => semantic_error(message, span),
+            (err, _) => err,
+        }
+    }
+
+    /// Adds a note pointing at a related declaration to the located error
+    pub fn with_note<T: Into<String>>(mut self, message: T, span: Option<&Span>) -> Self {
+        if let (ProtoError::SemanticError { notes, .. }, Some(span)) = (&mut self, span) {
+            notes.push(Note {
+                span: span.clone(),
+                message: message.into(),
+            });
+        }
+        self
+    }
+
+    /// Flattens `Multiple` into the list of single errors
+    pub fn into_errors(self) -> Vec<ProtoError> {
+        match self {
+            ProtoError::Multiple(errors) => errors
+                .into_iter()
+                .flat_map(ProtoError::into_errors)
+                .collect(),
+            err => vec![err],
+        }
+    }
 }
 
 impl Display for ProtoError {
@@ -45,48 +144,31 @@ impl Display for ProtoError {
             Default(s) => f.write_str(s.as_str()),
             CannotOpenFile(err) => write!(f, "Cannot open file: {}", err),
             IOError(err) => write!(f, "IO Error: {}", err),
-            UnknownCharacter {
-                file_path,
-                line,
-                column,
-                char,
-            } => write!(
-                f,
-                "Unknown character at {}:{}:{}: {}",
-                file_path.to_string_lossy(),
-                line,
-                column,
-                char
-            ),
-            SyntaxError {
-                file_path,
-                line,
-                column,
+            UnknownCharacter { span, char } => write!(f, "Unknown character at {}: {}", span, char),
+            SyntaxError { span, message } => write!(f, "{}: SyntaxError: {}", span, message),
+            SemanticError {
+                span,
                 message,
-            } => write!(
-                f,
-                "{}:{}:{}: SyntaxError: {}",
-                file_path.to_string_lossy(),
-                line,
-                column,
-                message
-            ),
-            InvalidIntLiteral {
-                file_path,
-                literal,
-                end_column,
-                line,
-                start_column,
+                notes,
             } => {
+                write!(f, "{}: Error: {}", span, message)?;
+                for note in notes {
+                    write!(f, "\n{}: Note: {}", note.span, note.message)?;
+                }
+                Ok(())
+            }
+            Multiple(errors) => {
+                for (i, err) in errors.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str("\n")?;
+                    }
+                    write!(f, "{}", err)?;
+                }
+                Ok(())
+            }
+            InvalidIntLiteral { span, literal } => {
                 write!(f, "Invalid integer literal: \"{}\"", literal)?;
-                write!(
-                    f,
-                    " at {}:{}:{} to {}",
-                    file_path.to_string_lossy(),
-                    line,
-                    start_column,
-                    end_column
-                )
+                write!(f, " at {} to {}", span, span.end_column)
             }
         }
     }
@@ -103,9 +185,20 @@ pub(super) fn syntax_error<T: Into<String>>(
     lexem: &lexems::LocatedLexem,
 ) -> ProtoError {
     ProtoError::SyntaxError {
-        file_path: lexem.range.start.file_path.to_path_buf(),
-        line: lexem.range.start.line,
-        column: lexem.range.start.column,
+        span: Span::from_range(&lexem.range),
         message: format!("{}, but {} occurred", message.into(), lexem.lexem).into(),
     }
 }
+
+pub(super) fn semantic_error<T: Into<String>>(message: T, span: &Span) -> ProtoError {
+    ProtoError::SemanticError {
+        span: span.clone(),
+        message: message.into(),
+        notes: Vec::new(),
+    }
+}
+
+/// Semantic error if the location of the declaration is known, plain error otherwise
+pub(super) fn located_error<T: Into<String>>(message: T, span: Option<&Span>) -> ProtoError {
+    ProtoError::Default(message.into()).located(span)
+}
@@ -0,0 +1,83 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    error::ProtoError,
+    file_system::FileSystem,
+    id_generator::IdGenerator,
+    package::{ProtoFile, parse_proto_file},
+};
+
+struct CachedFile {
+    content: String,
+    /// `None` if the file could not be parsed, it is still watched then
+    file: Option<ProtoFile>,
+    used: bool,
+}
+
+/// Parsed files kept between compilations, so that in watch mode
+/// only files changed since the previous compilation are parsed again.
+/// Ids are generated by the cache, so they stay unique among cached and new declarations
+pub(crate) struct ProtoFileCache {
+    id_generator: IdGenerator,
+    files: HashMap<PathBuf, CachedFile>,
+}
+
+impl ProtoFileCache {
+    pub fn new() -> Self {
+        Self {
+            id_generator: IdGenerator::new(),
+            files: HashMap::new(),
+        }
+    }
+
+    pub(super) fn id_generator(&mut self) -> &mut IdGenerator {
+        &mut self.id_generator
+    }
+
+    /// Parsed file, which is parsed again only if its content changed
+    pub(super) fn read(
+        &mut self,
+        file_system: &dyn FileSystem,
+        path: &Path,
+    ) -> Result<ProtoFile, ProtoError> {
+        let content = file_system
+            .read_to_string(path)
+            .map_err(ProtoError::CannotOpenFile)?;
+        if let Some(CachedFile {
+            content: cached_content,
+            file: Some(file),
+            used,
+        }) = self.files.get_mut(path)
+            && *cached_content == content
+        {
+            *used = true;
+            return Ok(file.clone());
+        }
+        let file = parse_proto_file(&mut self.id_generator, path, &content);
+        self.files.insert(
+            path.to_path_buf(),
+            CachedFile {
+                content,
+                file: file.as_ref().ok().cloned(),
+                used: true,
+            },
+        );
+        file
+    }
+
+    /// Forgets files which were not read since the previous call
+    pub fn finish_compilation(&mut self) {
+        self.files.retain(|_, cached| cached.used);
+        for cached in self.files.values_mut() {
+            cached.used = false;
+        }
+    }
+
+    /// Paths of the files read by the last compilation
+    pub fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.files.keys()
+    }
+}
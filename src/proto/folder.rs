@@ -1,11 +1,14 @@
-use std::{io, path::PathBuf};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 
 use tracing::instrument;
 
 #[derive(Debug)]
 pub(crate) struct ProtoFolder {
     pub files: Vec<PathBuf>,
-    path: PathBuf,
+    pub path: PathBuf,
 }
 
 impl std::fmt::Display for ProtoFolder {
@@ -18,7 +21,8 @@ impl std::fmt::Display for ProtoFolder {
     }
 }
 
-/// Recursively goes through the folder and collects all .proto files
+/// Recursively goes through the folder and collects all .proto files.
+/// Files are sorted by path, so errors are reported in the same order on all file systems
 #[instrument(ret)]
 pub(crate) fn read_proto_folder(folder_path: PathBuf) -> io::Result<ProtoFolder> {
     let mut folders = vec![folder_path.clone()];
@@ -36,9 +40,187 @@ pub(crate) fn read_proto_folder(folder_path: PathBuf) -> io::Result<ProtoFolder>
             }
         }
     }
+    all_proto_file_paths.sort();
 
     Ok(ProtoFolder {
         files: all_proto_file_paths,
         path: folder_path,
     })
 }
+
+/// Groups proto files given on the command line by the directory their imports are relative to.
+/// Directory is compiled entirely and imports of its files are relative to it.
+/// Files and glob patterns are relative to the first include path containing them,
+/// or to the current directory
+pub(crate) fn read_proto_inputs(
+    inputs: &[PathBuf],
+    include_paths: &[PathBuf],
+    cwd: &Path,
+) -> io::Result<Vec<ProtoFolder>> {
+    let mut res: Vec<ProtoFolder> = Vec::new();
+    let mut loose_files: Vec<PathBuf> = Vec::new();
+    for input in inputs {
+        if input.is_dir() {
+            res.push(read_proto_folder(input.clone())?);
+        } else if is_glob(input) {
+            let files = read_glob(input)?;
+            if files.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{} does not match any .proto file", input.display()),
+                ));
+            }
+            loose_files.extend(files);
+        } else if input.is_file() {
+            loose_files.push(input.clone());
+        } else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is not found", input.display()),
+            ));
+        }
+    }
+    group_files(&mut res, loose_files, include_paths, cwd);
+    Ok(res)
+}
+
+/// Adds the files to the folder of the first include path containing them,
+/// or to the folder of `default_root`
+pub(crate) fn group_files(
+    folders: &mut Vec<ProtoFolder>,
+    files: Vec<PathBuf>,
+    include_paths: &[PathBuf],
+    default_root: &Path,
+) {
+    for file in files {
+        let root = include_paths
+            .iter()
+            .find(|include_path| file.starts_with(include_path))
+            .map(PathBuf::as_path)
+            .unwrap_or(default_root);
+        match folders.iter_mut().find(|folder| folder.path == root) {
+            Some(folder) => folder.files.push(file),
+            None => folders.push(ProtoFolder {
+                files: vec![file],
+                path: root.to_path_buf(),
+            }),
+        }
+    }
+}
+
+fn is_glob(path: &Path) -> bool {
+    path.to_string_lossy().contains(['*', '?'])
+}
+
+/// Collects .proto files matching the pattern.
+/// `*` and `?` match characters of a single path component, `**` matches any number of components
+fn read_glob(pattern: &Path) -> io::Result<Vec<PathBuf>> {
+    let components = pattern
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    let literal_len = components
+        .iter()
+        .take_while(|c| !c.contains(['*', '?']))
+        .count();
+    let base: PathBuf = components[..literal_len].iter().collect();
+    let pattern = &components[literal_len..];
+    if !base.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut res = Vec::new();
+    for file in read_proto_folder(base.clone())?.files {
+        let relative = file
+            .strip_prefix(&base)
+            .unwrap()
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        if matches_components(pattern, &relative) {
+            res.push(file);
+        }
+    }
+    Ok(res)
+}
+
+fn matches_components(pattern: &[String], path: &[String]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            (0..=path.len()).any(|skipped| matches_components(rest, &path[skipped..]))
+        }
+        Some((first, rest)) => match path.split_first() {
+            Some((name, path_rest)) => {
+                let first = first.chars().collect::<Vec<_>>();
+                let name = name.chars().collect::<Vec<_>>();
+                matches_name(&first, &name) && matches_components(rest, path_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+fn matches_name(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skipped| matches_name(rest, &name[skipped..])),
+        Some(('?', rest)) => !name.is_empty() && matches_name(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && matches_name(rest, &name[1..]),
+    }
+}
+
+#[cfg(test)]
+mod test_folder {
+    use super::{matches_components, read_proto_folder};
+    use crate::test_utils::TempDir;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        let split = |s: &str| s.split('/').map(String::from).collect::<Vec<_>>();
+        matches_components(&split(pattern), &split(path))
+    }
+
+    #[test]
+    fn matches_glob_patterns() {
+        assert!(matches("*.proto", "user.proto"));
+        assert!(!matches("*.proto", "api/user.proto"));
+        assert!(matches("api/**/*.proto", "api/user.proto"));
+        assert!(matches("api/**/*.proto", "api/v1/admin/user.proto"));
+        assert!(matches("api/v?/user.proto", "api/v1/user.proto"));
+        assert!(!matches("api/v?/user.proto", "api/v10/user.proto"));
+        assert!(!matches("api/**/*.proto", "web/user.proto"));
+    }
+
+    #[test]
+    fn reads_files_in_path_order() {
+        let temp_dir = TempDir::new("folder_reads_files_in_path_order");
+        let dir = temp_dir.path().to_path_buf();
+        for name in [
+            "z.proto",
+            "b/c.proto",
+            "a.proto",
+            "b/a.proto",
+            "m/n/o.proto",
+            "notes.txt",
+        ] {
+            let path = dir.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        let folder = read_proto_folder(dir.clone()).unwrap();
+        let files = folder
+            .files
+            .iter()
+            .map(|f| f.strip_prefix(&dir).unwrap().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            files,
+            [
+                "a.proto",
+                "b/a.proto",
+                "b/c.proto",
+                "m/n/o.proto",
+                "z.proto"
+            ]
+        );
+    }
+}
@@ -1,8 +1,8 @@
-use std::{fmt::Display, path::Path, rc::Rc};
+use std::{fmt::Display, ops::Deref, path::Path, rc::Rc};
 
-use super::error::ProtoError;
+use super::{error::ProtoError, span::Span};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub(super) enum Lexem {
     Id(Rc<str>),
     Equal,
@@ -10,6 +10,9 @@ pub(super) enum Lexem {
     SemiColon,
     Dot,
     IntLiteral(i64),
+    /// Int literal above `i64::MAX`, valid only as an option value
+    UintLiteral(u64),
+    FloatLiteral(f64),
     OpenCurly,
     CloseCurly,
     OpenParens,
@@ -31,6 +34,8 @@ impl Display for Lexem {
             Lexem::SemiColon => write!(f, ";"),
             Lexem::Dot => write!(f, "."),
             Lexem::IntLiteral(i) => write!(f, "{}", i),
+            Lexem::UintLiteral(i) => write!(f, "{}", i),
+            Lexem::FloatLiteral(v) => write!(f, "{}", v),
             Lexem::OpenCurly => write!(f, "{{"),
             Lexem::Comma => write!(f, ","),
             Lexem::CloseCurly => write!(f, "}}"),
@@ -121,6 +126,7 @@ pub(super) fn read_lexems<'file_path>(
     let located_chars = read_chars(file_path, content);
     let mut current_char_index = 0;
     let mut located_lexems = Vec::new();
+    let mut errors = Vec::new();
     while current_char_index < located_chars.len() {
         let located_char = located_chars[current_char_index];
         let LocatedChar { char, position } = located_char;
@@ -128,20 +134,31 @@ pub(super) fn read_lexems<'file_path>(
             current_char_index += 1;
             continue;
         }
-        if char::is_digit(char, 10) || char == '-' {
-            let located_int_lexem = try_read_int(&located_chars, &mut current_char_index)?;
-            located_lexems.push(located_int_lexem);
-            continue;
-        }
-        if is_id_char(char) {
-            let located_id_lexem = try_read_id(&located_chars, &mut current_char_index)?;
-            located_lexems.push(located_id_lexem);
-            continue;
-        }
-        if char == '"' {
-            let string_lexem = try_read_string_literal(&located_chars, &mut current_char_index)?;
-            located_lexems.push(string_lexem);
-            continue;
+        let start_index = current_char_index;
+        let read_lexem = if char::is_digit(char, 10) || char == '-' {
+            Some(try_read_number(&located_chars, &mut current_char_index))
+        } else if is_id_char(char) {
+            Some(try_read_id(&located_chars, &mut current_char_index))
+        } else if char == '"' || char == '\'' {
+            Some(try_read_string_literal(
+                &located_chars,
+                &mut current_char_index,
+            ))
+        } else {
+            None
+        };
+        match read_lexem {
+            Some(Ok(located_lexem)) => {
+                located_lexems.push(located_lexem);
+                continue;
+            }
+            Some(Err(err)) => {
+                // Skips the rest of the broken lexem and continues with the next one
+                errors.push(err);
+                current_char_index = skip_lexem(&located_chars, start_index);
+                continue;
+            }
+            None => {}
         }
         if char == '/' {
             match located_chars.get(current_char_index + 1) {
@@ -153,10 +170,9 @@ pub(super) fn read_lexems<'file_path>(
                     try_read_multiline_comment(&located_chars, &mut current_char_index)?;
                 }
                 _ => {
-                    return Err(ProtoError::UnknownCharacter {
-                        file_path: position.file_path.to_path_buf(),
-                        line: position.line,
-                        column: position.column,
+                    current_char_index += 1;
+                    errors.push(ProtoError::UnknownCharacter {
+                        span: Span::at(&position),
                         char: char,
                     });
                 }
@@ -191,13 +207,12 @@ pub(super) fn read_lexems<'file_path>(
             located_lexems.push(located_lexem);
             continue;
         }
-        return Err(ProtoError::UnknownCharacter {
-            file_path: position.file_path.to_path_buf(),
-            line: position.line,
-            column: position.column,
+        errors.push(ProtoError::UnknownCharacter {
+            span: Span::at(&position),
             char: char,
         });
     }
+    ProtoError::from_errors(errors)?;
     let last_char_position = located_chars[located_chars.len() - 1].position;
     located_lexems.push(LocatedLexem {
         lexem: Lexem::EOF,
@@ -210,6 +225,18 @@ pub(super) fn read_lexems<'file_path>(
     Ok(located_lexems)
 }
 
+/// Returns index of the first whitespace or punctuation after the lexem starting at `start_index`
+fn skip_lexem(located_chars: &[LocatedChar], start_index: usize) -> usize {
+    let mut index = start_index + 1;
+    while located_chars
+        .get(index)
+        .is_some_and(|c| is_id_char(c.char) || c.char == '.')
+    {
+        index += 1;
+    }
+    index
+}
+
 fn try_read_id<'file_path>(
     located_chars: &[LocatedChar<'file_path>],
     located_char_index: &mut usize,
@@ -237,55 +264,128 @@ fn try_read_id<'file_path>(
     let located_lexem: LocatedLexem<'file_path> = LocatedLexem { lexem, range };
     Ok(located_lexem)
 }
-fn try_read_int<'file_path>(
+fn try_read_number<'file_path>(
     located_chars: &[LocatedChar<'file_path>],
     located_char_index: &mut usize,
 ) -> Result<LocatedLexem<'file_path>, ProtoError> {
-    let mut digits = String::new();
     let start = located_chars[*located_char_index].position;
     let mut end = start;
-    let mut minus_found = false;
-    loop {
-        if *located_char_index >= located_chars.len() {
-            break;
+    let mut literal = String::new();
+    let mut is_float = false;
+
+    let peek = |index: usize| located_chars.get(index).map(|c| c.char);
+    let mut take = |literal: &mut String, index: &mut usize| {
+        let LocatedChar { char, position } = located_chars[*index];
+        literal.push(char);
+        end = position;
+        *index += 1;
+    };
+
+    if peek(*located_char_index) == Some('-') {
+        take(&mut literal, located_char_index);
+        if peek(*located_char_index).is_some_and(|c| c.is_alphabetic()) {
+            let id_lexem = try_read_id(located_chars, located_char_index)?;
+            let value = match id_lexem.lexem {
+                Lexem::Id(id) if id.deref() == "inf" => f64::NEG_INFINITY,
+                Lexem::Id(id) if id.deref() == "nan" => f64::NAN,
+                _ => {
+                    return Err(ProtoError::UnknownCharacter {
+                        span: Span::at(&start),
+                        char: '-',
+                    });
+                }
+            };
+            return Ok(LocatedLexem {
+                lexem: Lexem::FloatLiteral(value),
+                range: SourceRange {
+                    start,
+                    end: id_lexem.range.end,
+                },
+            });
         }
-        let LocatedChar { char, position } = located_chars[*located_char_index];
-        if char::is_digit(char, 10) {
-            end = position;
-            *located_char_index += 1;
-            digits.push(char);
-            continue;
+    }
+
+    let is_hex = peek(*located_char_index) == Some('0')
+        && matches!(peek(*located_char_index + 1), Some('x') | Some('X'));
+    if is_hex {
+        take(&mut literal, located_char_index);
+        take(&mut literal, located_char_index);
+        while peek(*located_char_index).is_some_and(|c| c.is_ascii_hexdigit()) {
+            take(&mut literal, located_char_index);
         }
-        if !minus_found && char == '-' {
-            end = position;
-            *located_char_index += 1;
-            digits.push(char);
-            minus_found = true;
-            continue;
+    } else {
+        while peek(*located_char_index).is_some_and(|c| c.is_ascii_digit()) {
+            take(&mut literal, located_char_index);
         }
-        break;
-    }
-    if digits.len() <= 0 {
-        unreachable!()
-    }
-    let num = i64::from_str_radix(&digits, 10);
-    match num {
-        Ok(value) => {
-            let lexem = Lexem::IntLiteral(value);
-            let range = SourceRange { start, end };
-            let located_lexem: LocatedLexem<'file_path> = LocatedLexem { lexem, range };
-            Ok(located_lexem)
+        if peek(*located_char_index) == Some('.') {
+            is_float = true;
+            take(&mut literal, located_char_index);
+            while peek(*located_char_index).is_some_and(|c| c.is_ascii_digit()) {
+                take(&mut literal, located_char_index);
+            }
         }
-        Err(_) => {
-            return Err(ProtoError::InvalidIntLiteral {
-                literal: digits,
-                file_path: start.file_path.to_path_buf(),
-                line: start.line,
-                start_column: start.column,
-                end_column: end.column,
-            });
+        let has_exponent = matches!(peek(*located_char_index), Some('e') | Some('E'))
+            && match peek(*located_char_index + 1) {
+                Some('+') | Some('-') => {
+                    peek(*located_char_index + 2).is_some_and(|c| c.is_ascii_digit())
+                }
+                Some(c) => c.is_ascii_digit(),
+                None => false,
+            };
+        if has_exponent {
+            is_float = true;
+            take(&mut literal, located_char_index);
+            if matches!(peek(*located_char_index), Some('+') | Some('-')) {
+                take(&mut literal, located_char_index);
+            }
+            while peek(*located_char_index).is_some_and(|c| c.is_ascii_digit()) {
+                take(&mut literal, located_char_index);
+            }
         }
     }
+
+    let range = SourceRange { start, end };
+    let invalid_literal = || ProtoError::InvalidIntLiteral {
+        span: Span::from_range(&range),
+        literal: literal.clone(),
+    };
+
+    if is_float {
+        let value: f64 = literal.parse().map_err(|_| invalid_literal())?;
+        return Ok(LocatedLexem {
+            lexem: Lexem::FloatLiteral(value),
+            range,
+        });
+    }
+
+    let (negative, unsigned) = match literal.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, literal.as_str()),
+    };
+    let (digits, radix) = if is_hex {
+        (&unsigned[2..], 16)
+    } else if unsigned.len() > 1 && unsigned.starts_with('0') {
+        (&unsigned[1..], 8)
+    } else {
+        (unsigned, 10)
+    };
+    if digits.is_empty() {
+        return Err(invalid_literal());
+    }
+    // The magnitude is parsed as u64, so that both i64::MIN and u64::MAX can be written
+    let magnitude = u64::from_str_radix(digits, radix).map_err(|_| invalid_literal())?;
+    let lexem = if negative {
+        Lexem::IntLiteral(
+            0i64.checked_sub_unsigned(magnitude)
+                .ok_or_else(invalid_literal)?,
+        )
+    } else {
+        match i64::try_from(magnitude) {
+            Ok(value) => Lexem::IntLiteral(value),
+            Err(_) => Lexem::UintLiteral(magnitude),
+        }
+    };
+    Ok(LocatedLexem { lexem, range })
 }
 
 fn try_read_single_line_comment<'file_path>(
@@ -335,7 +435,8 @@ fn try_read_string_literal<'file_path>(
     located_char_index: &mut usize,
 ) -> Result<LocatedLexem<'file_path>, ProtoError> {
     let mut string_literal = String::new();
-    let mut last_char = located_chars[*located_char_index].char;
+    let quote = located_chars[*located_char_index].char;
+    let mut last_char = quote;
     let start = located_chars[*located_char_index].position;
     let mut end = start;
     *located_char_index += 1;
@@ -344,7 +445,7 @@ fn try_read_string_literal<'file_path>(
             break;
         }
         let LocatedChar { char, position } = located_chars[*located_char_index];
-        if char == '"' && last_char != '\\' {
+        if char == quote && last_char != '\\' {
             *located_char_index += 1;
             end = position;
             break;
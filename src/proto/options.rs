@@ -0,0 +1,174 @@
+use std::{ops::Deref, rc::Rc};
+
+/// Single component of an option name.
+///
+/// `(my.ext).field` consists of `Extension(["my", "ext"])` followed by `Name("field")`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionNamePart {
+    Name(Rc<str>),
+    Extension(Vec<Rc<str>>),
+}
+
+impl std::fmt::Display for OptionNamePart {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            OptionNamePart::Name(name) => write!(f, "{}", name),
+            OptionNamePart::Extension(path) => write!(f, "({})", path.join(".")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptionName {
+    pub parts: Vec<OptionNamePart>,
+}
+
+impl OptionName {
+    /// Returns true for built-in options like `json_name` or `packed`
+    pub fn is_simple(&self, name: &str) -> bool {
+        match &self.parts[..] {
+            [OptionNamePart::Name(n)] => n.deref() == name,
+            _ => false,
+        }
+    }
+
+    /// Returns true for custom options like `(my.ext)` with the given extension path
+    pub fn is_extension(&self, path: &[&str]) -> bool {
+        match &self.parts[..] {
+            [OptionNamePart::Extension(p)] => p.iter().map(Rc::deref).eq(path.iter().copied()),
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for OptionName {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (i, part) in self.parts.iter().enumerate() {
+            if i > 0 {
+                write!(f, ".")?;
+            }
+            write!(f, "{}", part)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Constant {
+    Identifier(Rc<str>),
+    Int(i64),
+    /// Int above `i64::MAX`, smaller values are always `Int`
+    Uint(u64),
+    Float(f64),
+    String(Rc<str>),
+    Bool(bool),
+    /// Text format message literal: `{ key: value, nested { ... } }`
+    Aggregate(Vec<(Rc<str>, Constant)>),
+    /// Text format list literal: `[1, 2, 3]`
+    List(Vec<Constant>),
+}
+
+// Declarations are compared structurally, floats by their bits so that
+// a `nan` literal is equal to itself.
+impl PartialEq for Constant {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Constant::Identifier(a), Constant::Identifier(b)) => a == b,
+            (Constant::Int(a), Constant::Int(b)) => a == b,
+            (Constant::Uint(a), Constant::Uint(b)) => a == b,
+            (Constant::Float(a), Constant::Float(b)) => a.to_bits() == b.to_bits(),
+            (Constant::String(a), Constant::String(b)) => a == b,
+            (Constant::Bool(a), Constant::Bool(b)) => a == b,
+            (Constant::Aggregate(a), Constant::Aggregate(b)) => a == b,
+            (Constant::List(a), Constant::List(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Constant {}
+
+impl Constant {
+    pub fn as_str(&self) -> Option<Rc<str>> {
+        match self {
+            Constant::String(s) => Some(Rc::clone(s)),
+            _ => None,
+        }
+    }
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Constant::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Constant {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Constant::Identifier(id) => write!(f, "{}", id),
+            Constant::Int(i) => write!(f, "{}", i),
+            Constant::Uint(i) => write!(f, "{}", i),
+            Constant::Float(v) if v.is_nan() => write!(f, "nan"),
+            Constant::Float(v) if v.is_infinite() && *v > 0f64 => write!(f, "inf"),
+            Constant::Float(v) if v.is_infinite() => write!(f, "-inf"),
+            Constant::Float(v) if v.fract() == 0f64 => write!(f, "{:.1}", v),
+            Constant::Float(v) => write!(f, "{}", v),
+            Constant::String(s) => write!(f, "\"{}\"", s),
+            Constant::Bool(b) => write!(f, "{}", b),
+            Constant::Aggregate(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, " {}: {}", key, value)?;
+                }
+                write!(f, " }}")
+            }
+            Constant::List(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtoOption {
+    pub name: OptionName,
+    pub value: Constant,
+}
+
+impl std::fmt::Display for ProtoOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} = {}", self.name, self.value)
+    }
+}
+
+/// Returns value of the last built-in option with the given name
+pub(crate) fn find_option<'a>(options: &'a [ProtoOption], name: &str) -> Option<&'a Constant> {
+    options
+        .iter()
+        .rev()
+        .find(|option| option.name.is_simple(name))
+        .map(|option| &option.value)
+}
+
+/// Returns value of the last custom option with the given extension path
+pub(crate) fn find_extension_option<'a>(
+    options: &'a [ProtoOption],
+    path: &[&str],
+) -> Option<&'a Constant> {
+    options
+        .iter()
+        .rev()
+        .find(|option| option.name.is_extension(path))
+        .map(|option| &option.value)
+}
@@ -1,18 +1,29 @@
 use super::{
     compiler::ts::ast,
-    error::ProtoError,
+    descriptor::default_json_name,
+    error::{ProtoError, located_error},
+    file_cache::ProtoFileCache,
+    file_system::FileSystem,
+    folder::ProtoFolder,
     id_generator::{IdGenerator, UniqueId},
     lexems,
+    options::{ProtoOption, find_option},
     proto_scope::{
-        builder::{ScopeBuilder, ScopeBuilderTrait, well_known::is_well_known_import},
+        builder::{
+            ScopeBuilder, ScopeBuilderTrait,
+            well_known::{is_descriptor_import, is_well_known_import},
+        },
         root_scope::RootScope,
+        validation::validate,
     },
+    span::Span,
     syntax,
 };
 use lexems::read_lexems;
 use std::{
+    cell::RefCell,
+    collections::HashSet,
     fmt::Display,
-    io::Read,
     ops::Deref,
     path::{Path, PathBuf},
     rc::Rc,
@@ -21,7 +32,7 @@ use syntax::parse_package;
 use tracing::instrument;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub(crate) enum ProtoVersion {
+pub enum ProtoVersion {
     Proto2,
     Proto3,
 }
@@ -37,43 +48,77 @@ impl std::fmt::Display for ProtoVersion {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) struct EnumEntry {
+pub struct EnumEntry {
     pub name: Rc<str>,
     pub value: i64,
+    pub options: Vec<ProtoOption>,
+    pub span: Option<Span>,
 }
 
 impl From<(Rc<str>, i64)> for EnumEntry {
     fn from((name, value): (Rc<str>, i64)) -> Self {
-        Self { name, value }
+        Self {
+            name,
+            value,
+            options: Vec::new(),
+            span: None,
+        }
     }
 }
 
 impl std::fmt::Display for EnumEntry {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{} = {}", self.name, self.value)
+        write!(f, "{} = {}", self.name, self.value)?;
+        write_field_options(f, &self.options)
+    }
+}
+
+fn write_field_options(f: &mut std::fmt::Formatter, options: &[ProtoOption]) -> std::fmt::Result {
+    if options.is_empty() {
+        return Ok(());
+    }
+    write!(f, " [")?;
+    for (i, option) in options.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", option)?;
     }
+    write!(f, "]")
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) struct EnumDeclaration {
+pub struct EnumDeclaration {
     pub id: usize,
     pub name: Rc<str>,
     pub entries: Vec<EnumEntry>,
+    pub options: Vec<ProtoOption>,
+    pub reserved: Vec<ReservedDeclaration>,
+    pub span: Option<Span>,
 }
 impl UniqueId for EnumDeclaration {
-    type Args = (Rc<str>, Vec<EnumEntry>);
+    type Args = (Rc<str>, Vec<EnumEntry>, Vec<ProtoOption>);
 
     fn create_with_id(id: usize, args: Self::Args) -> Self {
         EnumDeclaration {
             id,
             name: args.0,
             entries: args.1,
+            options: args.2,
+            reserved: Vec::new(),
+            span: None,
         }
     }
 }
 impl std::fmt::Display for EnumDeclaration {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "enum {} {{\n", self.name)?;
+        for option in &self.options {
+            writeln!(f, "  option {};", option)?;
+        }
+        for reserved in &self.reserved {
+            writeln!(f, "  {};", reserved)?;
+        }
         for entry in &self.entries {
             let entry_str = format!("{};", entry);
             let lines = entry_str.lines();
@@ -285,7 +330,7 @@ impl Clone for Type {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) enum FieldTypeReference {
+pub enum FieldTypeReference {
     IdPath(Vec<Rc<str>>),
     Repeated(Box<FieldTypeReference>),
     Optional(Box<FieldTypeReference>),
@@ -311,7 +356,7 @@ impl FieldTypeReference {
     pub fn id(id: &str) -> Self {
         Self::IdPath(vec![id.into()])
     }
-    pub fn trivial_resolve(&self) -> Option<Type> {
+    pub(crate) fn trivial_resolve(&self) -> Option<Type> {
         match self {
             FieldTypeReference::IdPath(_) => None,
             FieldTypeReference::Repeated(t) => {
@@ -439,11 +484,12 @@ impl std::fmt::Display for FieldTypeReference {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) struct FieldDeclaration {
+pub struct FieldDeclaration {
     pub name: Rc<str>,
     pub field_type_ref: FieldTypeReference,
     pub tag: i64,
-    pub attributes: Vec<(Rc<str>, Rc<str>)>,
+    pub options: Vec<ProtoOption>,
+    pub span: Option<Span>,
 }
 
 impl FieldDeclaration {
@@ -452,7 +498,8 @@ impl FieldDeclaration {
             name: Rc::from(name),
             field_type_ref,
             tag,
-            attributes: Vec::new(),
+            options: Vec::new(),
+            span: None,
         }
     }
 }
@@ -462,56 +509,172 @@ pub(crate) struct Field {
     pub name: Rc<str>,
     pub field_type: Type,
     pub tag: i64,
-    pub attributes: Vec<(Rc<str>, Rc<str>)>,
+    pub options: Vec<ProtoOption>,
+    /// Repeated scalars are written in the packed encoding
+    pub packed: bool,
+    pub span: Option<Span>,
 }
 
 impl Field {
+    /// Repeated scalar fields are packed by default in proto3 and unpacked in proto2,
+    /// `[packed = ...]` overrides the default
+    pub fn packed_option(options: &[ProtoOption], version: ProtoVersion) -> bool {
+        find_option(options, "packed")
+            .and_then(|value| value.as_bool())
+            .unwrap_or(version == ProtoVersion::Proto3)
+    }
+
     pub fn json_name(&self) -> Rc<str> {
-        for (key, value) in &self.attributes {
-            if key.deref() == "json_name" {
-                return Rc::clone(value);
-            }
-        }
-        Rc::clone(&self.name)
+        find_option(&self.options, "json_name")
+            .and_then(|value| value.as_str())
+            .unwrap_or_else(|| Rc::clone(&self.name))
+    }
+
+    /// Key of the field in the proto3 JSON mapping, `json_name` or the name in lowerCamelCase
+    pub fn json_key(&self) -> Rc<str> {
+        find_option(&self.options, "json_name")
+            .and_then(|value| value.as_str())
+            .unwrap_or_else(|| default_json_name(&self.name).into())
     }
 }
 
 impl std::fmt::Display for FieldDeclaration {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{} {} = {}", self.field_type_ref, self.name, self.tag)?;
-        if !self.attributes.is_empty() {
-            write!(f, " [")?;
-            for (i, (name, value)) in self.attributes.iter().enumerate() {
-                if i > 0 {
-                    write!(f, ", ")?;
-                }
-                write!(f, "{} = \"{}\"", name, value)?;
-            }
-            write!(f, "]")?;
-        };
-        Ok(())
+        write_field_options(f, &self.options)
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct OneOfGroup {
     pub name: Rc<str>,
-    pub options: Vec<Field>,
+    pub fields: Vec<Field>,
+    pub options: Vec<ProtoOption>,
+    pub span: Option<Span>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) struct OneOfDeclaration {
+pub struct OneOfDeclaration {
     pub name: Rc<str>,
-    pub options: Vec<FieldDeclaration>,
+    pub fields: Vec<FieldDeclaration>,
+    pub options: Vec<ProtoOption>,
+    pub span: Option<Span>,
 }
 
 impl std::fmt::Display for OneOfDeclaration {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "oneof {} {{\n", self.name)?;
+        writeln!(f, "oneof {} {{", self.name)?;
         for option in &self.options {
-            write!(f, "  {};\n", option)?;
+            writeln!(f, "  option {};", option)?;
         }
-        write!(f, "}}\n")
+        for field in &self.fields {
+            writeln!(f, "  {};", field)?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+/// Largest field number allowed by protobuf, `max` in ranges
+pub(crate) const MAX_FIELD_NUMBER: i64 = 536_870_911;
+
+/// Inclusive range of field numbers: `5`, `100 to 199` or `1000 to max`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldRange {
+    pub start: i64,
+    pub end: i64,
+}
+
+/// Numbers allowed for message fields
+pub(crate) const FIELD_NUMBERS: FieldRange = FieldRange {
+    start: 1,
+    end: MAX_FIELD_NUMBER,
+};
+
+/// Numbers allowed for enum values
+pub(crate) const ENUM_VALUES: FieldRange = FieldRange {
+    start: i32::MIN as i64,
+    end: i32::MAX as i64,
+};
+
+impl FieldRange {
+    pub fn contains(&self, tag: i64) -> bool {
+        self.start <= tag && tag <= self.end
+    }
+}
+
+impl std::fmt::Display for FieldRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.start == self.end {
+            return write!(f, "{}", self.start);
+        }
+        if self.end == MAX_FIELD_NUMBER {
+            return write!(f, "{} to max", self.start);
+        }
+        write!(f, "{} to {}", self.start, self.end)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtensionsDeclaration {
+    pub ranges: Vec<FieldRange>,
+    pub options: Vec<ProtoOption>,
+}
+
+impl std::fmt::Display for ExtensionsDeclaration {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "extensions ")?;
+        for (i, range) in self.ranges.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", range)?;
+        }
+        write_field_options(f, &self.options)
+    }
+}
+
+/// `reserved 2, 9 to 11;` or `reserved "foo", "bar";`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReservedDeclaration {
+    pub ranges: Vec<FieldRange>,
+    pub names: Vec<Rc<str>>,
+    pub span: Option<Span>,
+}
+
+impl ReservedDeclaration {
+    pub fn contains_number(&self, number: i64) -> bool {
+        self.ranges.iter().any(|range| range.contains(number))
+    }
+
+    pub fn contains_name(&self, name: &str) -> bool {
+        self.names.iter().any(|reserved| reserved.deref() == name)
+    }
+}
+
+impl std::fmt::Display for ReservedDeclaration {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "reserved ")?;
+        let ranges = self.ranges.iter().map(|range| range.to_string());
+        let names = self.names.iter().map(|name| format!("\"{}\"", name));
+        write!(f, "{}", ranges.chain(names).collect::<Vec<_>>().join(", "))
+    }
+}
+
+/// `extend Foo { ... }`, fields are added to the extended message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtendDeclaration {
+    pub extendee: FieldTypeReference,
+    pub fields: Vec<FieldDeclaration>,
+    pub extendee_span: Option<Span>,
+}
+
+impl std::fmt::Display for ExtendDeclaration {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "extend {} {{", self.extendee)?;
+        for field in &self.fields {
+            writeln!(f, "  {};", field)?;
+        }
+        writeln!(f, "}}")
     }
 }
 
@@ -520,12 +683,23 @@ pub(crate) enum MessageEntry {
     Field(Field),
     #[allow(dead_code)]
     OneOf(OneOfGroup),
+    /// Field declared by `extend` block of this message
+    Extension(Field),
 }
+/// Extension field declared in a file or a message, `extendee` is the id of the extended message
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) enum MessageDeclarationEntry {
+pub(crate) struct Extension {
+    pub extendee: usize,
+    pub field: Field,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MessageDeclarationEntry {
     Field(FieldDeclaration),
     Declaration(Declaration),
     OneOf(OneOfDeclaration),
+    Extensions(ExtensionsDeclaration),
+    Reserved(ReservedDeclaration),
 }
 impl std::fmt::Display for MessageDeclarationEntry {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -534,6 +708,8 @@ impl std::fmt::Display for MessageDeclarationEntry {
             Field(field) => write!(f, "{};", field),
             Declaration(decl) => write!(f, "\n{}", decl),
             OneOf(one_of_decl) => write!(f, "\n{}", one_of_decl),
+            Extensions(extensions) => write!(f, "{};", extensions),
+            Reserved(reserved) => write!(f, "{};", reserved),
         }
     }
 }
@@ -545,20 +721,24 @@ impl From<Declaration> for MessageDeclarationEntry {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) struct MessageDeclaration {
+pub struct MessageDeclaration {
     pub id: usize,
     pub name: Rc<str>,
     pub entries: Vec<MessageDeclarationEntry>,
+    pub options: Vec<ProtoOption>,
+    pub span: Option<Span>,
 }
 
 impl UniqueId for MessageDeclaration {
-    type Args = (Rc<str>, Vec<MessageDeclarationEntry>);
+    type Args = (Rc<str>, Vec<MessageDeclarationEntry>, Vec<ProtoOption>);
 
     fn create_with_id(id: usize, args: Self::Args) -> Self {
         MessageDeclaration {
             id,
             name: args.0,
             entries: args.1,
+            options: args.2,
+            span: None,
         }
     }
 }
@@ -566,6 +746,9 @@ impl UniqueId for MessageDeclaration {
 impl std::fmt::Display for MessageDeclaration {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "message {} {{\n", self.name)?;
+        for option in &self.options {
+            writeln!(f, "  option {};", option)?;
+        }
         for entry in &self.entries {
             let entry_str = format!("{}", entry);
             let lines = entry_str.lines();
@@ -578,9 +761,97 @@ impl std::fmt::Display for MessageDeclaration {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) enum Declaration {
+pub struct RpcDeclaration {
+    pub name: Rc<str>,
+    pub input_type: FieldTypeReference,
+    pub client_streaming: bool,
+    pub output_type: FieldTypeReference,
+    pub server_streaming: bool,
+    pub options: Vec<ProtoOption>,
+    pub span: Option<Span>,
+    pub input_span: Option<Span>,
+    pub output_span: Option<Span>,
+}
+
+impl std::fmt::Display for RpcDeclaration {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let stream = |is_stream: bool| if is_stream { "stream " } else { "" };
+        write!(
+            f,
+            "rpc {}({}{}) returns ({}{})",
+            self.name,
+            stream(self.client_streaming),
+            self.input_type,
+            stream(self.server_streaming),
+            self.output_type
+        )?;
+        if self.options.is_empty() {
+            return write!(f, ";");
+        }
+        writeln!(f, " {{")?;
+        for option in &self.options {
+            writeln!(f, "  option {};", option)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+/// Resolved rpc, input and output are ids of message declarations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Rpc {
+    pub name: Rc<str>,
+    pub input_type: usize,
+    pub client_streaming: bool,
+    pub output_type: usize,
+    pub server_streaming: bool,
+    pub options: Vec<ProtoOption>,
+    pub span: Option<Span>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceDeclaration {
+    pub id: usize,
+    pub name: Rc<str>,
+    pub rpcs: Vec<RpcDeclaration>,
+    pub options: Vec<ProtoOption>,
+    pub span: Option<Span>,
+}
+
+impl UniqueId for ServiceDeclaration {
+    type Args = (Rc<str>, Vec<RpcDeclaration>, Vec<ProtoOption>);
+
+    fn create_with_id(id: usize, args: Self::Args) -> Self {
+        ServiceDeclaration {
+            id,
+            name: args.0,
+            rpcs: args.1,
+            options: args.2,
+            span: None,
+        }
+    }
+}
+
+impl std::fmt::Display for ServiceDeclaration {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "service {} {{", self.name)?;
+        for option in &self.options {
+            writeln!(f, "  option {};", option)?;
+        }
+        for rpc in &self.rpcs {
+            for line in rpc.to_string().lines() {
+                writeln!(f, "  {}", line)?;
+            }
+        }
+        writeln!(f, "}}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Declaration {
     Enum(EnumDeclaration),
     Message(MessageDeclaration),
+    Service(ServiceDeclaration),
+    Extend(ExtendDeclaration),
 }
 
 impl From<EnumDeclaration> for Declaration {
@@ -593,6 +864,16 @@ impl From<MessageDeclaration> for Declaration {
         Declaration::Message(decl)
     }
 }
+impl From<ServiceDeclaration> for Declaration {
+    fn from(decl: ServiceDeclaration) -> Self {
+        Declaration::Service(decl)
+    }
+}
+impl From<ExtendDeclaration> for Declaration {
+    fn from(decl: ExtendDeclaration) -> Self {
+        Declaration::Extend(decl)
+    }
+}
 
 impl std::fmt::Display for Declaration {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -600,19 +881,35 @@ impl std::fmt::Display for Declaration {
         match self {
             Enum(e) => write!(f, "{}", e),
             Message(m) => write!(f, "{}", m),
+            Service(s) => write!(f, "{}", s),
+            Extend(e) => write!(f, "{}", e),
         }
     }
 }
 
-#[derive(Debug)]
-pub(crate) struct ImportPath {
+#[derive(Debug, Clone)]
+pub struct ImportPath {
     pub file_name: Rc<str>,
     pub packages: Vec<Rc<str>>,
+    /// Location of the import statement, imports are compared without it
+    pub span: Option<Span>,
+}
+
+impl ImportPath {
+    /// Path of the imported file relative to the source or include directory
+    pub fn to_path_buf(&self) -> PathBuf {
+        let mut res: PathBuf = self.packages.iter().map(|p| p.deref()).collect();
+        res.push(self.file_name.deref());
+        res
+    }
 }
 
 impl Display for ImportPath {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}/{}", self.packages.join("/"), self.file_name)
+        for package in &self.packages {
+            write!(f, "{}/", package)?;
+        }
+        write!(f, "{}", self.file_name)
     }
 }
 
@@ -640,13 +937,14 @@ impl Ord for ImportPath {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub(crate) struct ProtoFile {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtoFile {
     pub version: ProtoVersion,
     pub declarations: Vec<Declaration>,
     pub imports: Vec<ImportPath>,
     pub path: Vec<Rc<str>>,
     pub name: Rc<str>,
+    pub options: Vec<ProtoOption>,
 }
 
 impl std::fmt::Display for ProtoFile {
@@ -667,6 +965,13 @@ impl std::fmt::Display for ProtoFile {
             write!(f, "\npackage {};\n", self.path.join("."))?;
         }
 
+        if !self.options.is_empty() {
+            writeln!(f)?;
+            for option in &self.options {
+                writeln!(f, "option {};", option)?;
+            }
+        }
+
         for decl in &self.declarations {
             writeln!(f)?;
             writeln!(f, "{}", decl)?;
@@ -676,31 +981,115 @@ impl std::fmt::Display for ProtoFile {
     }
 }
 
-#[instrument]
-pub(crate) fn read_root_scope(files: &[PathBuf]) -> Result<RootScope, ProtoError> {
+#[instrument(skip(file_system, cache))]
+/// Loads the entry files and the files they import from their folders and `include_paths`.
+/// Imported files are generated only if `include_imports` is set,
+/// otherwise they are used for type resolution only.
+/// Files that did not change since they were put into the cache are not parsed again
+pub(crate) fn read_root_scope(
+    file_system: &dyn FileSystem,
+    proto_folders: &[ProtoFolder],
+    include_paths: &[PathBuf],
+    include_imports: bool,
+    cache: &mut ProtoFileCache,
+) -> Result<RootScope, ProtoError> {
     let builder = ScopeBuilder::new_ref();
-    let mut id_generator = IdGenerator::new();
-    for file in files {
-        let proto_file = read_proto_file(&mut id_generator, file)?;
-        for imprt in proto_file
-            .imports
+    let mut errors = Vec::new();
+    let mut loaded: HashSet<PathBuf> = HashSet::new();
+    let mut imports: Vec<ImportPath> = Vec::new();
+    let mut missing: HashSet<PathBuf> = HashSet::new();
+    for proto_folder in proto_folders {
+        for file in &proto_folder.files {
+            let relative_path = file.strip_prefix(&proto_folder.path).unwrap_or(file);
+            if !loaded.insert(relative_path.to_path_buf()) {
+                continue;
+            }
+            match load_proto_file(&builder, file_system, cache, file, true) {
+                Ok(file_imports) => imports.extend(file_imports),
+                Err(err) => errors.push(err),
+            }
+        }
+    }
+    let search_paths = proto_folders
+        .iter()
+        .map(|folder| &folder.path)
+        .chain(include_paths)
+        .collect::<Vec<_>>();
+    while let Some(import) = imports.pop() {
+        let import_path = import.to_path_buf();
+        let not_found =
+            || located_error(format!("File not found: {}", import), import.span.as_ref());
+        // Each import statement of a missing file is reported
+        if missing.contains(&import_path) {
+            errors.push(not_found());
+            continue;
+        }
+        if !loaded.insert(import_path.clone()) {
+            continue;
+        }
+        let Some(file) = search_paths
             .iter()
-            .filter(|imp| is_well_known_import(imp))
-        {
-            builder.load_well_known(&mut id_generator, &imprt.file_name);
+            .map(|search_path| search_path.join(&import_path))
+            .find(|file| file_system.is_file(file))
+        else {
+            errors.push(not_found());
+            missing.insert(import_path);
+            continue;
+        };
+        match load_proto_file(&builder, file_system, cache, &file, include_imports) {
+            Ok(file_imports) => imports.extend(file_imports),
+            Err(err) => errors.push(err),
+        }
+    }
+    // Resolution of partially loaded files would report missing declarations
+    ProtoError::from_errors(errors)?;
+    let root = builder.finish()?;
+    validate(&root)?;
+    Ok(root)
+}
+
+/// Reads the file into the builder and returns its imports that are not well known
+fn load_proto_file(
+    builder: &Rc<RefCell<ScopeBuilder>>,
+    file_system: &dyn FileSystem,
+    cache: &mut ProtoFileCache,
+    file: &Path,
+    generated: bool,
+) -> Result<Vec<ImportPath>, ProtoError> {
+    let proto_file = cache.read(file_system, file)?;
+    load_parsed_file(builder, cache.id_generator(), proto_file, generated)
+}
+
+/// Loads the file into the builder and returns its imports that are not well known,
+/// well known imports are loaded right away
+pub(super) fn load_parsed_file(
+    builder: &Rc<RefCell<ScopeBuilder>>,
+    id_generator: &mut IdGenerator,
+    proto_file: ProtoFile,
+    generated: bool,
+) -> Result<Vec<ImportPath>, ProtoError> {
+    let mut imports = Vec::new();
+    for imprt in &proto_file.imports {
+        if is_well_known_import(imprt) {
+            builder.load_well_known(id_generator, &imprt.file_name);
+        } else if !is_descriptor_import(imprt) {
+            imports.push(imprt.clone());
         }
+    }
+    if generated {
         builder.load(proto_file)?;
+    } else {
+        builder.load_dependency(proto_file)?;
     }
-    builder.finish()
+    Ok(imports)
 }
 
-#[instrument(skip(id_generator), ret)]
-fn read_proto_file(
+#[instrument(skip(id_generator, content), ret)]
+pub(crate) fn parse_proto_file(
     id_generator: &mut IdGenerator,
     file_path: &Path,
+    content: &str,
 ) -> Result<ProtoFile, ProtoError> {
-    let content = read_file_content(file_path)?;
-
     let relative_file_path = get_relative_path(file_path).ok_or_else(|| {
         ProtoError::Default(format!(
             "Cannot get relative path to file: {}",
@@ -708,7 +1097,7 @@ fn read_proto_file(
         ))
     })?;
 
-    let lexems = read_lexems(&relative_file_path, content.as_str())?;
+    let lexems = read_lexems(&relative_file_path, content)?;
 
     let file_name = file_path.file_name().unwrap().to_str().unwrap().to_string();
 
@@ -718,6 +1107,7 @@ fn read_proto_file(
         imports: vec![],
         path: vec![],
         name: file_name.into(),
+        options: vec![],
     };
 
     parse_package(id_generator, &lexems, &mut res)?;
@@ -725,19 +1115,16 @@ fn read_proto_file(
     Ok(res)
 }
 
+/// Path of the file shown in errors, paths of files not on the disk are kept as they are
 fn get_relative_path(file_path: &Path) -> Option<PathBuf> {
-    let cur_dir = std::env::current_dir().unwrap();
-    path_relative_from(file_path, &cur_dir)
-}
-
-fn read_file_content(file_path: &Path) -> Result<String, ProtoError> {
-    let mut content = String::new();
-    let mut file = std::fs::File::open(file_path).map_err(ProtoError::CannotOpenFile)?;
-
-    file.read_to_string(&mut content)
-        .map_err(ProtoError::IOError)?;
-
-    Ok(content)
+    if file_path.is_relative() {
+        return Some(file_path.to_path_buf());
+    }
+    match std::env::current_dir() {
+        Ok(cur_dir) => path_relative_from(file_path, &cur_dir),
+        // There is no current directory in WebAssembly
+        Err(_) => Some(file_path.to_path_buf()),
+    }
 }
 
 // This function is taken from: https://github.com/rust-lang/rust/blob/e1d0de82cc40b666b88d4a6d2c9dcbc81d7ed27f/src/librustc_back/rpath.rs#L116-L158
@@ -780,3 +1167,88 @@ fn path_relative_from(path: &Path, base: &Path) -> Option<PathBuf> {
         Some(comps.iter().map(|c| c.as_os_str()).collect())
     }
 }
+
+#[cfg(test)]
+mod test_read_root_scope {
+    use std::path::{Path, PathBuf};
+
+    use crate::{GenerationOptions, MemoryFileSystem, build_root_scope, compile};
+
+    fn files() -> MemoryFileSystem {
+        let mut files = MemoryFileSystem::new();
+        files.insert(
+            "api/user.proto",
+            r#"syntax = "proto3";
+package api;
+import "google/type/date.proto";
+message User {
+  google.type.Date birthday = 1;
+}
+"#,
+        );
+        files.insert(
+            "vendor/google/type/date.proto",
+            "syntax = \"proto3\";\npackage google.type;\nmessage Date { int32 year = 1; }\n",
+        );
+        files.insert(
+            "third_party/google/type/date.proto",
+            "syntax = \"proto3\";\npackage google.type;\nmessage Day { int32 day = 1; }\n",
+        );
+        files
+    }
+
+    #[test]
+    fn resolves_imports_in_include_paths() {
+        let files = files();
+        let entry_files = [PathBuf::from("api/user.proto")];
+        // The first include path containing the import is used
+        let include_paths = [PathBuf::from("vendor"), PathBuf::from("third_party")];
+        let root = build_root_scope(&files, &entry_files, &include_paths, false).unwrap();
+        let output = compile(&root, &GenerationOptions::default()).unwrap();
+        assert!(output.contains_key(Path::new("api/user/User/types.ts")));
+        assert!(!output.keys().any(|path| path.starts_with("google")));
+
+        let root = build_root_scope(&files, &entry_files, &include_paths, true).unwrap();
+        let output = compile(&root, &GenerationOptions::default()).unwrap();
+        assert!(output.contains_key(Path::new("google/type/date/Date/types.ts")));
+
+        let include_paths = [PathBuf::from("third_party")];
+        let err = build_root_scope(&files, &entry_files, &include_paths, false).unwrap_err();
+        assert!(err.to_string().contains("Cannot resolve google in User"));
+        let err = build_root_scope(&files, &entry_files, &[], false).unwrap_err();
+        assert_eq!(err.message(), "File not found: google/type/date.proto");
+        let span = err.span().unwrap();
+        assert_eq!((span.start_line, span.start_column), (3, 1));
+    }
+
+    #[test]
+    fn reports_imports_of_missing_files() {
+        let mut files = MemoryFileSystem::new();
+        for name in ["a", "b"] {
+            files.insert(
+                format!("app/{}.proto", name),
+                "syntax = \"proto3\";\npackage app;\nimport \"missing.proto\";\n",
+            );
+        }
+        let entry_files = [PathBuf::from("app/a.proto"), PathBuf::from("app/b.proto")];
+        let err = build_root_scope(&files, &entry_files, &[], false).unwrap_err();
+        let errors = err.into_errors();
+        // Unused imports are checked too, each import statement is reported
+        let mut locations = errors
+            .iter()
+            .map(|e| {
+                assert_eq!(e.message(), "File not found: missing.proto");
+                let span = e.span().unwrap();
+                (span.file_path.to_path_buf(), span.start_line)
+            })
+            .collect::<Vec<_>>();
+        locations.sort();
+        assert_eq!(
+            locations,
+            vec![
+                (PathBuf::from("app/a.proto"), 3),
+                (PathBuf::from("app/b.proto"), 3),
+            ]
+        );
+    }
+}
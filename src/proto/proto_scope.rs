@@ -2,10 +2,10 @@ use std::{rc::Rc, fmt::Formatter};
 
 use self::{
     enum_scope::EnumScope, file::FileScope, message::MessageScope, package::PackageScope,
-    root_scope::RootScope, traits::ChildrenScopes,
+    root_scope::RootScope, service::ServiceScope, traits::ChildrenScopes,
 };
 
-use super::protopath::PathComponent;
+use super::{protopath::PathComponent, span::Span};
 
 pub(super) mod builder;
 pub(super) mod enum_scope;
@@ -13,7 +13,9 @@ pub(super) mod file;
 pub(super) mod message;
 pub(super) mod package;
 pub(crate) mod root_scope;
+pub(super) mod service;
 pub(super) mod traits;
+pub(super) mod validation;
 
 #[derive(Debug)]
 pub(crate) enum ProtoScope {
@@ -22,6 +24,7 @@ pub(crate) enum ProtoScope {
     File(FileScope),
     Enum(EnumScope),
     Message(MessageScope),
+    Service(ServiceScope),
 }
 
 impl ProtoScope {
@@ -32,6 +35,7 @@ impl ProtoScope {
             ProtoScope::File(_) => None,
             ProtoScope::Enum(e) => Some(e.id),
             ProtoScope::Message(m) => Some(m.id),
+            ProtoScope::Service(s) => Some(s.id),
         }
     }
     pub fn as_path_component(&self) -> PathComponent {
@@ -41,6 +45,7 @@ impl ProtoScope {
             ProtoScope::File(f) => PathComponent::File(Rc::clone(&f.name)),
             ProtoScope::Enum(e) => PathComponent::Enum(Rc::clone(&e.name)),
             ProtoScope::Message(m) => PathComponent::Message(Rc::clone(&m.name)),
+            ProtoScope::Service(s) => PathComponent::Service(Rc::clone(&s.name)),
         }
     }
     pub fn get_message_declaration(&self) -> Option<&MessageScope> {
@@ -50,6 +55,16 @@ impl ProtoScope {
             ProtoScope::File(_) => None,
             ProtoScope::Enum(_) => None,
             ProtoScope::Message(m) => Some(m),
+            ProtoScope::Service(_) => None,
+        }
+    }
+    /// Location of the declaration of messages, enums and services
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            ProtoScope::Enum(e) => e.span.as_ref(),
+            ProtoScope::Message(m) => m.span.as_ref(),
+            ProtoScope::Service(s) => s.span.as_ref(),
+            _ => None,
         }
     }
     pub fn name(&self) -> Rc<str> {
@@ -59,6 +74,7 @@ impl ProtoScope {
             ProtoScope::File(f) => Rc::clone(&f.name),
             ProtoScope::Enum(e) => Rc::clone(&e.name),
             ProtoScope::Message(m) => Rc::clone(&m.name),
+            ProtoScope::Service(s) => Rc::clone(&s.name),
         }
     }
 }
@@ -93,6 +109,12 @@ impl From<MessageScope> for ProtoScope {
     }
 }
 
+impl From<ServiceScope> for ProtoScope {
+    fn from(service_scope: ServiceScope) -> Self {
+        ProtoScope::Service(service_scope)
+    }
+}
+
 impl ChildrenScopes for ProtoScope {
     fn children(&self) -> &[Rc<ProtoScope>] {
         match self {
@@ -101,6 +123,7 @@ impl ChildrenScopes for ProtoScope {
             ProtoScope::File(file) => file.children(),
             ProtoScope::Enum(enum_scope) => enum_scope.children(),
             ProtoScope::Message(message_scope) => message_scope.children(),
+            ProtoScope::Service(service_scope) => service_scope.children(),
         }
     }
 }
@@ -113,6 +136,7 @@ impl std::fmt::Display for ProtoScope {
             ProtoScope::File(file) => file.fmt(f),
             ProtoScope::Enum(_) => todo!(),
             ProtoScope::Message(m) => m.fmt(f),
+            ProtoScope::Service(s) => s.fmt(f),
         }
     }
 }
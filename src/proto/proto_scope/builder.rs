@@ -2,26 +2,31 @@ pub(crate) mod well_known;
 
 use std::{
     cell::RefCell,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     ops::Deref,
     rc::{Rc, Weak},
 };
 
 use crate::proto::{
-    error::ProtoError,
+    error::{ProtoError, located_error},
     id_generator::{IdGenerator, UniqueId},
+    options::ProtoOption,
     package::{
-        Declaration, EnumDeclaration, Field, FieldDeclaration, FieldTypeReference, ImportPath,
-        MessageDeclaration, MessageDeclarationEntry, MessageEntry, OneOfDeclaration, OneOfGroup,
-        ProtoFile, Type,
+        Declaration, EnumDeclaration, ExtendDeclaration, Extension, Field, FieldDeclaration,
+        FieldRange, FieldTypeReference, ImportPath, MessageDeclaration, MessageDeclarationEntry,
+        MessageEntry, OneOfDeclaration, OneOfGroup, ProtoFile, ProtoVersion, ReservedDeclaration,
+        Rpc, RpcDeclaration, ServiceDeclaration, Type,
     },
+    span::Span,
 };
 
-use self::well_known::create_well_known_file;
+use self::well_known::{
+    create_well_known_file, is_descriptor_import, is_descriptor_options_message,
+};
 
 use super::{
     ProtoScope, enum_scope::EnumScope, file::FileScope, message::MessageScope,
-    package::PackageScope, root_scope::RootScope,
+    package::PackageScope, root_scope::RootScope, service::ServiceScope,
 };
 
 #[derive(Debug)]
@@ -33,6 +38,10 @@ struct PackageData {
 struct FileData {
     name: Rc<str>,
     imports: Vec<ImportPath>,
+    version: ProtoVersion,
+    /// False for files that are only used to resolve imports
+    generated: bool,
+    options: Vec<ProtoOption>,
 }
 
 #[derive(Debug)]
@@ -57,13 +66,25 @@ struct MessageData {
     id: usize,
     name: Rc<str>,
     fields: Vec<FieldOrOneOf>,
+    extension_ranges: Vec<FieldRange>,
+    reserved: Vec<ReservedDeclaration>,
+    options: Vec<ProtoOption>,
+    span: Option<Span>,
 }
 
 impl UniqueId for MessageData {
     type Args = (Rc<str>, Vec<FieldOrOneOf>);
 
     fn create_with_id(id: usize, (name, fields): Self::Args) -> Self {
-        MessageData { id, name, fields }
+        MessageData {
+            id,
+            name,
+            fields,
+            extension_ranges: Vec::new(),
+            reserved: Vec::new(),
+            options: Vec::new(),
+            span: None,
+        }
     }
 }
 
@@ -73,6 +94,9 @@ enum ScopeData {
     File(FileData),
     Enum(EnumDeclaration),
     Message(MessageData),
+    Service(ServiceDeclaration),
+    /// Extend block has no name, it is resolved before other scopes
+    Extend(ExtendDeclaration),
 }
 
 impl ScopeData {
@@ -83,6 +107,8 @@ impl ScopeData {
             ScopeData::File(p) => Some(Rc::clone(&p.name)),
             ScopeData::Enum(p) => Some(Rc::clone(&p.name)),
             ScopeData::Message(p) => Some(Rc::clone(&p.name)),
+            ScopeData::Service(p) => Some(Rc::clone(&p.name)),
+            ScopeData::Extend(_) => None,
         }
     }
     fn id(&self) -> Option<usize> {
@@ -92,6 +118,8 @@ impl ScopeData {
             ScopeData::File(_) => None,
             ScopeData::Enum(e) => Some(e.id),
             ScopeData::Message(m) => Some(m.id),
+            ScopeData::Service(s) => Some(s.id),
+            ScopeData::Extend(_) => None,
         }
     }
     fn is_root(&self) -> bool {
@@ -109,6 +137,9 @@ impl ScopeData {
     fn is_message(&self) -> bool {
         return matches!(self, ScopeData::Message(_));
     }
+    fn is_extend(&self) -> bool {
+        matches!(self, ScopeData::Extend(_))
+    }
 }
 
 impl std::fmt::Display for ScopeData {
@@ -119,6 +150,8 @@ impl std::fmt::Display for ScopeData {
             ScopeData::File(data) => write!(f, "{}", data.name),
             ScopeData::Enum(data) => write!(f, "Enum {}", data.name),
             ScopeData::Message(data) => write!(f, "Message {}", data.name),
+            ScopeData::Service(data) => write!(f, "Service {}", data.name),
+            ScopeData::Extend(data) => write!(f, "Extend {}", data.extendee),
         }
     }
 }
@@ -131,6 +164,8 @@ impl std::fmt::Debug for ScopeData {
             Self::File(file) => file.fmt(f),
             Self::Enum(e) => e.fmt(f),
             Self::Message(m) => m.fmt(f),
+            Self::Service(s) => s.fmt(f),
+            Self::Extend(e) => e.fmt(f),
         }
     }
 }
@@ -234,7 +269,7 @@ impl ScopeBuilder {
                 if self.is_message() {
                     return Some(Type::Message(id));
                 }
-                unreachable!()
+                None
             }
             _ => return None,
         }
@@ -269,6 +304,9 @@ impl ScopeBuilder {
     fn is_message(&self) -> bool {
         self.data.is_message()
     }
+    fn is_extend(&self) -> bool {
+        self.data.is_extend()
+    }
     fn name(&self) -> Option<Rc<str>> {
         self.data.name()
     }
@@ -323,23 +361,43 @@ impl ScopeBuilder {
     fn new_file(
         name: Rc<str>,
         imports: Vec<ImportPath>,
+        version: ProtoVersion,
+        generated: bool,
+        options: Vec<ProtoOption>,
         parent: Rc<RefCell<ScopeBuilder>>,
     ) -> Self {
         Self {
-            data: ScopeData::File(FileData { name, imports }),
+            data: ScopeData::File(FileData {
+                name,
+                imports,
+                version,
+                generated,
+                options,
+            }),
             children: Vec::new(),
             parent: Some(Rc::downgrade(&parent)),
         }
     }
 
-    fn new_message(
-        id: usize,
-        name: Rc<str>,
-        fields: Vec<FieldOrOneOf>,
-        parent: Rc<RefCell<ScopeBuilder>>,
-    ) -> Self {
+    fn new_message(m: MessageData, parent: Rc<RefCell<ScopeBuilder>>) -> Self {
+        Self {
+            data: ScopeData::Message(m),
+            children: Vec::new(),
+            parent: Some(Rc::downgrade(&parent)),
+        }
+    }
+
+    fn new_service(s: ServiceDeclaration, parent: Rc<RefCell<ScopeBuilder>>) -> Self {
+        Self {
+            data: ScopeData::Service(s),
+            children: Vec::new(),
+            parent: Some(Rc::downgrade(&parent)),
+        }
+    }
+
+    fn new_extend(e: ExtendDeclaration, parent: Rc<RefCell<ScopeBuilder>>) -> Self {
         Self {
-            data: ScopeData::Message(MessageData { name, fields, id }),
+            data: ScopeData::Extend(e),
             children: Vec::new(),
             parent: Some(Rc::downgrade(&parent)),
         }
@@ -363,21 +421,35 @@ impl ScopeBuilder {
 
 pub(crate) trait ScopeBuilderTrait {
     fn load(&self, file: ProtoFile) -> Result<(), ProtoError>;
+    /// Loads file that is used only to resolve imports of other files
+    fn load_dependency(&self, file: ProtoFile) -> Result<(), ProtoError>;
     fn load_well_known(&self, id_gen: &mut IdGenerator, file_name: &str);
     fn finish(self) -> Result<RootScope, ProtoError>;
 }
 
 trait ScopeBuilderPrivate {
-    fn load_file(&self, file: ProtoFile, package_path: &[Rc<str>]) -> Result<(), ProtoError>;
+    fn load_file(
+        &self,
+        file: ProtoFile,
+        package_path: &[Rc<str>],
+        generated: bool,
+    ) -> Result<(), ProtoError>;
     fn load_declaration(&self, declaration: Declaration) -> Result<(), ProtoError>;
     fn load_enum(&self, enum_declaration: EnumDeclaration) -> Result<(), ProtoError>;
     fn load_message(&self, message_declaration: MessageDeclaration) -> Result<(), ProtoError>;
+    fn load_service(&self, service_declaration: ServiceDeclaration) -> Result<(), ProtoError>;
+    fn load_extend(&self, extend_declaration: ExtendDeclaration) -> Result<(), ProtoError>;
 }
 
 impl ScopeBuilderTrait for Rc<RefCell<ScopeBuilder>> {
     fn load(&self, file: ProtoFile) -> Result<(), ProtoError> {
         let package_path = file.path.clone();
-        self.load_file(file, &package_path)
+        self.load_file(file, &package_path, true)
+    }
+
+    fn load_dependency(&self, file: ProtoFile) -> Result<(), ProtoError> {
+        let package_path = file.path.clone();
+        self.load_file(file, &package_path, false)
     }
 
     fn load_well_known(&self, id_gen: &mut IdGenerator, imp: &str) {
@@ -417,11 +489,17 @@ impl ScopeBuilderTrait for Rc<RefCell<ScopeBuilder>> {
         let mut children: Vec<Rc<ProtoScope>> = Vec::new();
         let mut types: HashMap<usize, Vec<Rc<str>>> = Default::default();
 
+        let mut errors: Vec<ProtoError> = Vec::new();
+        let mut extensions: HashMap<usize, Vec<Field>> = Default::default();
+        for child_ref in root_builder.children.iter() {
+            collect_extensions(child_ref, &mut extensions, &mut errors);
+        }
+
         for child_ref in root_builder.children.iter() {
             let ResolveResult {
                 scope,
                 declaration_paths,
-            } = resolve(child_ref)?;
+            } = resolve(child_ref, &extensions, &mut errors);
             let name = scope.name();
             children.push(scope);
             for (id, mut path) in declaration_paths {
@@ -431,6 +509,11 @@ impl ScopeBuilderTrait for Rc<RefCell<ScopeBuilder>> {
             }
         }
 
+        // The same unresolved import is reported by every field that uses it
+        let mut reported = HashSet::new();
+        errors.retain(|err| reported.insert(err.to_string()));
+        ProtoError::from_errors(errors)?;
+
         Ok(RootScope { children, types })
     }
 }
@@ -500,15 +583,181 @@ struct ResolveResult {
     declaration_paths: Vec<(usize, Vec<Rc<str>>)>,
 }
 
-fn resolve(builder_ref: &Rc<RefCell<ScopeBuilder>>) -> Result<ResolveResult, ProtoError> {
+/// Resolves fields of all extend blocks and groups them by id of the extended message
+fn collect_extensions(
+    builder_ref: &Rc<RefCell<ScopeBuilder>>,
+    extensions: &mut HashMap<usize, Vec<Field>>,
+    errors: &mut Vec<ProtoError>,
+) {
+    let builder = builder_ref.borrow();
+    if let ScopeData::Extend(extend) = &builder.data
+        && let Some((extendee_id, fields)) = resolve_extend(&builder, extend, errors)
+    {
+        extensions.entry(extendee_id).or_default().extend(fields);
+    }
+    for child in &builder.children {
+        collect_extensions(child, extensions, errors);
+    }
+}
+
+/// Resolves the extended message and the fields of the extend block.
+/// Returns `None` if the extended type cannot be resolved
+/// or if it is a descriptor option, custom options do not change generated code
+fn resolve_extend(
+    builder: &ScopeBuilder,
+    extend: &ExtendDeclaration,
+    errors: &mut Vec<ProtoError>,
+) -> Option<(usize, Vec<Field>)> {
+    if let FieldTypeReference::IdPath(path) = &extend.extendee
+        && is_descriptor_options_message(path)
+    {
+        return None;
+    }
+    let extendee_id = match resolve_type(builder, &extend.extendee) {
+        Ok(Type::Message(id)) => id,
+        Ok(_) => {
+            errors.push(located_error(
+                format!("Extended type {} is not a message", extend.extendee),
+                extend.extendee_span.as_ref(),
+            ));
+            return None;
+        }
+        Err(err) => {
+            errors.push(err.located(extend.extendee_span.as_ref()));
+            return None;
+        }
+    };
+    let mut fields = Vec::new();
+    for field in &extend.fields {
+        let field_type = match resolve_type(builder, &field.field_type_ref) {
+            Ok(Type::Map(_, _)) => {
+                errors.push(located_error(
+                    format!(
+                        "Map field {} cannot be an extension of {}",
+                        field.name, extend.extendee
+                    ),
+                    field.span.as_ref(),
+                ));
+                continue;
+            }
+            Ok(field_type) => field_type,
+            Err(err) => {
+                errors.push(err.located(field.span.as_ref()));
+                continue;
+            }
+        };
+        fields.push(Field {
+            name: Rc::clone(&field.name),
+            field_type,
+            tag: field.tag,
+            options: field.options.clone(),
+            packed: Field::packed_option(&field.options, file_version(builder)),
+            span: field.span.clone(),
+        });
+    }
+    Some((extendee_id, fields))
+}
+
+/// Extensions declared by extend blocks among the children of the builder.
+/// Errors are not reported, they are reported when extensions are collected
+fn declared_extensions(builder: &ScopeBuilder) -> Vec<Extension> {
+    let mut res = Vec::new();
+    for child_ref in &builder.children {
+        let child = child_ref.borrow();
+        if let ScopeData::Extend(extend) = &child.data
+            && let Some((extendee, fields)) = resolve_extend(&child, extend, &mut Vec::new())
+        {
+            res.extend(fields.into_iter().map(|field| Extension { extendee, field }));
+        }
+    }
+    res
+}
+
+/// Returns error located at the field or enum value if its number or name is reserved
+fn check_reserved(
+    reserved: &[ReservedDeclaration],
+    kind: &str,
+    declaration_name: &str,
+    name: &str,
+    number: i64,
+    span: &Option<Span>,
+) -> Result<(), ProtoError> {
+    for r in reserved {
+        let message = if r.contains_number(number) {
+            format!(
+                "{} {} of {} uses reserved number {}",
+                kind, name, declaration_name, number
+            )
+        } else if r.contains_name(name) {
+            format!("{} {} of {} uses reserved name", kind, name, declaration_name)
+        } else {
+            continue;
+        };
+        return Err(
+            located_error(message, span.as_ref()).with_note("reserved here", r.span.as_ref()),
+        );
+    }
+    Ok(())
+}
+
+/// Checks that extension fits into extension ranges of the message and does not clash with its fields
+fn validate_extension(
+    message: &MessageData,
+    entries: &[MessageEntry],
+    extension: &Field,
+) -> Result<(), ProtoError> {
+    if !message
+        .extension_ranges
+        .iter()
+        .any(|range| range.contains(extension.tag))
+    {
+        return Err(located_error(
+            format!(
+                "Extension {} = {} is not in extension ranges of {}",
+                extension.name, extension.tag, message.name
+            ),
+            extension.span.as_ref(),
+        ));
+    }
+    for entry in entries {
+        let fields = match entry {
+            MessageEntry::Field(f) | MessageEntry::Extension(f) => std::slice::from_ref(f),
+            MessageEntry::OneOf(one_of) => one_of.fields.as_slice(),
+        };
+        for field in fields {
+            if field.tag == extension.tag || field.json_name() == extension.json_name() {
+                return Err(located_error(
+                    format!(
+                        "Extension {} = {} conflicts with field {} = {} of {}",
+                        extension.name, extension.tag, field.name, field.tag, message.name
+                    ),
+                    extension.span.as_ref(),
+                )
+                .with_note("field is defined here", field.span.as_ref()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Resolves the scope with all its children.
+/// Declarations that cannot be resolved are skipped and reported to `errors`
+fn resolve(
+    builder_ref: &Rc<RefCell<ScopeBuilder>>,
+    extensions: &HashMap<usize, Vec<Field>>,
+    errors: &mut Vec<ProtoError>,
+) -> ResolveResult {
     let builder = builder_ref.borrow();
     let mut children: Vec<Rc<ProtoScope>> = Vec::new();
     let mut declaration_paths: Vec<(usize, Vec<Rc<str>>)> = Vec::new();
     for child in &builder.children {
+        if child.borrow().is_extend() {
+            continue;
+        }
         let ResolveResult {
             scope,
             declaration_paths: declaration_scopes,
-        } = resolve(child)?;
+        } = resolve(child, extensions, errors);
         let name = scope.name();
         children.push(scope);
         for (id, mut path) in declaration_scopes {
@@ -523,15 +772,38 @@ fn resolve(builder_ref: &Rc<RefCell<ScopeBuilder>>) -> Result<ResolveResult, Pro
             children,
             name: Rc::clone(&p.name),
         })),
-        ScopeData::File(f) => Rc::new(ProtoScope::File(FileScope {
-            children,
-            name: Rc::clone(&f.name),
-        })),
+        ScopeData::File(f) => {
+            check_imports(&builder, f, errors);
+            Rc::new(ProtoScope::File(FileScope {
+                children,
+                name: Rc::clone(&f.name),
+                version: f.version,
+                generated: f.generated,
+                imports: f.imports.clone(),
+                options: f.options.clone(),
+                extensions: declared_extensions(&builder),
+            }))
+        }
         ScopeData::Enum(e) => {
+            for entry in &e.entries {
+                if let Err(err) = check_reserved(
+                    &e.reserved,
+                    "Enum value",
+                    &e.name,
+                    &entry.name,
+                    entry.value,
+                    &entry.span,
+                ) {
+                    errors.push(err);
+                }
+            }
             let enum_scope = Rc::new(ProtoScope::Enum(EnumScope {
                 id: e.id,
                 name: Rc::clone(&e.name),
                 entries: e.entries.clone(),
+                options: e.options.clone(),
+                reserved: e.reserved.clone(),
+                span: e.span.clone(),
             }));
 
             declaration_paths.push((e.id, vec![]));
@@ -539,53 +811,135 @@ fn resolve(builder_ref: &Rc<RefCell<ScopeBuilder>>) -> Result<ResolveResult, Pro
             enum_scope
         }
         ScopeData::Message(m) => {
+            for field in m.fields.iter() {
+                let fields = match field {
+                    FieldOrOneOf::Field(f) => std::slice::from_ref(f),
+                    FieldOrOneOf::OneOf(one_of) => one_of.fields.as_slice(),
+                };
+                for f in fields {
+                    if let Err(err) =
+                        check_reserved(&m.reserved, "Field", &m.name, &f.name, f.tag, &f.span)
+                    {
+                        errors.push(err);
+                    }
+                }
+            }
+            let version = file_version(&builder);
             let mut entries: Vec<MessageEntry> = vec![];
             for field in m.fields.iter() {
                 match field {
                     FieldOrOneOf::Field(f) => {
-                        let field_type = resolve_type(&builder, &f.field_type_ref)?;
+                        let field_type = match resolve_type(&builder, &f.field_type_ref) {
+                            Ok(field_type) => field_type,
+                            Err(err) => {
+                                errors.push(err.located(f.span.as_ref()));
+                                continue;
+                            }
+                        };
 
                         let entry = MessageEntry::Field(Field {
                             name: Rc::clone(&f.name),
                             field_type: field_type,
                             tag: f.tag,
-                            attributes: f.attributes.clone(),
+                            options: f.options.clone(),
+                            packed: Field::packed_option(&f.options, version),
+                            span: f.span.clone(),
                         });
 
                         entries.push(entry);
                     }
                     FieldOrOneOf::OneOf(one_of_decl) => {
                         let name = Rc::clone(&one_of_decl.name);
-                        let mut options = Vec::new();
-                        for option in &one_of_decl.options {
-                            let field_type = resolve_type(&builder, &option.field_type_ref)?;
-                            options.push(Field {
-                                name: Rc::clone(&option.name),
+                        let mut fields = Vec::new();
+                        for field in &one_of_decl.fields {
+                            let field_type = match resolve_type(&builder, &field.field_type_ref) {
+                                Ok(field_type) => field_type,
+                                Err(err) => {
+                                    errors.push(err.located(field.span.as_ref()));
+                                    continue;
+                                }
+                            };
+                            fields.push(Field {
+                                name: Rc::clone(&field.name),
                                 field_type: field_type,
-                                tag: option.tag,
-                                attributes: option.attributes.clone(),
+                                tag: field.tag,
+                                options: field.options.clone(),
+                                packed: Field::packed_option(&field.options, version),
+                                span: field.span.clone(),
                             });
                         }
-                        let entry = MessageEntry::OneOf(OneOfGroup { name, options });
+                        let entry = MessageEntry::OneOf(OneOfGroup {
+                            name,
+                            fields,
+                            options: one_of_decl.options.clone(),
+                            span: one_of_decl.span.clone(),
+                        });
                         entries.push(entry)
                     }
                 }
             }
+            for extension in extensions.get(&m.id).into_iter().flatten() {
+                match validate_extension(m, &entries, extension) {
+                    Ok(()) => entries.push(MessageEntry::Extension(extension.clone())),
+                    Err(err) => errors.push(err),
+                }
+            }
             let message_scope = Rc::new(ProtoScope::Message(MessageScope {
                 id: m.id,
                 name: Rc::clone(&m.name),
                 children,
                 entries,
+                options: m.options.clone(),
+                extension_ranges: m.extension_ranges.clone(),
+                reserved: m.reserved.clone(),
+                extensions: declared_extensions(&builder),
+                span: m.span.clone(),
             }));
             declaration_paths.push((m.id, vec![]));
             message_scope
         }
+        ScopeData::Service(s) => {
+            let mut rpcs: Vec<Rpc> = Vec::new();
+            for rpc in s.rpcs.iter() {
+                let input_type = resolve_rpc_type(&builder, rpc, &rpc.input_type)
+                    .map_err(|err| err.located(rpc.input_span.as_ref()));
+                let output_type = resolve_rpc_type(&builder, rpc, &rpc.output_type)
+                    .map_err(|err| err.located(rpc.output_span.as_ref()));
+                let (input_type, output_type) = match (input_type, output_type) {
+                    (Ok(input_type), Ok(output_type)) => (input_type, output_type),
+                    (input_type, output_type) => {
+                        errors.extend(input_type.err());
+                        errors.extend(output_type.err());
+                        continue;
+                    }
+                };
+                rpcs.push(Rpc {
+                    name: Rc::clone(&rpc.name),
+                    input_type,
+                    client_streaming: rpc.client_streaming,
+                    output_type,
+                    server_streaming: rpc.server_streaming,
+                    options: rpc.options.clone(),
+                    span: rpc.span.clone(),
+                });
+            }
+            let service_scope = Rc::new(ProtoScope::Service(ServiceScope {
+                id: s.id,
+                name: Rc::clone(&s.name),
+                rpcs,
+                options: s.options.clone(),
+                span: s.span.clone(),
+            }));
+            declaration_paths.push((s.id, vec![]));
+            service_scope
+        }
+        ScopeData::Extend(_) => unreachable!(),
     };
 
-    Ok(ResolveResult {
+    ResolveResult {
         scope: scope,
         declaration_paths,
-    })
+    }
 }
 
 fn resolve_type(
@@ -618,6 +972,26 @@ fn resolve_type(
     }
 }
 
+/// Resolves request or response type of the rpc to the message id
+fn resolve_rpc_type(
+    builder: &ScopeBuilder,
+    rpc: &RpcDeclaration,
+    type_ref: &FieldTypeReference,
+) -> Result<usize, ProtoError> {
+    match resolve_type(builder, type_ref)? {
+        Type::Message(id) => Ok(id),
+        _ => Err(ProtoError::new(
+            format!(
+                "Type {} of rpc {} is not a message{}",
+                type_ref,
+                rpc.name,
+                scope_suffix(builder),
+            )
+            .as_str(),
+        )),
+    }
+}
+
 fn resolve_full_path(builder: &ScopeBuilder, full_path: &[Rc<str>]) -> Result<Type, ProtoError> {
     if full_path.is_empty() {
         return Err(ProtoError::new("Cannot resolve empty full path"));
@@ -626,7 +1000,7 @@ fn resolve_full_path(builder: &ScopeBuilder, full_path: &[Rc<str>]) -> Result<Ty
     if in_file_resolution.is_some() {
         return Ok(in_file_resolution.unwrap());
     }
-    let imports = get_imports(&builder)?;
+    let imports = get_imports(&builder);
     let imported_files = imports
         .into_iter()
         .map(|p| builder.get_builder_by_absolute_path(&p).unwrap());
@@ -637,17 +1011,23 @@ fn resolve_full_path(builder: &ScopeBuilder, full_path: &[Rc<str>]) -> Result<Ty
             return Ok(resolved.unwrap());
         }
     }
+    if let Some(resolved) = resolve_in_own_file(builder, full_path) {
+        return Ok(resolved);
+    }
 
     return Err(ProtoError::new(
-        format!(
-            "Cannot resolve {}\n  in {}",
-            &full_path[0],
-            builder.name().unwrap_or("".into()),
-        )
-        .as_str(),
+        format!("Cannot resolve {}{}", &full_path[0], scope_suffix(builder)).as_str(),
     ));
 }
 
+/// ` in <name>` of the scope the type is resolved in, empty for unnamed scopes like extend blocks
+fn scope_suffix(builder: &ScopeBuilder) -> String {
+    match builder.name() {
+        Some(name) => format!(" in {}", name),
+        None => String::new(),
+    }
+}
+
 fn resolve_in_imported_file(file_builder: &ScopeBuilder, full_path: &[Rc<str>]) -> Option<Type> {
     for declaration_builder_ref in file_builder.get_all_declaration_builders() {
         let declaration_builder = declaration_builder_ref.borrow();
@@ -658,34 +1038,64 @@ fn resolve_in_imported_file(file_builder: &ScopeBuilder, full_path: &[Rc<str>])
     None
 }
 
-fn get_imports(builder: &ScopeBuilder) -> Result<Vec<Vec<Rc<str>>>, ProtoError> {
+/// Resolves the path qualified with the package, e.g. `my.package.Message.Nested`,
+/// among declarations of the file containing the builder
+fn resolve_in_own_file(builder: &ScopeBuilder, full_path: &[Rc<str>]) -> Option<Type> {
+    if builder.is_root() || builder.is_package() {
+        return None;
+    }
+    if builder.is_file() {
+        return resolve_in_imported_file(builder, full_path);
+    }
+    builder
+        .for_parent(|parent| resolve_in_own_file(parent, full_path))
+        .flatten()
+}
+
+/// Syntax of the file the scope is declared in
+fn file_version(builder: &ScopeBuilder) -> ProtoVersion {
+    match &builder.data {
+        ScopeData::File(f) => f.version,
+        _ => builder
+            .for_parent(file_version)
+            .unwrap_or(ProtoVersion::Proto3),
+    }
+}
+
+/// Paths of the files imported by the file of the scope.
+/// Imports that cannot be resolved are skipped, they are reported by `check_imports`
+fn get_imports(builder: &ScopeBuilder) -> Vec<Vec<Rc<str>>> {
     if builder.is_root() {
-        return Ok(vec![]);
+        return vec![];
     }
     if builder.is_package() {
-        return Ok(vec![]);
+        return vec![];
     }
     if !builder.is_file() {
-        return builder.for_parent(get_imports).unwrap_or(Ok(vec![]));
+        return builder.for_parent(get_imports).unwrap_or(vec![]);
     }
     let data = match &builder.data {
         ScopeData::File(f) => f,
         _ => unreachable!(),
     };
 
-    let mut res = Vec::new();
+    data.imports
+        .iter()
+        .filter(|imp| !is_descriptor_import(imp))
+        .filter_map(|imp| resolve_import(builder, &imp.packages, &imp.file_name))
+        .collect()
+}
 
-    for import_decl in &data.imports {
-        match resolve_import(&builder, &import_decl.packages, &import_decl.file_name) {
-            Some(imprt) => res.push(imprt),
-            None => {
-                return Err(ProtoError::new(
-                    format!("Cannot resolve import {}", import_decl).as_str(),
-                ));
-            }
+/// Reports imports of the file that are not loaded at their import statements
+fn check_imports(builder: &ScopeBuilder, file: &FileData, errors: &mut Vec<ProtoError>) {
+    for import_decl in file.imports.iter().filter(|imp| !is_descriptor_import(imp)) {
+        if resolve_import(builder, &import_decl.packages, &import_decl.file_name).is_none() {
+            errors.push(located_error(
+                format!("File not found: {}", import_decl),
+                import_decl.span.as_ref(),
+            ));
         }
     }
-    Ok(res)
 }
 
 fn resolve_import(
@@ -804,7 +1214,12 @@ fn resolve_in_parents_until_file(builder: &ScopeBuilder, full_path: &[Rc<str>])
 }
 
 impl ScopeBuilderPrivate for Rc<RefCell<ScopeBuilder>> {
-    fn load_file(&self, file: ProtoFile, path: &[Rc<str>]) -> Result<(), ProtoError> {
+    fn load_file(
+        &self,
+        file: ProtoFile,
+        path: &[Rc<str>],
+        generated: bool,
+    ) -> Result<(), ProtoError> {
         if path.is_empty() {
             let present = {
                 let cell = self.borrow();
@@ -814,7 +1229,14 @@ impl ScopeBuilderPrivate for Rc<RefCell<ScopeBuilder>> {
                 })
             };
             assert!(!present);
-            let file_builder = ScopeBuilder::new_file(file.name, file.imports, Rc::clone(self));
+            let file_builder = ScopeBuilder::new_file(
+                file.name,
+                file.imports,
+                file.version,
+                generated,
+                file.options,
+                Rc::clone(self),
+            );
             let file_builder_ref = Rc::new(RefCell::new(file_builder));
             for decl in file.declarations {
                 file_builder_ref.load_declaration(decl)?;
@@ -839,14 +1261,14 @@ impl ScopeBuilderPrivate for Rc<RefCell<ScopeBuilder>> {
             Some(ind) => {
                 let cell = self.borrow();
                 let child_ref = Rc::clone(&cell.children[ind]);
-                child_ref.load_file(file, &path[1..])?;
+                child_ref.load_file(file, &path[1..], generated)?;
                 Ok(())
             }
             None => {
                 let package_builder =
                     ScopeBuilder::new_package(Rc::clone(&path[0]), Rc::clone(self));
                 let package_ref = Rc::new(RefCell::new(package_builder));
-                package_ref.load_file(file, &path[1..])?;
+                package_ref.load_file(file, &path[1..], generated)?;
                 {
                     let mut cell = self.borrow_mut();
                     cell.children.push(package_ref);
@@ -860,9 +1282,31 @@ impl ScopeBuilderPrivate for Rc<RefCell<ScopeBuilder>> {
         match declaration {
             Declaration::Enum(e) => self.load_enum(e),
             Declaration::Message(m) => self.load_message(m),
+            Declaration::Service(s) => self.load_service(s),
+            Declaration::Extend(e) => self.load_extend(e),
         }
     }
 
+    fn load_extend(&self, extend_declaration: ExtendDeclaration) -> Result<(), ProtoError> {
+        let extend_builder = ScopeBuilder::new_extend(extend_declaration, Rc::clone(self));
+        let extend_ref = Rc::new(RefCell::new(extend_builder));
+        {
+            let mut cell = self.borrow_mut();
+            cell.children.push(extend_ref);
+        }
+        Ok(())
+    }
+
+    fn load_service(&self, service_declaration: ServiceDeclaration) -> Result<(), ProtoError> {
+        let service_builder = ScopeBuilder::new_service(service_declaration, Rc::clone(self));
+        let service_ref = Rc::new(RefCell::new(service_builder));
+        {
+            let mut cell = self.borrow_mut();
+            cell.children.push(service_ref);
+        }
+        Ok(())
+    }
+
     fn load_enum(&self, enum_declaration: EnumDeclaration) -> Result<(), ProtoError> {
         let enum_builder = ScopeBuilder::new_enum(enum_declaration, Rc::clone(self));
         let enum_ref = Rc::new(RefCell::new(enum_builder));
@@ -877,23 +1321,34 @@ impl ScopeBuilderPrivate for Rc<RefCell<ScopeBuilder>> {
         let mut fields: Vec<FieldOrOneOf> = Vec::new();
         let mut sub_messages: Vec<MessageDeclaration> = Vec::new();
         let mut sub_enums: Vec<EnumDeclaration> = Vec::new();
+        let mut sub_extends: Vec<ExtendDeclaration> = Vec::new();
+        let mut extension_ranges: Vec<FieldRange> = Vec::new();
+        let mut reserved: Vec<ReservedDeclaration> = Vec::new();
         for entry in message_declaration.entries {
             match entry {
                 MessageDeclarationEntry::Field(f) => fields.push(FieldOrOneOf::Field(f)),
                 MessageDeclarationEntry::Declaration(decl) => match decl {
                     Declaration::Enum(e) => sub_enums.push(e),
                     Declaration::Message(m) => sub_messages.push(m),
+                    Declaration::Extend(e) => sub_extends.push(e),
+                    Declaration::Service(_) => unreachable!(),
                 },
                 MessageDeclarationEntry::OneOf(o) => fields.push(FieldOrOneOf::OneOf(o)),
+                MessageDeclarationEntry::Extensions(e) => extension_ranges.extend(e.ranges),
+                MessageDeclarationEntry::Reserved(r) => reserved.push(r),
             }
         }
 
-        let message_builder = ScopeBuilder::new_message(
-            message_declaration.id,
-            message_declaration.name,
+        let message_data = MessageData {
+            id: message_declaration.id,
+            name: message_declaration.name,
             fields,
-            Rc::clone(self),
-        );
+            extension_ranges,
+            reserved,
+            options: message_declaration.options,
+            span: message_declaration.span,
+        };
+        let message_builder = ScopeBuilder::new_message(message_data, Rc::clone(self));
         let message_builder_ref = Rc::new(RefCell::new(message_builder));
         for e in sub_enums {
             message_builder_ref.load_enum(e)?;
@@ -901,6 +1356,9 @@ impl ScopeBuilderPrivate for Rc<RefCell<ScopeBuilder>> {
         for m in sub_messages {
             message_builder_ref.load_message(m)?;
         }
+        for e in sub_extends {
+            message_builder_ref.load_extend(e)?;
+        }
         {
             let mut cell = self.borrow_mut();
             cell.children.push(message_builder_ref);
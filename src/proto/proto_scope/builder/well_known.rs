@@ -43,6 +43,35 @@ pub(crate) fn is_well_known_import(imp: &ImportPath) -> bool {
     }
     return is_valid_well_known_import_file_name(&imp.file_name);
 }
+/// `google/protobuf/descriptor.proto` is imported only to declare custom options
+pub(crate) fn is_descriptor_import(imp: &ImportPath) -> bool {
+    imp.packages.len() == 2
+        && &*imp.packages[0] == "google"
+        && &*imp.packages[1] == "protobuf"
+        && &*imp.file_name == "descriptor.proto"
+}
+
+/// Messages of descriptor.proto extended by custom options
+pub(super) fn is_descriptor_options_message(path: &[Rc<str>]) -> bool {
+    match path {
+        [google, protobuf, name] if &**google == "google" && &**protobuf == "protobuf" => {
+            matches!(
+                &**name,
+                "FileOptions"
+                    | "MessageOptions"
+                    | "FieldOptions"
+                    | "OneofOptions"
+                    | "EnumOptions"
+                    | "EnumValueOptions"
+                    | "ServiceOptions"
+                    | "MethodOptions"
+                    | "ExtensionRangeOptions"
+            )
+        }
+        _ => false,
+    }
+}
+
 fn is_valid_well_known_import_file_name(imp: &str) -> bool {
     match imp {
         "any.proto"
@@ -2,7 +2,7 @@ use std::{cell::RefCell, rc::Rc};
 
 use crate::proto::{
     id_generator::IdGenerator,
-    package::{FieldDeclaration, FieldTypeReference},
+    package::{FieldDeclaration, FieldTypeReference, ProtoVersion},
     proto_scope::builder::{FileData, MessageData, ScopeBuilder, ScopeData},
 };
 
@@ -11,6 +11,9 @@ pub(in crate::proto) fn create_file(id_gen: &mut IdGenerator) -> Rc<RefCell<Scop
         data: ScopeData::File(FileData {
             name: Rc::from("any.proto"),
             imports: Vec::new(),
+            version: ProtoVersion::Proto3,
+            generated: true,
+            options: Vec::new(),
         }),
         parent: None,
         children: vec![],
@@ -2,6 +2,7 @@ use std::{cell::RefCell, rc::Rc};
 
 use crate::proto::{
     id_generator::IdGenerator,
+    package::ProtoVersion,
     proto_scope::builder::{FileData, ScopeBuilder, ScopeData},
 };
 
@@ -12,6 +13,9 @@ pub(in crate::proto) fn create_file(id_gen: &mut IdGenerator) -> Rc<RefCell<Scop
         data: ScopeData::File(FileData {
             name: Rc::from("duration.proto"),
             imports: Vec::new(),
+            version: ProtoVersion::Proto3,
+            generated: true,
+            options: Vec::new(),
         }),
         parent: None,
         children: vec![],
@@ -2,7 +2,9 @@ use std::{cell::RefCell, rc::Rc};
 
 use crate::proto::{
     id_generator::IdGenerator,
-    package::{EnumDeclaration, FieldDeclaration, FieldTypeReference, OneOfDeclaration},
+    package::{
+        EnumDeclaration, FieldDeclaration, FieldTypeReference, OneOfDeclaration, ProtoVersion,
+    },
     proto_scope::builder::{FileData, MessageData, ScopeBuilder, ScopeData},
 };
 
@@ -11,6 +13,9 @@ pub(in crate::proto) fn create_file(id_gen: &mut IdGenerator) -> Rc<RefCell<Scop
         data: ScopeData::File(FileData {
             name: Rc::from("struct.proto"),
             imports: Vec::new(),
+            version: ProtoVersion::Proto3,
+            generated: true,
+            options: Vec::new(),
         }),
         parent: None,
         children: vec![],
@@ -50,7 +55,11 @@ pub(in crate::proto) fn create_file(id_gen: &mut IdGenerator) -> Rc<RefCell<Scop
 }
 
 fn create_enums(id_gen: &mut IdGenerator) -> Vec<EnumDeclaration> {
-    vec![id_gen.create(("NullValue".into(), vec![("NULL_VALUE".into(), 0).into()]))]
+    vec![id_gen.create((
+        "NullValue".into(),
+        vec![("NULL_VALUE".into(), 0).into()],
+        Vec::new(),
+    ))]
 }
 
 fn create_messages(id_gen: &mut IdGenerator) -> Vec<MessageData> {
@@ -71,7 +80,7 @@ fn create_messages(id_gen: &mut IdGenerator) -> Vec<MessageData> {
             "Value".into(),
             vec![OneOfDeclaration {
                 name: "kind".into(),
-                options: vec![
+                fields: vec![
                     FieldDeclaration::new("null_value", FieldTypeReference::id("NullValue"), 1)
                         .into(),
                     FieldDeclaration::new("number_value", FieldTypeReference::Double, 2).into(),
@@ -82,6 +91,8 @@ fn create_messages(id_gen: &mut IdGenerator) -> Vec<MessageData> {
                     FieldDeclaration::new("list_value", FieldTypeReference::id("ListValue"), 6)
                         .into(),
                 ],
+                options: Vec::new(),
+                span: None,
             }
             .into()],
         )),
@@ -1,6 +1,10 @@
 use std::rc::Rc;
 
-use crate::proto::package::EnumEntry;
+use crate::proto::{
+    options::ProtoOption,
+    package::{EnumEntry, ReservedDeclaration},
+    span::Span,
+};
 
 use super::{traits::ChildrenScopes, ProtoScope};
 
@@ -9,6 +13,9 @@ pub(crate) struct EnumScope {
     pub id: usize,
     pub name: Rc<str>,
     pub entries: Vec<EnumEntry>,
+    pub options: Vec<ProtoOption>,
+    pub reserved: Vec<ReservedDeclaration>,
+    pub span: Option<Span>,
 }
 
 impl std::fmt::Display for EnumScope {
@@ -39,14 +46,21 @@ mod test_format {
                 EnumEntry {
                     name: "Hello".into(),
                     value: 0,
+                    options: vec![],
+                    span: None,
                 }
                 .into(),
                 EnumEntry {
                     name: "World".into(),
                     value: 1,
+                    options: vec![],
+                    span: None,
                 }
                 .into(),
             ],
+            options: vec![],
+            reserved: vec![],
+            span: None,
         };
         let str = format!("{}", enum_scope);
         assert_eq!(
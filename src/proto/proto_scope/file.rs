@@ -1,11 +1,23 @@
 use std::{rc::{Rc}, fmt::Write};
 
+use crate::proto::{
+    options::ProtoOption,
+    package::{Extension, ImportPath, ProtoVersion},
+};
+
 use super::{traits::ChildrenScopes, ProtoScope};
 
 #[derive(Debug)]
 pub(crate) struct FileScope {
     pub name: Rc<str>,
     pub children: Vec<Rc<ProtoScope>>,
+    pub version: ProtoVersion,
+    /// False for files found only in import search paths
+    pub generated: bool,
+    pub imports: Vec<ImportPath>,
+    pub options: Vec<ProtoOption>,
+    /// Fields of `extend` blocks declared at the top level of the file
+    pub extensions: Vec<Extension>,
 }
 
 impl ChildrenScopes for FileScope {
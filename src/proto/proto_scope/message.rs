@@ -1,6 +1,10 @@
 use std::{fmt::Write, rc::Rc};
 
-use crate::proto::package::{MessageEntry, Field};
+use crate::proto::{
+    options::ProtoOption,
+    package::{Extension, Field, FieldRange, MessageEntry, ReservedDeclaration},
+    span::Span,
+};
 
 use super::{traits::ChildrenScopes, ProtoScope};
 
@@ -10,6 +14,12 @@ pub(crate) struct MessageScope {
     pub name: Rc<str>,
     pub children: Vec<Rc<ProtoScope>>,
     pub entries: Vec<MessageEntry>,
+    pub options: Vec<ProtoOption>,
+    pub extension_ranges: Vec<FieldRange>,
+    pub reserved: Vec<ReservedDeclaration>,
+    /// Fields of `extend` blocks declared in the message, they extend other messages
+    pub extensions: Vec<Extension>,
+    pub span: Option<Span>,
 }
 
 impl ChildrenScopes for MessageScope {
@@ -19,19 +29,34 @@ impl ChildrenScopes for MessageScope {
 }
 
 impl MessageScope {
+    /// All fields including extensions sorted by tag
     pub fn get_fields(&self) -> Vec<&Field> {
         let mut fields = self
             .entries
             .iter()
             .flat_map(|f| match f {
                 MessageEntry::Field(f) => vec![f],
-                MessageEntry::OneOf(one_of) => one_of.options.iter().collect(),
+                MessageEntry::OneOf(one_of) => one_of.fields.iter().collect(),
+                MessageEntry::Extension(f) => vec![f],
             })
             .collect::<Vec<_>>();
 
         fields.sort_by_key(|x| x.tag);
         fields
     }
+
+    pub fn is_one_of_member(&self, field: &Field) -> bool {
+        self.entries.iter().any(|entry| match entry {
+            MessageEntry::OneOf(one_of) => one_of.fields.iter().any(|f| f.tag == field.tag),
+            _ => false,
+        })
+    }
+
+    pub fn is_extension(&self, field: &Field) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| matches!(entry, MessageEntry::Extension(f) if f.tag == field.tag))
+    }
 }
 
 impl std::fmt::Display for MessageScope {
@@ -8,13 +8,13 @@ use super::{
 };
 
 #[derive(Debug)]
-pub(crate) struct RootScope {
-    pub children: Vec<Rc<ProtoScope>>,
-    pub types: HashMap<usize, Vec<Rc<str>>>,
+pub struct RootScope {
+    pub(crate) children: Vec<Rc<ProtoScope>>,
+    pub(crate) types: HashMap<usize, Vec<Rc<str>>>,
 }
 
 impl RootScope {
-    pub fn get_declaration_path(&self, decl_id: usize) -> Option<ProtoPath> {
+    pub(crate) fn get_declaration_path(&self, decl_id: usize) -> Option<ProtoPath> {
         let mut res = ProtoPath::new();
         let mut str_path = &self.types.get(&decl_id)?[..];
         let first_name = &str_path[0];
@@ -30,6 +30,15 @@ impl RootScope {
         Some(res)
     }
 
+    pub(crate) fn get_declaration(&self, decl_id: usize) -> Option<Rc<ProtoScope>> {
+        let str_path = &self.types.get(&decl_id)?[..];
+        let mut current = self.resolve_name(&str_path[0])?;
+        for name in &str_path[1..] {
+            current = current.resolve_name(name)?;
+        }
+        Some(current)
+    }
+
     pub fn get_declaration_name(&self, decl_id: usize) -> Option<Rc<str>> {
         let str_path = &self.types.get(&decl_id)?;
         let last_name = &str_path[str_path.len() - 1];
@@ -0,0 +1,43 @@
+use std::rc::Rc;
+
+use crate::proto::{options::ProtoOption, package::Rpc, span::Span};
+
+use super::{ProtoScope, traits::ChildrenScopes};
+
+#[derive(Debug)]
+pub(crate) struct ServiceScope {
+    pub id: usize,
+    pub name: Rc<str>,
+    pub rpcs: Vec<Rpc>,
+    pub options: Vec<ProtoOption>,
+    pub span: Option<Span>,
+}
+
+impl ChildrenScopes for ServiceScope {
+    fn children(&self) -> &[Rc<ProtoScope>] {
+        &[]
+    }
+}
+
+impl std::fmt::Display for ServiceScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "/* id: {} */", self.id)?;
+        writeln!(f, "service {} {{", self.name)?;
+        for option in self.options.iter() {
+            writeln!(f, "  option {};", option)?;
+        }
+        for rpc in self.rpcs.iter() {
+            let stream = |is_stream: bool| if is_stream { "stream " } else { "" };
+            writeln!(
+                f,
+                "  rpc {}({}/* id: {} */) returns ({}/* id: {} */);",
+                rpc.name,
+                stream(rpc.client_streaming),
+                rpc.input_type,
+                stream(rpc.server_streaming),
+                rpc.output_type
+            )?;
+        }
+        writeln!(f, "}}")
+    }
+}
@@ -0,0 +1,439 @@
+use std::{collections::HashMap, rc::Rc};
+
+use crate::proto::{
+    compiler::ts::rpc_name_to_method_name::rpc_name_to_method_name,
+    error::{ProtoError, located_error},
+    options::find_option,
+    package::{
+        EnumEntry, FIELD_NUMBERS, Field, FieldRange, MessageEntry, OneOfGroup, ProtoVersion, Rpc,
+        Type,
+    },
+};
+
+use super::{
+    ProtoScope, enum_scope::EnumScope, message::MessageScope, root_scope::RootScope,
+    service::ServiceScope, traits::ChildrenScopes,
+};
+
+/// Field numbers used internally by the protobuf implementation
+const IMPLEMENTATION_RESERVED: FieldRange = FieldRange {
+    start: 19_000,
+    end: 19_999,
+};
+
+/// Checks the resolved schema and reports all violations at once
+pub(in crate::proto) fn validate(root: &RootScope) -> Result<(), ProtoError> {
+    let mut errors = Vec::new();
+    validate_names(root.children(), None, &mut errors);
+    for child in root.children() {
+        validate_scope(child, "", ProtoVersion::Proto2, &mut errors);
+    }
+    ProtoError::from_errors(errors)
+}
+
+/// `path` is the full name of the package or message containing the scope
+fn validate_scope(
+    scope: &ProtoScope,
+    path: &str,
+    version: ProtoVersion,
+    errors: &mut Vec<ProtoError>,
+) {
+    let full_name = |scope: &ProtoScope| match path {
+        "" => scope.name().to_string(),
+        _ => format!("{}.{}", path, scope.name()),
+    };
+    let (path, version) = match scope {
+        ProtoScope::File(file) => (path.to_string(), file.version),
+        ProtoScope::Package(_) => {
+            let path = full_name(scope);
+            validate_names(scope.children(), Some(&format!("package {}", path)), errors);
+            (path, version)
+        }
+        ProtoScope::Message(message) => {
+            validate_message(message, errors);
+            let path = full_name(scope);
+            validate_names(scope.children(), Some(&format!("message {}", path)), errors);
+            (path, version)
+        }
+        ProtoScope::Enum(enum_scope) => {
+            validate_enum(enum_scope, version, errors);
+            (path.to_string(), version)
+        }
+        ProtoScope::Service(service) => {
+            validate_service(service, errors);
+            (path.to_string(), version)
+        }
+        _ => (path.to_string(), version),
+    };
+    for child in scope.children() {
+        validate_scope(child, &path, version, errors);
+    }
+}
+
+/// Messages, enums and services declared in all files of a package, or nested in a message,
+/// must have distinct names
+fn validate_names(
+    children: &[Rc<ProtoScope>],
+    container: Option<&str>,
+    errors: &mut Vec<ProtoError>,
+) {
+    // Nested messages and enums are not kept in the order of their declarations
+    let in_source_order = |scopes: &[Rc<ProtoScope>]| {
+        let mut scopes = scopes
+            .iter()
+            .filter(|scope| scope.id().is_some())
+            .map(Rc::clone)
+            .collect::<Vec<_>>();
+        scopes.sort_by_key(|scope| scope.span().map(|s| (s.start_line, s.start_column)));
+        scopes
+    };
+    let mut declarations = Vec::new();
+    for child in children {
+        if let ProtoScope::File(file) = child.as_ref() {
+            declarations.extend(in_source_order(&file.children));
+        }
+    }
+    declarations.extend(in_source_order(children));
+    let mut by_name: HashMap<Rc<str>, &ProtoScope> = HashMap::new();
+    for declaration in &declarations {
+        let name = declaration.name();
+        if let Some(first) = by_name.get(&name) {
+            let message = match container {
+                Some(container) => format!("{} is already defined in {}", name, container),
+                None => format!("{} is already defined", name),
+            };
+            errors.push(
+                located_error(message, declaration.span())
+                    .with_note("first defined here", first.span()),
+            );
+        } else {
+            by_name.insert(name, declaration);
+        }
+    }
+}
+
+fn validate_message(message: &MessageScope, errors: &mut Vec<ProtoError>) {
+    let mut fields: Vec<(&Field, Option<&OneOfGroup>)> = Vec::new();
+    for entry in &message.entries {
+        match entry {
+            MessageEntry::Field(f) | MessageEntry::Extension(f) => fields.push((f, None)),
+            MessageEntry::OneOf(one_of) => {
+                fields.extend(one_of.fields.iter().map(|f| (f, Some(one_of))));
+            }
+        }
+    }
+
+    let mut by_tag: HashMap<i64, &Field> = HashMap::new();
+    let mut by_name: HashMap<&str, &Field> = HashMap::new();
+    for (field, one_of) in fields {
+        if let Some(one_of) = one_of
+            && let Type::Map(_, _) = field.field_type
+        {
+            errors.push(located_error(
+                format!(
+                    "Map field {} of {} cannot be a member of oneof {}",
+                    field.name, message.name, one_of.name
+                ),
+                field.span.as_ref(),
+            ));
+        }
+        if !FIELD_NUMBERS.contains(field.tag) {
+            errors.push(located_error(
+                format!(
+                    "Field {} of {} has number {}, but field numbers must be in range {} to {}",
+                    field.name, message.name, field.tag, FIELD_NUMBERS.start, FIELD_NUMBERS.end
+                ),
+                field.span.as_ref(),
+            ));
+        } else if IMPLEMENTATION_RESERVED.contains(field.tag) {
+            errors.push(located_error(
+                format!(
+                    "Field {} of {} has number {}, but numbers {} are reserved for the protobuf implementation",
+                    field.name, message.name, field.tag, IMPLEMENTATION_RESERVED
+                ),
+                field.span.as_ref(),
+            ));
+        }
+        if let Some(first) = by_tag.get(&field.tag) {
+            errors.push(
+                located_error(
+                    format!(
+                        "Field {} of {} has the same number {} as field {}",
+                        field.name, message.name, field.tag, first.name
+                    ),
+                    field.span.as_ref(),
+                )
+                .with_note("first defined here", first.span.as_ref()),
+            );
+        } else {
+            by_tag.insert(field.tag, field);
+        }
+        if let Some(first) = by_name.get(&*field.name) {
+            errors.push(
+                located_error(
+                    format!(
+                        "Field {} of {} is already declared with number {}",
+                        field.name, message.name, first.tag
+                    ),
+                    field.span.as_ref(),
+                )
+                .with_note("first defined here", first.span.as_ref()),
+            );
+        } else {
+            by_name.insert(&field.name, field);
+        }
+    }
+}
+
+/// Rpcs become methods of the generated client and handler, so their names must stay
+/// distinct after the first letter is lowercased
+fn validate_service(service: &ServiceScope, errors: &mut Vec<ProtoError>) {
+    let mut by_method_name: HashMap<String, &Rpc> = HashMap::new();
+    for rpc in &service.rpcs {
+        let method_name = rpc_name_to_method_name(&rpc.name);
+        let Some(first) = by_method_name.get(&method_name) else {
+            by_method_name.insert(method_name, rpc);
+            continue;
+        };
+        let message = if first.name == rpc.name {
+            format!("Rpc {} of {} is already defined", rpc.name, service.name)
+        } else {
+            format!(
+                "Rpc {} of {} generates the same method {} as rpc {}",
+                rpc.name, service.name, method_name, first.name
+            )
+        };
+        errors.push(
+            located_error(message, rpc.span.as_ref())
+                .with_note("first defined here", first.span.as_ref()),
+        );
+    }
+}
+
+fn validate_enum(enum_scope: &EnumScope, version: ProtoVersion, errors: &mut Vec<ProtoError>) {
+    if version == ProtoVersion::Proto3
+        && let Some(first) = enum_scope.entries.first()
+        && first.value != 0
+    {
+        errors.push(located_error(
+            format!(
+                "The first value of proto3 enum {} must be zero, but {} = {}",
+                enum_scope.name, first.name, first.value
+            ),
+            first.span.as_ref(),
+        ));
+    }
+
+    let allow_alias = find_option(&enum_scope.options, "allow_alias")
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false);
+    if allow_alias {
+        return;
+    }
+    let mut by_value: HashMap<i64, &EnumEntry> = HashMap::new();
+    for entry in &enum_scope.entries {
+        if let Some(first) = by_value.get(&entry.value) {
+            errors.push(
+                located_error(
+                    format!(
+                        "Enum value {} of {} has the same number {} as {}, set option allow_alias = true to allow aliases",
+                        entry.name, enum_scope.name, entry.value, first.name
+                    ),
+                    entry.span.as_ref(),
+                )
+                .with_note("first defined here", first.span.as_ref()),
+            );
+        } else {
+            by_value.insert(entry.value, entry);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_validate {
+    use std::{collections::HashMap, rc::Rc};
+
+    use crate::proto::{
+        error::ProtoError,
+        package::{EnumEntry, Field, MessageEntry, ProtoVersion, Type},
+        proto_scope::{
+            ProtoScope, enum_scope::EnumScope, file::FileScope, message::MessageScope,
+            root_scope::RootScope,
+        },
+    };
+
+    use super::validate;
+    use crate::test_utils::try_build_files;
+
+    fn field(name: &str, tag: i64) -> MessageEntry {
+        MessageEntry::Field(Field {
+            name: name.into(),
+            field_type: Type::Int32,
+            tag,
+            options: vec![],
+            packed: true,
+            span: None,
+        })
+    }
+
+    #[test]
+    fn reports_all_violations() {
+        let message = MessageScope {
+            id: 0,
+            name: "Msg".into(),
+            children: vec![],
+            entries: vec![field("a", 1), field("b", 1), field("c", 19_500)],
+            options: vec![],
+            extension_ranges: vec![],
+            reserved: vec![],
+            extensions: vec![],
+            span: None,
+        };
+        let enum_scope = EnumScope {
+            id: 1,
+            name: "Kind".into(),
+            entries: vec![EnumEntry {
+                name: "FIRST".into(),
+                value: 1,
+                options: vec![],
+                span: None,
+            }],
+            options: vec![],
+            reserved: vec![],
+            span: None,
+        };
+        let root = RootScope {
+            children: vec![Rc::new(ProtoScope::File(FileScope {
+                name: "a.proto".into(),
+                children: vec![
+                    Rc::new(ProtoScope::Message(message)),
+                    Rc::new(ProtoScope::Enum(enum_scope)),
+                ],
+                version: ProtoVersion::Proto3,
+                generated: true,
+                imports: vec![],
+                options: vec![],
+                extensions: vec![],
+            }))],
+            types: HashMap::new(),
+        };
+        let errors = match validate(&root) {
+            Err(ProtoError::Multiple(errors)) => {
+                errors.iter().map(|e| e.to_string()).collect::<Vec<_>>()
+            }
+            other => panic!("expected multiple errors, got {:?}", other),
+        };
+        assert_eq!(
+            errors,
+            vec![
+                "Field b of Msg has the same number 1 as field a",
+                "Field c of Msg has number 19500, but numbers 19000 to 19999 are reserved for the protobuf implementation",
+                "The first value of proto3 enum Kind must be zero, but FIRST = 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_duplicate_declarations() {
+        let err = try_build_files(&[
+            (
+                "app/a.proto",
+                "syntax = \"proto3\";\npackage app;\nmessage M { int32 id = 536870912; }\n",
+            ),
+            (
+                "app/b.proto",
+                "syntax = \"proto3\";\npackage app;\nenum M { M_UNKNOWN = 0; }\nmessage N {\n  message I {}\n  enum I { I_UNKNOWN = 0; }\n}\n",
+            ),
+        ])
+        .unwrap_err();
+        let errors = err.into_errors();
+        let messages = errors.iter().map(|e| e.message()).collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                "M is already defined in package app",
+                "Field id of M has number 536870912, but field numbers must be in range 1 to 536870911",
+                "I is already defined in message app.N",
+            ]
+        );
+        let span = errors[0].span().unwrap();
+        assert!(span.file_path.ends_with("app/b.proto"));
+        assert_eq!((span.start_line, span.start_column), (3, 1));
+        match &errors[0] {
+            ProtoError::SemanticError { notes, .. } => {
+                assert!(notes[0].span.file_path.ends_with("app/a.proto"));
+                assert_eq!(notes[0].span.start_line, 3);
+            }
+            other => panic!("expected semantic error, got {:?}", other),
+        }
+        let span = errors[2].span().unwrap();
+        assert_eq!((span.start_line, span.start_column), (6, 3));
+    }
+
+    #[test]
+    fn reports_rpcs_generating_the_same_method() {
+        let err = try_build_files(&[(
+            "app/api.proto",
+            r#"syntax = "proto3";
+package app;
+message Req {}
+service Api {
+  rpc Get(Req) returns (Req);
+  rpc get(Req) returns (Req);
+  rpc Get(Req) returns (Req);
+}
+"#,
+        )])
+        .unwrap_err();
+        let errors = err.into_errors();
+        let messages = errors.iter().map(|e| e.message()).collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                "Rpc get of Api generates the same method get as rpc Get",
+                "Rpc Get of Api is already defined",
+            ]
+        );
+        let lines = errors
+            .iter()
+            .map(|e| e.span().unwrap().start_line)
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![6, 7]);
+    }
+
+    #[test]
+    fn locates_unresolved_rpc_types_and_extendees() {
+        let err = try_build_files(&[(
+            "app/api.proto",
+            r#"syntax = "proto2";
+package app;
+enum E { E_UNKNOWN = 0; }
+service Api {
+  rpc Get(Missing) returns (E);
+}
+extend Unknown {
+  optional int32 id = 100;
+}
+"#,
+        )])
+        .unwrap_err();
+        let errors = err.into_errors();
+        let messages = errors.iter().map(|e| e.message()).collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                "Cannot resolve Unknown",
+                "Cannot resolve Missing in Api",
+                "Type E of rpc Get is not a message in Api",
+            ]
+        );
+        let positions = errors
+            .iter()
+            .map(|e| {
+                let span = e.span().unwrap();
+                (span.start_line, span.start_column, span.end_column)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(positions, vec![(7, 8, 14), (5, 11, 17), (5, 29, 29)]);
+    }
+}
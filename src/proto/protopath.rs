@@ -6,6 +6,7 @@ pub(crate) enum PathComponent {
     File(Rc<str>),
     Message(Rc<str>),
     Enum(Rc<str>),
+    Service(Rc<str>),
 }
 impl PathComponent {
     pub fn as_str(&self) -> Rc<str> {
@@ -14,6 +15,7 @@ impl PathComponent {
             PathComponent::File(s) => Rc::clone(&s),
             PathComponent::Message(s) => Rc::clone(&s),
             PathComponent::Enum(s) => Rc::clone(&s),
+            PathComponent::Service(s) => Rc::clone(s),
         }
     }
 }
@@ -47,6 +49,15 @@ impl ProtoPath {
     pub fn is_empty(&self) -> bool {
         self.path.is_empty()
     }
+    /// Returns fully qualified proto name, like `my.package.Message.Nested`
+    pub fn full_name(&self) -> String {
+        self.path
+            .iter()
+            .filter(|component| !matches!(component, PathComponent::File(_)))
+            .map(|component| component.as_str().to_string())
+            .collect::<Vec<_>>()
+            .join(".")
+    }
 }
 
 impl std::fmt::Display for ProtoPath {
@@ -59,6 +70,7 @@ impl std::fmt::Display for ProtoPath {
                 (PathComponent::Package(prev), _) => write!(f, "{}/", prev)?,
                 (PathComponent::File(prev), _) => write!(f, "{}::", prev)?,
                 (PathComponent::Enum(_), _) => unreachable!(),
+                (PathComponent::Service(_), _) => unreachable!(),
                 (PathComponent::Message(prev), _) => write!(f, "{}.", prev)?,
             }
         }
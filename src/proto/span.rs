@@ -0,0 +1,57 @@
+use std::{fmt::Display, path::Path, rc::Rc};
+
+use super::lexems::{LocatedLexem, Position, SourceRange};
+
+/// Location of a declaration in a proto file.
+/// Unlike `SourceRange` it does not borrow the file path, so it can be stored in declarations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub file_path: Rc<Path>,
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl Span {
+    /// Span from the start of the first lexem to the end of the last one
+    pub(super) fn between(first: &LocatedLexem, last: &LocatedLexem) -> Self {
+        Span {
+            file_path: Rc::from(first.range.start.file_path),
+            start_line: first.range.start.line,
+            start_column: first.range.start.column,
+            end_line: last.range.end.line,
+            end_column: last.range.end.column,
+        }
+    }
+
+    pub(super) fn from_range(range: &SourceRange) -> Self {
+        Span {
+            file_path: Rc::from(range.start.file_path),
+            start_line: range.start.line,
+            start_column: range.start.column,
+            end_line: range.end.line,
+            end_column: range.end.column,
+        }
+    }
+
+    /// Span of a single character
+    pub(super) fn at(position: &Position) -> Self {
+        Span::from_range(&SourceRange {
+            start: *position,
+            end: *position,
+        })
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}",
+            self.file_path.to_string_lossy(),
+            self.start_line,
+            self.start_column
+        )
+    }
+}
@@ -1,20 +1,23 @@
-use std::{ops::Deref, rc::Rc};
+use std::{ops::Deref, path::Path, rc::Rc};
 
 use crate::proto::package::FieldDeclaration;
 
 use super::{
     error::{ProtoError, syntax_error},
     id_generator::IdGenerator,
-    lexems::{Lexem, LocatedLexem},
+    lexems::{Lexem, LocatedLexem, read_lexems},
+    options::{Constant, OptionName, OptionNamePart, ProtoOption},
     package::{
-        Declaration, EnumDeclaration, EnumEntry, FieldTypeReference, ImportPath,
+        Declaration, ENUM_VALUES, EnumDeclaration, EnumEntry, ExtendDeclaration,
+        ExtensionsDeclaration, FIELD_NUMBERS, FieldRange, FieldTypeReference, ImportPath,
         MessageDeclaration, MessageDeclarationEntry, OneOfDeclaration, ProtoFile,
+        ReservedDeclaration, RpcDeclaration, ServiceDeclaration,
     },
+    span::Span,
 };
 
 #[derive(Debug, Clone)]
 enum Task {
-    ParseStatements,
     ParseStatement,
     ParseSyntaxStatement,
     ParseImportStatement,
@@ -23,25 +26,38 @@ enum Task {
     ParseEnumDeclaration,
     ParseEnumEntries,
     ParseEnumEntry,
+    /// [EnumEntry[], String, i64, Option<ProtoOption[]>] -> EnumEntry[]
+    PushEnumEntry,
     WrapRepeated,
     WrapOptional,
     ParseFieldDeclaration,
     ParseMessageStatement,
     ExpectLexem(Lexem),
     Push(StackItem),
-    ParseFieldAttributes,
+    ParseFieldOptions,
     /// Takes declaration from the stack
     /// And pushes it to the package declarations
     AppendDeclarationToPackage,
-    ParseFieldAttribute,
+    ParseFieldOption,
     PushFieldDeclaration,
-    PushFieldAttribute,
+    PushFieldOption,
     ParseMessageEntries,
     ParseMessageEntry,
-    ParseOptionalAttributes,
+    ParseOptionalOptions,
+    /// Parses `option name = constant;` and pushes ProtoOption
+    ParseOptionStatement,
+    /// Parses `name`, `(full.ident)` or `(full.ident).name` and pushes OptionName
+    ParseOptionName,
+    /// Parses constant value of an option and pushes it
+    ParseConstant,
+    /// [OptionName, Constant] -> ProtoOption
+    WrapOption,
+    /// Takes ProtoOption from the stack and appends it to the file options
+    AppendOptionToFile,
+    /// [ProtoOption[], Entries, ProtoOption] -> [ProtoOption[], Entries]
+    PushDeclarationOption,
     ParseInt64,
     ParseFieldType,
-    ParseStringLiteral,
     WrapMessageEntry,
     PushMessageEntry,
     PushMessageStatement,
@@ -57,6 +73,38 @@ enum Task {
     PushOneOf,
     /// Parses identifier and places it into stack
     ParseId,
+    /// Parses service declaration and pushes it to stack
+    ParseServiceDeclaration,
+    ParseServiceEntries,
+    /// Parses `rpc Name(stream Req) returns (stream Res) { ... }`
+    ParseRpc,
+    /// Parses `stream Type` or `Type` and pushes RpcType
+    ParseRpcType,
+    /// [Start, FieldType] -> RpcType
+    WrapRpcType(bool),
+    /// Parses `;` or `{ option ...; }` and pushes ProtoOption[]
+    ParseRpcBody,
+    ParseRpcOptions,
+    /// [ProtoOption[], ProtoOption] -> ProtoOption[]
+    PushOption,
+    /// [Rpc[], String, RpcType, RpcType, ProtoOption[]] -> Rpc[]
+    PushRpc,
+    /// Parses `extend Type { fields }` and pushes ExtendDeclaration
+    ParseExtendDeclaration,
+    /// [Start, FieldType] -> Extendee
+    WrapExtendee,
+    /// [Extendee, ProtoOption[], MessageEntry[]] -> ExtendDeclaration
+    PushExtend,
+    /// Parses `1, 5 to 10, 100 to max` within bounds and pushes FieldRange[]
+    ParseFieldRanges(FieldRange),
+    /// [MessageEntry[], FieldRange[], Option<ProtoOption[]>] -> MessageEntry[]
+    PushExtensions,
+    /// Pushes index of the current lexem, it is used as start of the declaration span
+    PushStart,
+    /// Parses `reserved 1, 5 to max;` or `reserved "a", "b";` and pushes ReservedDeclaration
+    ParseReserved(FieldRange),
+    /// [Reserved[], ProtoOption[], EnumEntry[], Reserved] -> [Reserved[], ProtoOption[], EnumEntry[]]
+    PushEnumReserved,
 }
 use Task::*;
 use tracing::instrument;
@@ -71,9 +119,25 @@ enum StackItem {
     FieldType(FieldTypeReference),
     Int64(i64),
     Message(MessageDeclaration),
-    OptionalAttributes(Option<Vec<(Rc<str>, Rc<str>)>>),
+    OptionalOptions(Option<Vec<ProtoOption>>),
+    Options(Vec<ProtoOption>),
+    Option(ProtoOption),
+    OptionName(OptionName),
+    Constant(Constant),
     Enum(EnumDeclaration),
     OneOf(OneOfDeclaration),
+    /// Type of rpc input or output with its location, flag is true for streams
+    RpcType(bool, FieldTypeReference, Span),
+    /// Extended message of an extend block with its location
+    Extendee(FieldTypeReference, Span),
+    RpcList(Vec<RpcDeclaration>),
+    Service(ServiceDeclaration),
+    FieldRanges(Vec<FieldRange>),
+    Extend(ExtendDeclaration),
+    /// Index of the first lexem of a declaration
+    Start(usize),
+    Reserved(ReservedDeclaration),
+    ReservedList(Vec<ReservedDeclaration>),
 }
 
 impl From<Rc<str>> for StackItem {
@@ -111,32 +175,87 @@ impl From<FieldTypeReference> for StackItem {
         StackItem::FieldType(f)
     }
 }
+impl From<ServiceDeclaration> for StackItem {
+    fn from(s: ServiceDeclaration) -> Self {
+        StackItem::Service(s)
+    }
+}
+impl From<ExtendDeclaration> for StackItem {
+    fn from(e: ExtendDeclaration) -> Self {
+        StackItem::Extend(e)
+    }
+}
+impl From<ProtoOption> for StackItem {
+    fn from(o: ProtoOption) -> Self {
+        StackItem::Option(o)
+    }
+}
 
+/// Parses all top level statements of the file.
+/// Statement with a syntax error is skipped, so that errors of the next statements are reported too
 #[instrument(skip_all, fields(file_name = res.name.to_string()))]
 pub(super) fn parse_package(
     id_gen: &mut IdGenerator,
     located_lexems: &[LocatedLexem],
     res: &mut ProtoFile,
 ) -> Result<(), ProtoError> {
+    let mut errors = Vec::new();
+    let mut ind = 0;
+    while located_lexems[ind].lexem != Lexem::EOF {
+        match parse_statement(id_gen, located_lexems, ind, res) {
+            Ok(next_ind) => ind = next_ind,
+            Err(err) => {
+                errors.push(err);
+                ind = skip_statement(located_lexems, ind);
+            }
+        }
+    }
+    ProtoError::from_errors(errors)
+}
+
+/// Parses the text format message of an uninterpreted option, which is written without braces
+pub(super) fn parse_aggregate_text(path: &Path, text: &str) -> Result<Constant, ProtoError> {
+    let located_lexems = read_lexems(path, &format!("{} }}", text))?;
     let mut ind = 0;
-    let mut tasks: Vec<Task> = vec![ParseStatements];
+    let res = parse_aggregate(&located_lexems, &mut ind, Lexem::CloseCurly)?;
+    let loc_lexem = &located_lexems[ind];
+    if loc_lexem.lexem != Lexem::EOF {
+        return Err(syntax_error("Expected end of the message", loc_lexem));
+    }
+    Ok(res)
+}
+
+/// Returns index of the lexem after the end of the statement starting at `start`.
+/// Statement ends with `;` or with the `}` closing its body
+fn skip_statement(located_lexems: &[LocatedLexem], start: usize) -> usize {
+    let mut depth = 0usize;
+    let mut ind = start;
+    loop {
+        match located_lexems[ind].lexem {
+            Lexem::EOF => return ind,
+            Lexem::OpenCurly => depth += 1,
+            Lexem::CloseCurly if depth <= 1 => return ind + 1,
+            Lexem::CloseCurly => depth -= 1,
+            Lexem::SemiColon if depth == 0 => return ind + 1,
+            _ => {}
+        }
+        ind += 1;
+    }
+}
+
+/// Parses single top level statement and returns index of the lexem after it
+fn parse_statement(
+    id_gen: &mut IdGenerator,
+    located_lexems: &[LocatedLexem],
+    start: usize,
+    res: &mut ProtoFile,
+) -> Result<usize, ProtoError> {
+    let mut ind = start;
+    let mut tasks: Vec<Task> = vec![ParseStatement];
     let mut stack: Vec<StackItem> = Vec::new();
     while let Some(task) = tasks.pop() {
         tracing::trace!(?task, lexem = ?&located_lexems[ind].lexem, ?stack, "do");
         match task {
-            ParseStatements => {
-                let located_lexem = &located_lexems[ind];
-                let lexem = &located_lexem.lexem;
-                match lexem {
-                    Lexem::EOF => {
-                        break;
-                    }
-                    _ => {
-                        tasks.push(ParseStatements);
-                        tasks.push(ParseStatement)
-                    }
-                }
-            }
             ParseStatement => {
                 let located_lexem = &located_lexems[ind];
                 let lexem = &located_lexem.lexem;
@@ -163,13 +282,20 @@ pub(super) fn parse_package(
                         tasks.push(ParseEnumDeclaration);
                         continue;
                     }
+                    Lexem::Id(id) if id.deref() == "service" => {
+                        tasks.push(AppendDeclarationToPackage);
+                        tasks.push(ParseServiceDeclaration);
+                        continue;
+                    }
                     Lexem::Id(id) if id.deref() == "option" => {
-                        tracing::error!("option statement is not supported");
-                        todo!("option statement is not supported yet");
+                        tasks.push(AppendOptionToFile);
+                        tasks.push(ParseOptionStatement);
+                        continue;
                     }
                     Lexem::Id(id) if id.deref() == "extend" => {
-                        tracing::error!("extend statement is not supported");
-                        todo!("extend statement is not supported yet");
+                        tasks.push(AppendDeclarationToPackage);
+                        tasks.push(ParseExtendDeclaration);
+                        continue;
                     }
                     Lexem::Id(id) => {
                         return Err(syntax_error(
@@ -194,11 +320,67 @@ pub(super) fn parse_package(
                 let declaration = match stack.pop() {
                     Some(StackItem::Message(message)) => Declaration::Message(message),
                     Some(StackItem::Enum(enum_decl)) => Declaration::Enum(enum_decl),
+                    Some(StackItem::Service(service)) => Declaration::Service(service),
+                    Some(StackItem::Extend(extend)) => Declaration::Extend(extend),
                     _ => unreachable!(),
                 };
                 res.declarations.push(declaration);
                 continue;
             }
+            AppendOptionToFile => {
+                let option = match stack.pop() {
+                    Some(StackItem::Option(option)) => option,
+                    _ => unreachable!(),
+                };
+                res.options.push(option);
+                continue;
+            }
+            ParseOptionStatement => {
+                tasks.push(ExpectLexem(Lexem::SemiColon));
+                tasks.push(WrapOption);
+                tasks.push(ParseConstant);
+                tasks.push(ExpectLexem(Lexem::Equal));
+                tasks.push(ParseOptionName);
+                tasks.push(ExpectLexem(Lexem::Id("option".into())));
+                continue;
+            }
+            ParseOptionName => {
+                let option_name = parse_option_name(located_lexems, &mut ind)?;
+                stack.push(StackItem::OptionName(option_name));
+                continue;
+            }
+            ParseConstant => {
+                let constant = parse_constant(located_lexems, &mut ind)?;
+                stack.push(StackItem::Constant(constant));
+                continue;
+            }
+            WrapOption => {
+                let value = match stack.pop() {
+                    Some(StackItem::Constant(value)) => value,
+                    _ => unreachable!(),
+                };
+                let name = match stack.pop() {
+                    Some(StackItem::OptionName(name)) => name,
+                    _ => unreachable!(),
+                };
+                stack.push(ProtoOption { name, value }.into());
+                continue;
+            }
+            PushDeclarationOption => {
+                let option = match stack.pop() {
+                    Some(StackItem::Option(option)) => option,
+                    _ => unreachable!(),
+                };
+                let entries = stack.pop().unwrap();
+                let mut options = match stack.pop() {
+                    Some(StackItem::Options(options)) => options,
+                    _ => unreachable!(),
+                };
+                options.push(option);
+                stack.push(StackItem::Options(options));
+                stack.push(entries);
+                continue;
+            }
             ExpectKeyTypeOnStack => {
                 let key_type = match stack.pop() {
                     Some(StackItem::FieldType(field_type)) => field_type,
@@ -273,8 +455,12 @@ pub(super) fn parse_package(
                     (Lexem::Id(id), Lexem::StringLiteral(s), Lexem::SemiColon)
                         if id.deref().eq("import") =>
                     {
+                        let mut imports_components: ImportPath = parse_import_path(s);
+                        imports_components.span = Some(Span::between(
+                            &located_lexems[ind],
+                            &located_lexems[ind + 2],
+                        ));
                         ind += 3;
-                        let imports_components: ImportPath = parse_import_path(s);
                         res.imports.push(imports_components);
                         continue;
                     }
@@ -303,68 +489,69 @@ pub(super) fn parse_package(
                     }
                 }
             }
-            ParseOptionalAttributes => {
+            ParseOptionalOptions => {
                 let located_lexem = &located_lexems[ind];
                 let lexem = &located_lexem.lexem;
                 match lexem {
                     Lexem::OpenBracket => {
-                        stack.push(StackItem::OptionalAttributes(Some(Vec::new())));
-                        tasks.push(ParseFieldAttributes);
+                        stack.push(StackItem::OptionalOptions(Some(Vec::new())));
+                        tasks.push(ParseFieldOptions);
+                        tasks.push(ParseFieldOption);
                         tasks.push(ExpectLexem(Lexem::OpenBracket));
                         continue;
                     }
                     _ => {
-                        stack.push(StackItem::OptionalAttributes(None));
+                        stack.push(StackItem::OptionalOptions(None));
                         continue;
                     }
                 }
             }
-            ParseFieldAttributes => {
+            ParseFieldOptions => {
                 let located_lexem = &located_lexems[ind];
                 let lexem = &located_lexem.lexem;
-                if let Lexem::CloseBracket = lexem {
-                    ind += 1;
-                    continue;
+                match lexem {
+                    Lexem::CloseBracket => {
+                        ind += 1;
+                        continue;
+                    }
+                    Lexem::Comma => {
+                        ind += 1;
+                        tasks.push(ParseFieldOptions);
+                        tasks.push(ParseFieldOption);
+                        continue;
+                    }
+                    _ => {
+                        return Err(syntax_error("Expected comma or ]", located_lexem));
+                    }
                 }
-                tasks.push(ParseFieldAttributes);
-                tasks.push(ParseFieldAttribute);
-
-                continue;
             }
-            ParseFieldAttribute => {
-                tasks.push(PushFieldAttribute);
-                tasks.push(ParseStringLiteral);
+            ParseFieldOption => {
+                tasks.push(PushFieldOption);
+                tasks.push(ParseConstant);
                 tasks.push(ExpectLexem(Lexem::Equal));
-                tasks.push(ParseId);
+                tasks.push(ParseOptionName);
                 continue;
             }
-            PushFieldAttribute => {
+            PushFieldOption => {
                 let value = match stack.pop() {
-                    Some(StackItem::String(s)) => s,
+                    Some(StackItem::Constant(value)) => value,
                     _ => unreachable!(),
                 };
-                let key = match stack.pop() {
-                    Some(StackItem::String(s)) => s,
-                    invalid_item => {
-                        println!("invalid item = {:?}", invalid_item);
-                        println!("value = {:?}", value);
-                        println!("location = {:?}", located_lexems[ind].range.start);
-                        print_state(stack, tasks, task, &located_lexems[ind - 1..]);
-                        unreachable!();
-                    }
+                let name = match stack.pop() {
+                    Some(StackItem::OptionName(name)) => name,
+                    _ => unreachable!(),
                 };
-                let optional_list_item = stack.pop().unwrap();
-                let mut optional_list = match optional_list_item {
-                    StackItem::OptionalAttributes(Some(optional_list)) => optional_list,
+                let mut optional_list = match stack.pop() {
+                    Some(StackItem::OptionalOptions(Some(optional_list))) => optional_list,
                     _ => unreachable!(),
                 };
-                optional_list.push((key, value));
-                stack.push(StackItem::OptionalAttributes(Some(optional_list)));
+                optional_list.push(ProtoOption { name, value });
+                stack.push(StackItem::OptionalOptions(Some(optional_list)));
                 continue;
             }
             PushFieldDeclaration => {
-                let attributes = match stack.pop() {
-                    Some(StackItem::OptionalAttributes(optional_attributes)) => optional_attributes,
+                let options = match stack.pop() {
+                    Some(StackItem::OptionalOptions(optional_options)) => optional_options,
                     _ => unreachable!(),
                 }
                 .unwrap_or_default();
@@ -380,11 +567,19 @@ pub(super) fn parse_package(
                     Some(StackItem::FieldType(field_type)) => field_type,
                     _ => unreachable!(),
                 };
+                let start = match stack.pop() {
+                    Some(StackItem::Start(start)) => start,
+                    _ => unreachable!(),
+                };
                 let field_declaration = FieldDeclaration {
                     name,
                     tag,
                     field_type_ref: field_type,
-                    attributes,
+                    options,
+                    span: Some(Span::between(
+                        &located_lexems[start],
+                        &located_lexems[ind - 2],
+                    )),
                 };
                 let mut message_entries = match stack.pop() {
                     Some(StackItem::MessageEntriesList(list)) => list,
@@ -450,6 +645,7 @@ pub(super) fn parse_package(
                     "Not enough lexems for enum statement",
                 )?;
 
+                stack.push(StackItem::Start(ind));
                 ind += 1;
                 let name_loc_lexem = &located_lexems[ind];
                 let name = &name_loc_lexem.lexem;
@@ -467,6 +663,8 @@ pub(super) fn parse_package(
                     }
                 }
                 ind += 1;
+                stack.push(StackItem::ReservedList(Vec::new()));
+                stack.push(StackItem::Options(Vec::new()));
                 stack.push(StackItem::EnumEntriesList(Vec::new()));
                 tasks.push(ParseEnumEntries);
                 continue;
@@ -477,23 +675,54 @@ pub(super) fn parse_package(
                 match separator {
                     Lexem::CloseCurly => {
                         ind += 1;
-                        let list_item = stack.pop().unwrap();
-                        let enum_name_item = stack.pop().unwrap();
-                        match (list_item, enum_name_item) {
-                            (StackItem::EnumEntriesList(entries), StackItem::String(name)) => {
-                                let enum_declaration: EnumDeclaration =
-                                    id_gen.create((name, entries));
-                                stack.push(enum_declaration.into());
-                            }
-                            (a, b) => {
-                                println!(
-                                    "Invalid stack items for enum declaration finishing: {:?} and {:?}",
-                                    a, b
-                                );
-                                print_state(stack, tasks, task, &located_lexems[ind..]);
-                                todo!("Cannot handle separator {:?}", separator);
-                            }
-                        }
+                        let entries = match stack.pop() {
+                            Some(StackItem::EnumEntriesList(entries)) => entries,
+                            _ => unreachable!(),
+                        };
+                        let options = match stack.pop() {
+                            Some(StackItem::Options(options)) => options,
+                            _ => unreachable!(),
+                        };
+                        let reserved = match stack.pop() {
+                            Some(StackItem::ReservedList(reserved)) => reserved,
+                            _ => unreachable!(),
+                        };
+                        let name = match stack.pop() {
+                            Some(StackItem::String(name)) => name,
+                            _ => unreachable!(),
+                        };
+                        let start = match stack.pop() {
+                            Some(StackItem::Start(start)) => start,
+                            _ => unreachable!(),
+                        };
+                        let mut enum_declaration: EnumDeclaration =
+                            id_gen.create((name, entries, options));
+                        enum_declaration.reserved = reserved;
+                        enum_declaration.span = Some(Span::between(
+                            &located_lexems[start],
+                            &located_lexems[ind - 1],
+                        ));
+                        stack.push(enum_declaration.into());
+                    }
+                    Lexem::SemiColon => {
+                        ind += 1;
+                        tasks.push(ParseEnumEntries);
+                        continue;
+                    }
+                    Lexem::Id(id) if id.deref() == "option" => {
+                        tasks.push(ParseEnumEntries);
+                        tasks.push(PushDeclarationOption);
+                        tasks.push(ParseOptionStatement);
+                        continue;
+                    }
+                    Lexem::Id(id)
+                        if id.deref() == "reserved"
+                            && is_reserved_statement(located_lexems, ind) =>
+                    {
+                        tasks.push(ParseEnumEntries);
+                        tasks.push(PushEnumReserved);
+                        tasks.push(ParseReserved(ENUM_VALUES));
+                        continue;
                     }
                     Lexem::Id(_) => {
                         tasks.push(ParseEnumEntries);
@@ -501,57 +730,67 @@ pub(super) fn parse_package(
                         continue;
                     }
                     _ => {
-                        print_state(stack, tasks, task, &located_lexems[ind..]);
-                        todo!("Cannot handle separator {:?}", separator);
+                        return Err(syntax_error(
+                            "Expected enum entry or closing curly brace",
+                            loc_separator,
+                        ));
                     }
                 }
             }
             ParseEnumEntry => {
-                assert_enough_length(located_lexems, ind, 4, "Not enough lexems for enum entry")?;
-                let id_loc = &located_lexems[ind];
-                ind += 1;
-                let eq_loc = &located_lexems[ind];
-                ind += 1;
-                let value_loc = &located_lexems[ind];
-                ind += 1;
-                let semi_loc = &located_lexems[ind];
-                ind += 1;
-                match (
-                    &id_loc.lexem,
-                    &eq_loc.lexem,
-                    &value_loc.lexem,
-                    &semi_loc.lexem,
-                ) {
-                    (Lexem::Id(id), Lexem::Equal, Lexem::IntLiteral(value), Lexem::SemiColon) => {
-                        let entries = stack.pop().unwrap();
-                        match entries {
-                            StackItem::EnumEntriesList(mut list) => {
-                                list.push(super::package::EnumEntry {
-                                    name: Rc::clone(id),
-                                    value: *value,
-                                });
-                                stack.push(StackItem::EnumEntriesList(list));
-                            }
-                            _ => {
-                                unreachable!()
-                            }
-                        }
-                        continue;
-                    }
-                    _ => {
-                        print_state(stack, tasks, task, &located_lexems[ind..]);
-                        todo!("Cannot parse enum entry")
-                    }
+                tasks.push(PushEnumEntry);
+                tasks.push(ExpectLexem(Lexem::SemiColon));
+                tasks.push(ParseOptionalOptions);
+                tasks.push(ParseInt64);
+                tasks.push(ExpectLexem(Lexem::Equal));
+                tasks.push(ParseId);
+                tasks.push(PushStart);
+                continue;
+            }
+            PushEnumEntry => {
+                let options = match stack.pop() {
+                    Some(StackItem::OptionalOptions(optional_options)) => optional_options,
+                    _ => unreachable!(),
                 }
+                .unwrap_or_default();
+                let value = match stack.pop() {
+                    Some(StackItem::Int64(value)) => value,
+                    _ => unreachable!(),
+                };
+                let name = match stack.pop() {
+                    Some(StackItem::String(name)) => name,
+                    _ => unreachable!(),
+                };
+                let start = match stack.pop() {
+                    Some(StackItem::Start(start)) => start,
+                    _ => unreachable!(),
+                };
+                let mut entries = match stack.pop() {
+                    Some(StackItem::EnumEntriesList(entries)) => entries,
+                    _ => unreachable!(),
+                };
+                entries.push(EnumEntry {
+                    name,
+                    value,
+                    options,
+                    span: Some(Span::between(
+                        &located_lexems[start],
+                        &located_lexems[ind - 2],
+                    )),
+                });
+                stack.push(StackItem::EnumEntriesList(entries));
+                continue;
             }
             ParseMessageStatement => {
                 tasks.push(PushMessageStatement);
                 tasks.push(ExpectLexem(Lexem::CloseCurly));
                 tasks.push(ParseMessageEntries);
                 tasks.push(Push(StackItem::MessageEntriesList(Vec::new())));
+                tasks.push(Push(StackItem::Options(Vec::new())));
                 tasks.push(ExpectLexem(Lexem::OpenCurly));
                 tasks.push(ParseId);
                 tasks.push(ExpectLexem(Lexem::Id("message".into())));
+                tasks.push(PushStart);
                 continue;
             }
             Push(stack_item) => {
@@ -563,12 +802,24 @@ pub(super) fn parse_package(
                     Some(StackItem::MessageEntriesList(entries)) => entries,
                     _ => unreachable!(),
                 };
+                let options = match stack.pop() {
+                    Some(StackItem::Options(options)) => options,
+                    _ => unreachable!(),
+                };
                 let message_name = match stack.pop() {
                     Some(StackItem::String(name)) => name,
                     _ => unreachable!(),
                 };
-                let message_declaration: MessageDeclaration =
-                    id_gen.create((message_name, entries));
+                let start = match stack.pop() {
+                    Some(StackItem::Start(start)) => start,
+                    _ => unreachable!(),
+                };
+                let mut message_declaration: MessageDeclaration =
+                    id_gen.create((message_name, entries, options));
+                message_declaration.span = Some(Span::between(
+                    &located_lexems[start],
+                    &located_lexems[ind - 1],
+                ));
                 stack.push(message_declaration.into());
                 continue;
             }
@@ -585,6 +836,12 @@ pub(super) fn parse_package(
                         message_entry
                     }
                     Some(StackItem::OneOf(decl)) => MessageDeclarationEntry::OneOf(decl),
+                    Some(StackItem::Reserved(decl)) => MessageDeclarationEntry::Reserved(decl),
+                    Some(StackItem::Extend(extend)) => {
+                        let decl: Declaration = extend.into();
+                        let message_entry: MessageDeclarationEntry = decl.into();
+                        message_entry
+                    }
                     _ => unreachable!(),
                 };
                 stack.push(entry.into());
@@ -615,9 +872,16 @@ pub(super) fn parse_package(
                     Lexem::CloseCurly => {
                         continue;
                     }
+                    Lexem::SemiColon => {
+                        ind += 1;
+                        tasks.push(ParseMessageEntries);
+                        continue;
+                    }
                     _ => {
-                        print_state(stack, tasks, task, &located_lexems[ind..]);
-                        todo!("Cannot handle separator {:?}", separator);
+                        return Err(syntax_error(
+                            "Expected message entry or closing curly brace",
+                            loc_separator,
+                        ));
                     }
                 }
             }
@@ -637,6 +901,40 @@ pub(super) fn parse_package(
                         tasks.push(ParseEnumDeclaration);
                         continue;
                     }
+                    Lexem::Id(id) if id.deref() == "option" => {
+                        tasks.push(PushDeclarationOption);
+                        tasks.push(ParseOptionStatement);
+                        continue;
+                    }
+                    Lexem::Id(id) if id.deref() == "extend" => {
+                        tasks.push(PushMessageEntry);
+                        tasks.push(WrapMessageEntry);
+                        tasks.push(ParseExtendDeclaration);
+                        continue;
+                    }
+                    Lexem::Id(id)
+                        if id.deref() == "extensions"
+                            && matches!(
+                                located_lexems.get(ind + 1).map(|l| &l.lexem),
+                                Some(Lexem::IntLiteral(_))
+                            ) =>
+                    {
+                        tasks.push(PushExtensions);
+                        tasks.push(ExpectLexem(Lexem::SemiColon));
+                        tasks.push(ParseOptionalOptions);
+                        tasks.push(ParseFieldRanges(FIELD_NUMBERS));
+                        tasks.push(ExpectLexem(Lexem::Id("extensions".into())));
+                        continue;
+                    }
+                    Lexem::Id(id)
+                        if id.deref() == "reserved"
+                            && is_reserved_statement(located_lexems, ind) =>
+                    {
+                        tasks.push(PushMessageEntry);
+                        tasks.push(WrapMessageEntry);
+                        tasks.push(ParseReserved(FIELD_NUMBERS));
+                        continue;
+                    }
                     Lexem::Id(id) if id.deref() == "oneof" => {
                         tasks.push(PushMessageEntry);
                         tasks.push(WrapMessageEntry);
@@ -645,8 +943,10 @@ pub(super) fn parse_package(
                         tasks.push(ParseMessageEntries);
                         tasks.push(ExpectLexem(Lexem::OpenCurly));
                         tasks.push(Push(StackItem::MessageEntriesList(Vec::new())));
+                        tasks.push(Push(StackItem::Options(Vec::new())));
                         tasks.push(ParseId);
                         tasks.push(ExpectLexem(Lexem::Id("oneof".into())));
+                        tasks.push(PushStart);
                         continue;
                     }
                     Lexem::Id(id) if id.deref() == "enum" => {
@@ -668,10 +968,18 @@ pub(super) fn parse_package(
                     Some(StackItem::MessageEntriesList(entries)) => entries,
                     _ => unreachable!(),
                 };
+                let options = match stack.pop() {
+                    Some(StackItem::Options(options)) => options,
+                    _ => unreachable!(),
+                };
                 let one_of_name = match stack.pop() {
                     Some(StackItem::String(name)) => name,
                     _ => unreachable!(),
                 };
+                let start = match stack.pop() {
+                    Some(StackItem::Start(start)) => start,
+                    _ => unreachable!(),
+                };
                 if message_entries.iter().any(|entry| match entry {
                     MessageDeclarationEntry::Field(_) => false,
                     _ => true,
@@ -683,7 +991,12 @@ pub(super) fn parse_package(
                 }
                 let one_of_declaration = OneOfDeclaration {
                     name: one_of_name,
-                    options: message_entries
+                    options,
+                    span: Some(Span::between(
+                        &located_lexems[start],
+                        &located_lexems[ind - 1],
+                    )),
+                    fields: message_entries
                         .iter()
                         .filter_map(|entry| match entry {
                             MessageDeclarationEntry::Field(field_decl) => {
@@ -795,11 +1108,366 @@ pub(super) fn parse_package(
             ParseFieldDeclaration => {
                 tasks.push(PushFieldDeclaration);
                 tasks.push(ExpectLexem(Lexem::SemiColon));
-                tasks.push(ParseOptionalAttributes);
+                tasks.push(ParseOptionalOptions);
                 tasks.push(ParseInt64);
                 tasks.push(ExpectLexem(Lexem::Equal));
                 tasks.push(ParseId);
                 tasks.push(ParseFieldType);
+                tasks.push(PushStart);
+                continue;
+            }
+            ParseServiceDeclaration => {
+                tasks.push(ParseServiceEntries);
+                tasks.push(Push(StackItem::RpcList(Vec::new())));
+                tasks.push(Push(StackItem::Options(Vec::new())));
+                tasks.push(ExpectLexem(Lexem::OpenCurly));
+                tasks.push(ParseId);
+                tasks.push(ExpectLexem(Lexem::Id("service".into())));
+                tasks.push(PushStart);
+                continue;
+            }
+            ParseServiceEntries => {
+                let loc_separator = &located_lexems[ind];
+                let separator = &loc_separator.lexem;
+                match separator {
+                    Lexem::CloseCurly => {
+                        ind += 1;
+                        let rpcs = match stack.pop() {
+                            Some(StackItem::RpcList(rpcs)) => rpcs,
+                            _ => unreachable!(),
+                        };
+                        let options = match stack.pop() {
+                            Some(StackItem::Options(options)) => options,
+                            _ => unreachable!(),
+                        };
+                        let name = match stack.pop() {
+                            Some(StackItem::String(name)) => name,
+                            _ => unreachable!(),
+                        };
+                        let start = match stack.pop() {
+                            Some(StackItem::Start(start)) => start,
+                            _ => unreachable!(),
+                        };
+                        let mut service: ServiceDeclaration = id_gen.create((name, rpcs, options));
+                        service.span = Some(Span::between(
+                            &located_lexems[start],
+                            &located_lexems[ind - 1],
+                        ));
+                        stack.push(service.into());
+                        continue;
+                    }
+                    Lexem::SemiColon => {
+                        ind += 1;
+                        tasks.push(ParseServiceEntries);
+                        continue;
+                    }
+                    Lexem::Id(id) if id.deref() == "option" => {
+                        tasks.push(ParseServiceEntries);
+                        tasks.push(PushDeclarationOption);
+                        tasks.push(ParseOptionStatement);
+                        continue;
+                    }
+                    Lexem::Id(id) if id.deref() == "rpc" => {
+                        tasks.push(ParseServiceEntries);
+                        tasks.push(ParseRpc);
+                        continue;
+                    }
+                    _ => {
+                        return Err(syntax_error(
+                            "Expected rpc, option or closing curly brace",
+                            loc_separator,
+                        ));
+                    }
+                }
+            }
+            ParseRpc => {
+                tasks.push(PushRpc);
+                tasks.push(ParseRpcBody);
+                tasks.push(ExpectLexem(Lexem::CloseParens));
+                tasks.push(ParseRpcType);
+                tasks.push(ExpectLexem(Lexem::OpenParens));
+                tasks.push(ExpectLexem(Lexem::Id("returns".into())));
+                tasks.push(ExpectLexem(Lexem::CloseParens));
+                tasks.push(ParseRpcType);
+                tasks.push(ExpectLexem(Lexem::OpenParens));
+                tasks.push(ParseId);
+                tasks.push(ExpectLexem(Lexem::Id("rpc".into())));
+                tasks.push(PushStart);
+                continue;
+            }
+            ParseRpcType => {
+                assert_enough_length(located_lexems, ind, 2, "Expected rpc type")?;
+                let stream = match (&located_lexems[ind].lexem, &located_lexems[ind + 1].lexem) {
+                    (Lexem::Id(id), Lexem::Id(_)) if id.deref() == "stream" => {
+                        ind += 1;
+                        true
+                    }
+                    _ => false,
+                };
+                tasks.push(WrapRpcType(stream));
+                tasks.push(WrapFieldType);
+                tasks.push(ParseIdPath);
+                tasks.push(PushStart);
+                continue;
+            }
+            WrapRpcType(stream) => {
+                let field_type = match stack.pop() {
+                    Some(StackItem::FieldType(field_type)) => field_type,
+                    _ => unreachable!(),
+                };
+                let start = match stack.pop() {
+                    Some(StackItem::Start(start)) => start,
+                    _ => unreachable!(),
+                };
+                let span = Span::between(&located_lexems[start], &located_lexems[ind - 1]);
+                stack.push(StackItem::RpcType(stream, field_type, span));
+                continue;
+            }
+            ParseRpcBody => {
+                let loc_lexem = &located_lexems[ind];
+                match &loc_lexem.lexem {
+                    Lexem::SemiColon => {
+                        ind += 1;
+                        stack.push(StackItem::Options(Vec::new()));
+                        continue;
+                    }
+                    Lexem::OpenCurly => {
+                        ind += 1;
+                        stack.push(StackItem::Options(Vec::new()));
+                        tasks.push(ParseRpcOptions);
+                        continue;
+                    }
+                    _ => {
+                        return Err(syntax_error("Expected ; or {", loc_lexem));
+                    }
+                }
+            }
+            ParseRpcOptions => {
+                let loc_lexem = &located_lexems[ind];
+                match &loc_lexem.lexem {
+                    Lexem::CloseCurly => {
+                        ind += 1;
+                        continue;
+                    }
+                    Lexem::SemiColon => {
+                        ind += 1;
+                        tasks.push(ParseRpcOptions);
+                        continue;
+                    }
+                    Lexem::Id(id) if id.deref() == "option" => {
+                        tasks.push(ParseRpcOptions);
+                        tasks.push(PushOption);
+                        tasks.push(ParseOptionStatement);
+                        continue;
+                    }
+                    _ => {
+                        return Err(syntax_error(
+                            "Expected option or closing curly brace",
+                            loc_lexem,
+                        ));
+                    }
+                }
+            }
+            PushOption => {
+                let option = match stack.pop() {
+                    Some(StackItem::Option(option)) => option,
+                    _ => unreachable!(),
+                };
+                let mut options = match stack.pop() {
+                    Some(StackItem::Options(options)) => options,
+                    _ => unreachable!(),
+                };
+                options.push(option);
+                stack.push(StackItem::Options(options));
+                continue;
+            }
+            PushRpc => {
+                let options = match stack.pop() {
+                    Some(StackItem::Options(options)) => options,
+                    _ => unreachable!(),
+                };
+                let (server_streaming, output_type, output_span) = match stack.pop() {
+                    Some(StackItem::RpcType(stream, field_type, span)) => {
+                        (stream, field_type, span)
+                    }
+                    _ => unreachable!(),
+                };
+                let (client_streaming, input_type, input_span) = match stack.pop() {
+                    Some(StackItem::RpcType(stream, field_type, span)) => {
+                        (stream, field_type, span)
+                    }
+                    _ => unreachable!(),
+                };
+                let name = match stack.pop() {
+                    Some(StackItem::String(name)) => name,
+                    _ => unreachable!(),
+                };
+                let start = match stack.pop() {
+                    Some(StackItem::Start(start)) => start,
+                    _ => unreachable!(),
+                };
+                let mut rpcs = match stack.pop() {
+                    Some(StackItem::RpcList(rpcs)) => rpcs,
+                    _ => unreachable!(),
+                };
+                rpcs.push(RpcDeclaration {
+                    name,
+                    input_type,
+                    client_streaming,
+                    output_type,
+                    server_streaming,
+                    options,
+                    input_span: Some(input_span),
+                    output_span: Some(output_span),
+                    span: Some(Span::between(
+                        &located_lexems[start],
+                        &located_lexems[ind - 1],
+                    )),
+                });
+                stack.push(StackItem::RpcList(rpcs));
+                continue;
+            }
+            ParseExtendDeclaration => {
+                tasks.push(PushExtend);
+                tasks.push(ExpectLexem(Lexem::CloseCurly));
+                tasks.push(ParseMessageEntries);
+                tasks.push(Push(StackItem::MessageEntriesList(Vec::new())));
+                tasks.push(Push(StackItem::Options(Vec::new())));
+                tasks.push(ExpectLexem(Lexem::OpenCurly));
+                tasks.push(WrapExtendee);
+                tasks.push(WrapFieldType);
+                tasks.push(ParseIdPath);
+                tasks.push(PushStart);
+                tasks.push(ExpectLexem(Lexem::Id("extend".into())));
+                continue;
+            }
+            WrapExtendee => {
+                let field_type = match stack.pop() {
+                    Some(StackItem::FieldType(field_type)) => field_type,
+                    _ => unreachable!(),
+                };
+                let start = match stack.pop() {
+                    Some(StackItem::Start(start)) => start,
+                    _ => unreachable!(),
+                };
+                let span = Span::between(&located_lexems[start], &located_lexems[ind - 1]);
+                stack.push(StackItem::Extendee(field_type, span));
+                continue;
+            }
+            PushExtend => {
+                let message_entries = match stack.pop() {
+                    Some(StackItem::MessageEntriesList(entries)) => entries,
+                    _ => unreachable!(),
+                };
+                let options = match stack.pop() {
+                    Some(StackItem::Options(options)) => options,
+                    _ => unreachable!(),
+                };
+                let (extendee, extendee_span) = match stack.pop() {
+                    Some(StackItem::Extendee(field_type, span)) => (field_type, span),
+                    _ => unreachable!(),
+                };
+                let mut fields = Vec::new();
+                for entry in message_entries {
+                    match entry {
+                        MessageDeclarationEntry::Field(field) => fields.push(field),
+                        _ => {
+                            return Err(syntax_error(
+                                "extend can contain only field declarations",
+                                &located_lexems[ind - 1],
+                            ));
+                        }
+                    }
+                }
+                if !options.is_empty() {
+                    return Err(syntax_error(
+                        "extend cannot contain options",
+                        &located_lexems[ind - 1],
+                    ));
+                }
+                stack.push(
+                    ExtendDeclaration {
+                        extendee,
+                        fields,
+                        extendee_span: Some(extendee_span),
+                    }
+                    .into(),
+                );
+                continue;
+            }
+            ParseFieldRanges(bounds) => {
+                let ranges = parse_field_ranges(located_lexems, &mut ind, bounds)?;
+                stack.push(StackItem::FieldRanges(ranges));
+                continue;
+            }
+            PushStart => {
+                stack.push(StackItem::Start(ind));
+                continue;
+            }
+            ParseReserved(bounds) => {
+                let start = ind;
+                ind += 1;
+                let mut reserved = ReservedDeclaration {
+                    ranges: Vec::new(),
+                    names: Vec::new(),
+                    span: None,
+                };
+                match &located_lexems[ind].lexem {
+                    Lexem::StringLiteral(_) => {
+                        reserved.names = parse_reserved_names(located_lexems, &mut ind)?;
+                    }
+                    _ => {
+                        reserved.ranges = parse_field_ranges(located_lexems, &mut ind, bounds)?;
+                    }
+                }
+                let semi_colon_loc_lexem = &located_lexems[ind];
+                if semi_colon_loc_lexem.lexem != Lexem::SemiColon {
+                    return Err(syntax_error("Expected ;", semi_colon_loc_lexem));
+                }
+                reserved.span = Some(Span::between(
+                    &located_lexems[start],
+                    &located_lexems[ind - 1],
+                ));
+                ind += 1;
+                stack.push(StackItem::Reserved(reserved));
+                continue;
+            }
+            PushEnumReserved => {
+                let reserved = match stack.pop() {
+                    Some(StackItem::Reserved(reserved)) => reserved,
+                    _ => unreachable!(),
+                };
+                let entries = stack.pop().unwrap();
+                let options = stack.pop().unwrap();
+                let mut reserved_list = match stack.pop() {
+                    Some(StackItem::ReservedList(reserved_list)) => reserved_list,
+                    _ => unreachable!(),
+                };
+                reserved_list.push(reserved);
+                stack.push(StackItem::ReservedList(reserved_list));
+                stack.push(options);
+                stack.push(entries);
+                continue;
+            }
+            PushExtensions => {
+                let options = match stack.pop() {
+                    Some(StackItem::OptionalOptions(optional_options)) => optional_options,
+                    _ => unreachable!(),
+                }
+                .unwrap_or_default();
+                let ranges = match stack.pop() {
+                    Some(StackItem::FieldRanges(ranges)) => ranges,
+                    _ => unreachable!(),
+                };
+                let mut entries = match stack.pop() {
+                    Some(StackItem::MessageEntriesList(entries)) => entries,
+                    _ => unreachable!(),
+                };
+                entries.push(MessageDeclarationEntry::Extensions(ExtensionsDeclaration {
+                    ranges,
+                    options,
+                }));
+                stack.push(StackItem::MessageEntriesList(entries));
                 continue;
             }
             ExpectLexem(expected_lexem) => {
@@ -835,24 +1503,9 @@ pub(super) fn parse_package(
                     }
                 }
             }
-            ParseStringLiteral => {
-                assert_enough_length(located_lexems, ind, 1, "Expected identifier")?;
-                let loc_lexem = &located_lexems[ind];
-                let lexem = &loc_lexem.lexem;
-                match lexem {
-                    Lexem::StringLiteral(found_literal) => {
-                        ind += 1;
-                        stack.push(found_literal.clone().into());
-                        continue;
-                    }
-                    _ => {
-                        return Err(syntax_error("expected identifier", loc_lexem));
-                    }
-                }
-            }
         }
     }
-    Ok(())
+    Ok(ind)
 }
 
 fn parse_import_path(s: &str) -> ImportPath {
@@ -866,9 +1519,286 @@ fn parse_import_path(s: &str) -> ImportPath {
     return ImportPath {
         packages,
         file_name,
+        span: None,
     };
 }
 
+fn parse_option_name(
+    located_lexems: &[LocatedLexem],
+    ind: &mut usize,
+) -> Result<OptionName, ProtoError> {
+    let mut parts = Vec::new();
+    loop {
+        let loc_lexem = &located_lexems[*ind];
+        match &loc_lexem.lexem {
+            Lexem::Id(id) => {
+                *ind += 1;
+                parts.push(OptionNamePart::Name(Rc::clone(id)));
+            }
+            Lexem::OpenParens => {
+                *ind += 1;
+                if let Lexem::Dot = located_lexems[*ind].lexem {
+                    *ind += 1;
+                }
+                let path = parse_full_ident(located_lexems, ind)?;
+                let close_loc_lexem = &located_lexems[*ind];
+                if close_loc_lexem.lexem != Lexem::CloseParens {
+                    return Err(syntax_error("Expected )", close_loc_lexem));
+                }
+                *ind += 1;
+                parts.push(OptionNamePart::Extension(path));
+            }
+            _ => {
+                return Err(syntax_error("Expected option name", loc_lexem));
+            }
+        }
+        match located_lexems[*ind].lexem {
+            Lexem::Dot => {
+                *ind += 1;
+            }
+            _ => break,
+        }
+    }
+    Ok(OptionName { parts })
+}
+
+/// `reserved` is a statement only if it is followed by a number or a name,
+/// otherwise it is a name of the field type or of the enum value
+fn is_reserved_statement(located_lexems: &[LocatedLexem], ind: usize) -> bool {
+    matches!(
+        located_lexems.get(ind + 1).map(|l| &l.lexem),
+        Some(Lexem::IntLiteral(_) | Lexem::StringLiteral(_))
+    )
+}
+
+fn parse_reserved_names(
+    located_lexems: &[LocatedLexem],
+    ind: &mut usize,
+) -> Result<Vec<Rc<str>>, ProtoError> {
+    let mut names = Vec::new();
+    loop {
+        let loc_lexem = &located_lexems[*ind];
+        match &loc_lexem.lexem {
+            Lexem::StringLiteral(name) => {
+                *ind += 1;
+                names.push(Rc::clone(name));
+            }
+            _ => {
+                return Err(syntax_error("Expected reserved name", loc_lexem));
+            }
+        }
+        match located_lexems[*ind].lexem {
+            Lexem::Comma => {
+                *ind += 1;
+            }
+            _ => break,
+        }
+    }
+    Ok(names)
+}
+
+fn parse_field_ranges(
+    located_lexems: &[LocatedLexem],
+    ind: &mut usize,
+    bounds: FieldRange,
+) -> Result<Vec<FieldRange>, ProtoError> {
+    let mut ranges = Vec::new();
+    loop {
+        let start_loc_lexem = &located_lexems[*ind];
+        let start = match start_loc_lexem.lexem {
+            Lexem::IntLiteral(start) => start,
+            _ => return Err(syntax_error("Expected field number", start_loc_lexem)),
+        };
+        *ind += 1;
+        let end = match &located_lexems[*ind].lexem {
+            Lexem::Id(id) if id.deref() == "to" => {
+                *ind += 1;
+                let end_loc_lexem = &located_lexems[*ind];
+                *ind += 1;
+                match &end_loc_lexem.lexem {
+                    Lexem::IntLiteral(end) => *end,
+                    Lexem::Id(id) if id.deref() == "max" => bounds.end,
+                    _ => return Err(syntax_error("Expected field number or max", end_loc_lexem)),
+                }
+            }
+            _ => start,
+        };
+        if start < bounds.start || start > end || end > bounds.end {
+            return Err(syntax_error(
+                format!("Invalid field range {} to {}", start, end),
+                start_loc_lexem,
+            ));
+        }
+        ranges.push(FieldRange { start, end });
+        match located_lexems[*ind].lexem {
+            Lexem::Comma => {
+                *ind += 1;
+            }
+            _ => break,
+        }
+    }
+    Ok(ranges)
+}
+
+fn parse_full_ident(
+    located_lexems: &[LocatedLexem],
+    ind: &mut usize,
+) -> Result<Vec<Rc<str>>, ProtoError> {
+    let mut path = Vec::new();
+    loop {
+        let loc_lexem = &located_lexems[*ind];
+        match &loc_lexem.lexem {
+            Lexem::Id(id) => {
+                *ind += 1;
+                path.push(Rc::clone(id));
+            }
+            _ => {
+                return Err(syntax_error("Expected identifier", loc_lexem));
+            }
+        }
+        match located_lexems[*ind].lexem {
+            Lexem::Dot => {
+                *ind += 1;
+            }
+            _ => break,
+        }
+    }
+    Ok(path)
+}
+
+fn parse_constant(
+    located_lexems: &[LocatedLexem],
+    ind: &mut usize,
+) -> Result<Constant, ProtoError> {
+    let loc_lexem = &located_lexems[*ind];
+    match &loc_lexem.lexem {
+        Lexem::StringLiteral(s) => {
+            *ind += 1;
+            let mut value = s.to_string();
+            while let Lexem::StringLiteral(next) = &located_lexems[*ind].lexem {
+                *ind += 1;
+                value.push_str(next);
+            }
+            Ok(Constant::String(value.into()))
+        }
+        Lexem::IntLiteral(i) => {
+            *ind += 1;
+            Ok(Constant::Int(*i))
+        }
+        Lexem::UintLiteral(i) => {
+            *ind += 1;
+            Ok(Constant::Uint(*i))
+        }
+        Lexem::FloatLiteral(f) => {
+            *ind += 1;
+            Ok(Constant::Float(*f))
+        }
+        Lexem::Id(id) => match id.deref() {
+            "true" => {
+                *ind += 1;
+                Ok(Constant::Bool(true))
+            }
+            "false" => {
+                *ind += 1;
+                Ok(Constant::Bool(false))
+            }
+            "inf" => {
+                *ind += 1;
+                Ok(Constant::Float(f64::INFINITY))
+            }
+            "nan" => {
+                *ind += 1;
+                Ok(Constant::Float(f64::NAN))
+            }
+            _ => {
+                let path = parse_full_ident(located_lexems, ind)?;
+                Ok(Constant::Identifier(path.join(".").into()))
+            }
+        },
+        Lexem::OpenCurly => {
+            *ind += 1;
+            parse_aggregate(located_lexems, ind, Lexem::CloseCurly)
+        }
+        _ => Err(syntax_error("Expected constant", loc_lexem)),
+    }
+}
+
+/// Parses the body of a text format message literal until `close` lexem
+fn parse_aggregate(
+    located_lexems: &[LocatedLexem],
+    ind: &mut usize,
+    close: Lexem,
+) -> Result<Constant, ProtoError> {
+    let mut entries = Vec::new();
+    loop {
+        let loc_lexem = &located_lexems[*ind];
+        if loc_lexem.lexem == close {
+            *ind += 1;
+            break;
+        }
+        let key: Rc<str> = match &loc_lexem.lexem {
+            Lexem::Id(id) => {
+                *ind += 1;
+                Rc::clone(id)
+            }
+            Lexem::OpenBracket => {
+                *ind += 1;
+                let path = parse_full_ident(located_lexems, ind)?;
+                let close_loc_lexem = &located_lexems[*ind];
+                if close_loc_lexem.lexem != Lexem::CloseBracket {
+                    return Err(syntax_error("Expected ]", close_loc_lexem));
+                }
+                *ind += 1;
+                format!("[{}]", path.join(".")).into()
+            }
+            _ => {
+                return Err(syntax_error("Expected field name", loc_lexem));
+            }
+        };
+        if let Lexem::Colon = located_lexems[*ind].lexem {
+            *ind += 1;
+        }
+        let value = parse_aggregate_value(located_lexems, ind)?;
+        entries.push((key, value));
+        if let Lexem::Comma | Lexem::SemiColon = located_lexems[*ind].lexem {
+            *ind += 1;
+        }
+    }
+    Ok(Constant::Aggregate(entries))
+}
+
+fn parse_aggregate_value(
+    located_lexems: &[LocatedLexem],
+    ind: &mut usize,
+) -> Result<Constant, ProtoError> {
+    match located_lexems[*ind].lexem {
+        Lexem::Less => {
+            *ind += 1;
+            parse_aggregate(located_lexems, ind, Lexem::Greater)
+        }
+        Lexem::OpenBracket => {
+            *ind += 1;
+            let mut values = Vec::new();
+            loop {
+                if let Lexem::CloseBracket = located_lexems[*ind].lexem {
+                    *ind += 1;
+                    break;
+                }
+                if !values.is_empty() {
+                    let comma_loc_lexem = &located_lexems[*ind];
+                    if comma_loc_lexem.lexem != Lexem::Comma {
+                        return Err(syntax_error("Expected comma or ]", comma_loc_lexem));
+                    }
+                    *ind += 1;
+                }
+                values.push(parse_aggregate_value(located_lexems, ind)?);
+            }
+            Ok(Constant::List(values))
+        }
+        _ => parse_constant(located_lexems, ind),
+    }
+}
+
 #[cfg(test)]
 mod test {
     #[test]
@@ -879,10 +1809,302 @@ mod test {
             res,
             super::ImportPath {
                 packages: vec!["google".into(), "protobuf".into()],
-                file_name: "timestamp.proto".into()
+                file_name: "timestamp.proto".into(),
+                span: None,
             }
         );
     }
+
+    fn parse(content: &str) -> super::ProtoFile {
+        let path = std::path::Path::new("test.proto");
+        let lexems = super::super::lexems::read_lexems(path, content).unwrap();
+        let mut res = super::ProtoFile {
+            version: super::super::package::ProtoVersion::Proto3,
+            declarations: vec![],
+            imports: vec![],
+            path: vec![],
+            name: "test.proto".into(),
+            options: vec![],
+        };
+        let mut id_gen = super::IdGenerator::new();
+        super::parse_package(&mut id_gen, &lexems, &mut res).unwrap();
+        res
+    }
+
+    #[test]
+    fn parses_options() {
+        use super::super::options::{Constant, OptionNamePart};
+        use super::Declaration;
+
+        let file = parse(
+            r#"
+            syntax = "proto3";
+            option java_package = "com.example";
+            option optimize_for = SPEED;
+            option (my.ext).field = -1.5e3;
+            message Foo {
+                option deprecated = true;
+                repeated int32 ids = 1 [packed = false, json_name = "IDS"];
+                oneof kind {
+                    option (oneof_opt) = { a: 1 b: [2, 3] };
+                    string name = 2;
+                }
+            }
+            enum Color {
+                option allow_alias = true;
+                RED = 0 [deprecated = true];
+                CRIMSON = 0x0;
+            }
+            "#,
+        );
+        let options = file
+            .options
+            .iter()
+            .map(|o| o.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            options,
+            vec![
+                "java_package = \"com.example\"",
+                "optimize_for = SPEED",
+                "(my.ext).field = -1500.0",
+            ]
+        );
+        assert_eq!(
+            file.options[2].name.parts[0],
+            OptionNamePart::Extension(vec!["my".into(), "ext".into()])
+        );
+        let message = match &file.declarations[0] {
+            Declaration::Message(m) => m,
+            _ => panic!("expected message"),
+        };
+        assert_eq!(message.options[0].value, Constant::Bool(true));
+        assert_eq!(
+            message.entries[0].to_string(),
+            "repeated int32 ids = 1 [packed = false, json_name = \"IDS\"];"
+        );
+        match &message.entries[1] {
+            super::MessageDeclarationEntry::OneOf(one_of) => assert_eq!(
+                one_of.options[0].to_string(),
+                "(oneof_opt) = { a: 1, b: [2, 3] }"
+            ),
+            _ => panic!("expected oneof"),
+        }
+        let enum_declaration = match &file.declarations[1] {
+            Declaration::Enum(e) => e,
+            _ => panic!("expected enum"),
+        };
+        assert_eq!(
+            enum_declaration.options[0].to_string(),
+            "allow_alias = true"
+        );
+        assert_eq!(enum_declaration.entries[0].options.len(), 1);
+        assert_eq!(enum_declaration.entries[1].value, 0);
+    }
+
+    #[test]
+    fn parses_int_constants_at_bounds() {
+        use super::super::options::Constant;
+        use super::Declaration;
+
+        let file = parse(
+            r#"
+            syntax = "proto2";
+            message Foo {
+                optional int64 min = 1 [default = -9223372036854775808];
+                optional uint64 max = 2 [default = 18446744073709551615];
+                optional int64 hex = 3 [default = -0x8000000000000000];
+            }
+            "#,
+        );
+        let message = match &file.declarations[0] {
+            Declaration::Message(m) => m,
+            _ => panic!("expected message"),
+        };
+        let defaults = message
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                super::MessageDeclarationEntry::Field(f) => f.options[0].value.clone(),
+                _ => panic!("expected field"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            defaults,
+            vec![
+                Constant::Int(i64::MIN),
+                Constant::Uint(u64::MAX),
+                Constant::Int(i64::MIN),
+            ]
+        );
+        assert_eq!(defaults[1].to_string(), "18446744073709551615");
+
+        let path = std::path::Path::new("test.proto");
+        for literal in ["-9223372036854775809", "18446744073709551616"] {
+            let err = super::super::lexems::read_lexems(path, literal).unwrap_err();
+            assert!(err.to_string().contains(literal), "{}", err);
+        }
+    }
+
+    #[test]
+    fn parses_services() {
+        use super::Declaration;
+
+        let file = parse(
+            r#"
+            syntax = "proto3";
+            service Greeter {
+                option deprecated = true;
+                rpc SayHello(HelloRequest) returns (HelloReply);
+                rpc Chat(stream pkg.Message) returns (stream) {
+                    option idempotency_level = NO_SIDE_EFFECTS;
+                };
+            }
+            "#,
+        );
+        let service = match &file.declarations[0] {
+            Declaration::Service(s) => s,
+            _ => panic!("expected service"),
+        };
+        assert_eq!(service.name.as_ref(), "Greeter");
+        assert_eq!(service.options.len(), 1);
+        assert_eq!(
+            service.rpcs[0].to_string(),
+            "rpc SayHello(HelloRequest) returns (HelloReply);"
+        );
+        let chat = &service.rpcs[1];
+        assert!(chat.client_streaming);
+        assert!(!chat.server_streaming);
+        assert_eq!(chat.input_type.to_string(), "pkg.Message");
+        assert_eq!(chat.output_type.to_string(), "stream");
+        assert_eq!(
+            chat.options[0].to_string(),
+            "idempotency_level = NO_SIDE_EFFECTS"
+        );
+    }
+
+    #[test]
+    fn parses_extensions() {
+        use super::Declaration;
+
+        let file = parse(
+            r#"
+            syntax = "proto2";
+            message Base {
+                optional string name = 1;
+                extensions 5, 100 to 199, 1000 to max;
+                extend Other {
+                    optional Base base = 10;
+                }
+            }
+            extend Base {
+                optional int32 weight = 100 [deprecated = true];
+                repeated string tags = 1000;
+            }
+            "#,
+        );
+        let message = match &file.declarations[0] {
+            Declaration::Message(m) => m,
+            _ => panic!("expected message"),
+        };
+        assert_eq!(
+            message.entries[1].to_string(),
+            "extensions 5, 100 to 199, 1000 to max;"
+        );
+        match &message.entries[2] {
+            super::MessageDeclarationEntry::Declaration(Declaration::Extend(extend)) => {
+                assert_eq!(extend.extendee.to_string(), "Other");
+                assert_eq!(extend.fields[0].tag, 10);
+            }
+            _ => panic!("expected extend"),
+        }
+        let extend = match &file.declarations[1] {
+            Declaration::Extend(e) => e,
+            _ => panic!("expected extend"),
+        };
+        assert_eq!(
+            extend.to_string(),
+            "extend Base {\n  optional int32 weight = 100 [deprecated = true];\n  repeated string tags = 1000;\n}\n"
+        );
+    }
+
+    #[test]
+    fn parses_reserved() {
+        use super::Declaration;
+
+        let file = parse(
+            r#"
+            syntax = "proto3";
+            message Foo {
+                reserved 2, 15, 9 to 11, 100 to max;
+                reserved "foo", "bar";
+                string name = 1;
+            }
+            enum Color {
+                reserved -3 to -1;
+                reserved "BLUE";
+                RED = 0;
+            }
+            "#,
+        );
+        let message = match &file.declarations[0] {
+            Declaration::Message(m) => m,
+            _ => panic!("expected message"),
+        };
+        assert_eq!(
+            message.entries[0].to_string(),
+            "reserved 2, 15, 9 to 11, 100 to max;"
+        );
+        assert_eq!(message.entries[1].to_string(), "reserved \"foo\", \"bar\";");
+        match &message.entries[2] {
+            super::MessageDeclarationEntry::Field(field) => {
+                let span = field.span.as_ref().unwrap();
+                assert_eq!((span.start_line, span.start_column), (6, 17));
+                assert_eq!((span.end_line, span.end_column), (6, 31));
+            }
+            _ => panic!("expected field"),
+        }
+        let enum_declaration = match &file.declarations[1] {
+            Declaration::Enum(e) => e,
+            _ => panic!("expected enum"),
+        };
+        assert!(enum_declaration.reserved[0].contains_number(-2));
+        assert!(enum_declaration.reserved[1].contains_name("BLUE"));
+        assert_eq!(enum_declaration.entries.len(), 1);
+    }
+
+    #[test]
+    fn recovers_after_syntax_errors() {
+        let path = std::path::Path::new("test.proto");
+        let content = r#"
+            messag Foo { int32 a = 1; }
+            message Bar { int32 b = ; }
+            message Baz { int32 c = 1; }
+            enum Color { RED = 0 }
+        "#;
+        let lexems = super::super::lexems::read_lexems(path, content).unwrap();
+        let mut res = super::ProtoFile {
+            version: super::super::package::ProtoVersion::Proto3,
+            declarations: vec![],
+            imports: vec![],
+            path: vec![],
+            name: "test.proto".into(),
+            options: vec![],
+        };
+        let mut id_gen = super::IdGenerator::new();
+        let errors = super::parse_package(&mut id_gen, &lexems, &mut res)
+            .unwrap_err()
+            .into_errors();
+        let lines = errors
+            .iter()
+            .map(|err| match err {
+                super::ProtoError::SyntaxError { span, .. } => span.start_line,
+                _ => panic!("expected syntax error"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![2, 3, 5]);
+        assert_eq!(res.declarations.len(), 1);
+    }
 }
 
 fn print_stack(stack: &[StackItem]) {
@@ -901,9 +2123,22 @@ fn print_stack(stack: &[StackItem]) {
                 StackItem::MessageEntry(_) => "MessageEntry",
                 StackItem::Int64(_) => "i64",
                 StackItem::Message(_) => "message",
-                StackItem::OptionalAttributes(_) => "attributes[]?",
+                StackItem::OptionalOptions(_) => "options[]?",
+                StackItem::Options(_) => "options[]",
+                StackItem::Option(_) => "option",
+                StackItem::OptionName(_) => "option name",
+                StackItem::Constant(_) => "constant",
                 StackItem::Enum(_) => "enum",
                 StackItem::OneOf(_) => "oneof",
+                StackItem::RpcType(_, _, _) => "rpc type",
+                StackItem::Extendee(_, _) => "extendee",
+                StackItem::RpcList(_) => "rpc[]",
+                StackItem::Service(_) => "service",
+                StackItem::FieldRanges(_) => "range[]",
+                StackItem::Extend(_) => "extend",
+                StackItem::Start(_) => "start",
+                StackItem::Reserved(_) => "reserved",
+                StackItem::ReservedList(_) => "reserved[]",
             })
             .collect::<Vec<_>>()
             .join("\n")
@@ -0,0 +1,65 @@
+//! Fixtures shared by the tests of the compiler
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    GenerationOptions, MemoryFileSystem, ProtoError, RootScope, build_root_scope, compile,
+};
+
+/// Resolves the files given by their paths and contents, all of them are entry files
+pub(crate) fn build_files(files: &[(&str, &str)]) -> RootScope {
+    try_build_files(files).unwrap()
+}
+
+/// Same as [`build_files`], but returns errors of invalid files
+pub(crate) fn try_build_files(files: &[(&str, &str)]) -> Result<RootScope, ProtoError> {
+    let mut file_system = MemoryFileSystem::new();
+    for (path, content) in files {
+        file_system.insert(*path, *content);
+    }
+    let entry_files = files
+        .iter()
+        .map(|(path, _)| PathBuf::from(path))
+        .collect::<Vec<_>>();
+    build_root_scope(&file_system, &entry_files, &[], false)
+}
+
+/// Generated TypeScript files by their paths
+pub(crate) fn compile_files(
+    files: &[(&str, &str)],
+    options: &GenerationOptions,
+) -> BTreeMap<String, String> {
+    compile(&build_files(files), options)
+        .unwrap()
+        .into_iter()
+        .map(|(path, content)| (path.to_string_lossy().into_owned(), content))
+        .collect()
+}
+
+/// Empty directory of a single test, unique to the process so that concurrent runs
+/// do not share it. The directory is removed when dropped
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    pub(crate) fn new(test_name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("protos_ts_{}_{}", test_name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,210 @@
+use std::path::{Path, PathBuf};
+
+use js_sys::{Array, JSON, Object, Reflect};
+use wasm_bindgen::{JsCast, prelude::*};
+
+use crate::{
+    CodegenOptions, GenerationOptions, Int64Mode, MemoryFileSystem, ProtoError, build_root_scope,
+    compile, proto::diagnostic::render_json,
+};
+
+/// Compiles schemas given as an object of file contents by their paths into an object
+/// of TypeScript files by their paths.
+/// Files under `options.includePaths` are compiled only if they are imported
+/// and `options.includeImports` is set, all other files are compiled.
+/// `options.services` disables generation of service clients and handlers if it is `false`.
+/// `options.int64` is the representation of 64-bit integers: `long`, `bigint`, `string`
+/// or `number`.
+/// Fails with an `Error` whose `diagnostics` property holds the errors
+/// in the format of `--error-format json`
+#[wasm_bindgen(js_name = compile)]
+pub fn compile_files(files: &Object, options: Option<Object>) -> Result<Object, JsValue> {
+    let options = options.unwrap_or_default();
+    let include_paths = read_strings(&options, "includePaths")?
+        .into_iter()
+        .map(PathBuf::from)
+        .collect::<Vec<_>>();
+    let include_imports = read_bool(&options, "includeImports")?.unwrap_or(false);
+    let services = read_bool(&options, "services")?;
+    let int64 = read_int64(&options)?;
+
+    let mut sources = Vec::new();
+    for entry in Object::entries(files).iter() {
+        let entry: Array = entry.unchecked_into();
+        let path = entry.get(0).as_string().unwrap_or_default();
+        let content = entry
+            .get(1)
+            .as_string()
+            .ok_or_else(|| type_error(&format!("Content of {} is not a string", path)))?;
+        sources.push((PathBuf::from(path), content));
+    }
+
+    let default_codegen = CodegenOptions::default();
+    let generation = GenerationOptions {
+        codegen: CodegenOptions {
+            services: services.unwrap_or(default_codegen.services),
+            int64: int64.unwrap_or(default_codegen.int64),
+            ..default_codegen
+        },
+        ..GenerationOptions::default()
+    };
+    let output = compile_sources(sources, &include_paths, include_imports, &generation)
+        .map_err(to_js_error)?;
+
+    let res = Object::new();
+    for (path, content) in output {
+        Reflect::set(&res, &path.into(), &content.into())?;
+    }
+    Ok(res)
+}
+
+/// Compiles the sources by their paths into TypeScript files by their paths,
+/// sources under `include_paths` are used only if they are imported
+fn compile_sources(
+    sources: Vec<(PathBuf, String)>,
+    include_paths: &[PathBuf],
+    include_imports: bool,
+    generation: &GenerationOptions,
+) -> Result<Vec<(String, String)>, ProtoError> {
+    let mut file_system = MemoryFileSystem::new();
+    let mut entry_files = Vec::new();
+    for (path, content) in sources {
+        if !include_paths.iter().any(|p| path.starts_with(p)) {
+            entry_files.push(path.clone());
+        }
+        file_system.insert(path, content);
+    }
+    let root = build_root_scope(&file_system, &entry_files, include_paths, include_imports)?;
+    let output = compile(&root, generation)?;
+    Ok(output
+        .into_iter()
+        .map(|(path, content)| (to_js_path(&path), content))
+        .collect())
+}
+
+fn read_strings(options: &Object, key: &str) -> Result<Vec<String>, JsValue> {
+    let value = Reflect::get(options, &key.into())?;
+    if value.is_undefined() {
+        return Ok(Vec::new());
+    }
+    let invalid = || type_error(&format!("options.{} must be an array of strings", key));
+    if !Array::is_array(&value) {
+        return Err(invalid());
+    }
+    value
+        .unchecked_into::<Array>()
+        .iter()
+        .map(|item| item.as_string().ok_or_else(invalid))
+        .collect()
+}
+
+fn read_bool(options: &Object, key: &str) -> Result<Option<bool>, JsValue> {
+    let value = Reflect::get(options, &key.into())?;
+    if value.is_undefined() {
+        return Ok(None);
+    }
+    value
+        .as_bool()
+        .map(Some)
+        .ok_or_else(|| type_error(&format!("options.{} must be a boolean", key)))
+}
+
+fn read_int64(options: &Object) -> Result<Option<Int64Mode>, JsValue> {
+    let value = Reflect::get(options, &"int64".into())?;
+    if value.is_undefined() {
+        return Ok(None);
+    }
+    value
+        .as_string()
+        .and_then(|name| Int64Mode::from_name(&name))
+        .map(Some)
+        .ok_or_else(|| {
+            type_error("options.int64 must be one of \"long\", \"bigint\", \"string\", \"number\"")
+        })
+}
+
+fn type_error(message: &str) -> JsValue {
+    js_sys::TypeError::new(message).into()
+}
+
+fn to_js_error(error: ProtoError) -> JsValue {
+    let js_error = js_sys::Error::new(&error.to_string());
+    let diagnostics = Array::new();
+    for error in error.into_errors() {
+        diagnostics.push(&JSON::parse(&render_json(&error)).unwrap_or(JsValue::NULL));
+    }
+    // Setting a property of a new object cannot fail
+    let _ = Reflect::set(&js_error, &"diagnostics".into(), &diagnostics);
+    js_error.into()
+}
+
+/// Paths of the output always use `/`, regardless of the platform
+fn to_js_path(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod test_wasm {
+    use std::path::PathBuf;
+
+    use crate::{CodegenOptions, GenerationOptions, Int64Mode};
+
+    use super::compile_sources;
+
+    fn sources() -> Vec<(PathBuf, String)> {
+        vec![
+            (
+                PathBuf::from("app/user.proto"),
+                "syntax = \"proto3\";\npackage app;\nimport \"lib/id.proto\";\nmessage User { lib.Id id = 1; int64 visits = 2; }\n".into(),
+            ),
+            (
+                PathBuf::from("vendor/lib/id.proto"),
+                "syntax = \"proto3\";\npackage lib;\nmessage Id { string value = 1; }\n".into(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn compiles_sources_by_paths() {
+        let include_paths = [PathBuf::from("vendor")];
+        let output = compile_sources(
+            sources(),
+            &include_paths,
+            false,
+            &GenerationOptions::default(),
+        )
+        .unwrap();
+        let paths = output
+            .iter()
+            .map(|(path, _)| path.as_str())
+            .collect::<Vec<_>>();
+        assert!(paths.contains(&"app/user/User/types.ts"));
+        assert!(!paths.iter().any(|path| path.starts_with("lib/")));
+
+        let generation = GenerationOptions {
+            codegen: CodegenOptions {
+                int64: Int64Mode::String,
+                ..CodegenOptions::default()
+            },
+            ..GenerationOptions::default()
+        };
+        let output = compile_sources(sources(), &include_paths, true, &generation).unwrap();
+        let (_, types) = output
+            .iter()
+            .find(|(path, _)| path == "app/user/User/types.ts")
+            .unwrap();
+        assert!(types.contains("visits: string"));
+        assert!(output.iter().any(|(path, _)| path == "lib/id/Id/types.ts"));
+
+        // Without include paths all sources are compiled
+        let output = compile_sources(sources(), &[], false, &generation).unwrap();
+        assert!(output.iter().any(|(path, _)| path == "lib/id/Id/types.ts"));
+
+        let invalid = vec![(PathBuf::from("app/user.proto"), "message User {".into())];
+        let err = compile_sources(invalid, &[], false, &generation).unwrap_err();
+        assert!(!err.into_errors().is_empty());
+    }
+}
@@ -0,0 +1,132 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    thread::sleep,
+    time::{Duration, SystemTime},
+};
+
+use crate::proto::{file_cache::ProtoFileCache, folder::read_proto_inputs};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Modification time and size of the watched files, `None` if the file was removed
+type Snapshot = BTreeMap<PathBuf, Option<(Option<SystemTime>, u64)>>;
+
+/// Compiles and then polls the files for changes, compiling again after each change.
+/// Watched files are the files matched by `src` and all files read by the last compilation
+pub(crate) fn watch(
+    src: &[PathBuf],
+    include_paths: &[PathBuf],
+    cwd: &Path,
+    cache: &mut ProtoFileCache,
+    mut compile: impl FnMut(&mut ProtoFileCache),
+) -> ! {
+    loop {
+        compile(cache);
+        let watched = snapshot(src, include_paths, cwd, cache);
+        eprintln!("Watching {} files for changes", watched.len());
+        while snapshot(src, include_paths, cwd, cache) == watched {
+            sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+fn snapshot(
+    src: &[PathBuf],
+    include_paths: &[PathBuf],
+    cwd: &Path,
+    cache: &ProtoFileCache,
+) -> Snapshot {
+    // Inputs are expanded again, so that added files are compiled too
+    let entry_files: Vec<PathBuf> = read_proto_inputs(src, include_paths, cwd)
+        .map(|folders| folders.into_iter().flat_map(|f| f.files).collect())
+        .unwrap_or_default();
+    entry_files
+        .into_iter()
+        .chain(cache.paths().cloned())
+        .map(|path| {
+            let metadata = std::fs::metadata(&path)
+                .ok()
+                .map(|m| (m.modified().ok(), m.len()));
+            (path, metadata)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test_watch {
+    use std::{
+        collections::BTreeMap,
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    use crate::{
+        GenerationOptions, OsFileSystem, compile,
+        proto::{file_cache::ProtoFileCache, folder::read_proto_inputs, package::read_root_scope},
+        test_utils::TempDir,
+    };
+
+    use super::snapshot;
+
+    /// One compilation of the watch loop, as done by the command line interface
+    fn compile_inputs(
+        src: &[PathBuf],
+        include_paths: &[PathBuf],
+        cache: &mut ProtoFileCache,
+    ) -> BTreeMap<PathBuf, String> {
+        let folders = read_proto_inputs(src, include_paths, Path::new("")).unwrap();
+        let root = read_root_scope(&OsFileSystem, &folders, include_paths, true, cache);
+        cache.finish_compilation();
+        compile(&root.unwrap(), &GenerationOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn rebuilds_after_changes_of_watched_files() {
+        let temp_dir = TempDir::new("watch_rebuilds_after_changes");
+        let dir = temp_dir.path();
+        let (proto_dir, include_dir) = (dir.join("proto"), dir.join("include"));
+        fs::create_dir_all(&proto_dir).unwrap();
+        fs::create_dir_all(include_dir.join("shared")).unwrap();
+        fs::write(
+            proto_dir.join("app.proto"),
+            "syntax = \"proto3\";\npackage app;\nimport \"shared/id.proto\";\nmessage User { shared.Id id = 1; }\n",
+        )
+        .unwrap();
+        fs::write(
+            include_dir.join("shared/id.proto"),
+            "syntax = \"proto3\";\npackage shared;\nmessage Id { string value = 1; }\n",
+        )
+        .unwrap();
+        let (src, include_paths) = (vec![proto_dir.clone()], vec![include_dir.clone()]);
+        let cwd = Path::new("");
+        let mut cache = ProtoFileCache::new();
+
+        let output = compile_inputs(&src, &include_paths, &mut cache);
+        assert!(output.contains_key(Path::new("shared/id/Id/types.ts")));
+        let watched = snapshot(&src, &include_paths, cwd, &cache);
+        assert!(watched.contains_key(&include_dir.join("shared/id.proto")));
+        assert_eq!(snapshot(&src, &include_paths, cwd, &cache), watched);
+
+        // Imported files are watched too
+        fs::write(
+            include_dir.join("shared/id.proto"),
+            "syntax = \"proto3\";\npackage shared;\nmessage Id { string value = 1; }\nmessage Name { string value = 1; }\n",
+        )
+        .unwrap();
+        assert_ne!(snapshot(&src, &include_paths, cwd, &cache), watched);
+        let output = compile_inputs(&src, &include_paths, &mut cache);
+        assert!(output.contains_key(Path::new("shared/id/Name/types.ts")));
+
+        // Files added to the inputs are compiled by the next compilation
+        let watched = snapshot(&src, &include_paths, cwd, &cache);
+        fs::write(
+            proto_dir.join("admin.proto"),
+            "syntax = \"proto3\";\npackage admin;\nmessage Admin { string name = 1; }\n",
+        )
+        .unwrap();
+        assert_ne!(snapshot(&src, &include_paths, cwd, &cache), watched);
+        let output = compile_inputs(&src, &include_paths, &mut cache);
+        assert!(output.contains_key(Path::new("admin/admin/Admin/types.ts")));
+    }
+}